@@ -1,3 +1,4 @@
+use std::fmt;
 use std::io::{self, IsTerminal, Write};
 use nu_ansi_term::Style;
 use crate::BrainfuckReaderError;
@@ -130,3 +131,108 @@ pub fn rat_to_nu(c: ratatui::style::Color) -> nu_ansi_term::Color {
     }
 }
 
+
+/// A key such as `ctrl+d`, independent of the terminal library: the REPL's line editor and the
+/// IDE are built on different crossterm releases, so each converts it to its own key types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySpec {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub key: KeyName,
+}
+
+/// The key of a [`KeySpec`], without modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyName {
+    Char(char),
+    F(u8),
+    Enter,
+    Tab,
+    Esc,
+    Backspace,
+    Delete,
+    Insert,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+/// Parse a key like `ctrl+d`, `alt+up`, `shift+enter`, `f5` or `ctrl++`.
+pub fn parse_key_spec(spec: &str) -> Result<KeySpec, String> {
+    let lower = spec.to_ascii_lowercase();
+    let (modifier_part, key) = match lower.strip_suffix("++") {
+        Some(rest) => (rest, "+"),
+        None => match lower.rsplit_once('+') {
+            Some((mods, key)) => (mods, key),
+            None => ("", lower.as_str()),
+        },
+    };
+
+    let mut parsed = KeySpec { ctrl: false, alt: false, shift: false, key: KeyName::Enter };
+    for name in modifier_part.split('+').filter(|m| !m.is_empty()) {
+        match name {
+            "ctrl" | "control" => parsed.ctrl = true,
+            "alt" | "meta" => parsed.alt = true,
+            "shift" => parsed.shift = true,
+            _ => return Err(format!("unknown modifier '{name}' in '{spec}'")),
+        }
+    }
+
+    parsed.key = match key {
+        "enter" | "return" => KeyName::Enter,
+        "tab" => KeyName::Tab,
+        "esc" | "escape" => KeyName::Esc,
+        "space" => KeyName::Char(' '),
+        "backspace" => KeyName::Backspace,
+        "delete" | "del" => KeyName::Delete,
+        "insert" => KeyName::Insert,
+        "up" => KeyName::Up,
+        "down" => KeyName::Down,
+        "left" => KeyName::Left,
+        "right" => KeyName::Right,
+        "home" => KeyName::Home,
+        "end" => KeyName::End,
+        "pageup" => KeyName::PageUp,
+        "pagedown" => KeyName::PageDown,
+        _ => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyName::Char(c),
+                (Some('f'), Some(_)) => match key[1..].parse::<u8>() {
+                    Ok(n @ 1..=24) => KeyName::F(n),
+                    _ => return Err(format!("unknown key '{key}' in '{spec}'")),
+                },
+                _ => return Err(format!("unknown key '{key}' in '{spec}'")),
+            }
+        }
+    };
+    Ok(parsed)
+}
+
+/// Parse a comma-separated list of keys; an empty list is valid.
+pub fn parse_key_list(keys: &str) -> Result<Vec<KeySpec>, String> {
+    keys.split(',').map(str::trim).filter(|key| !key.is_empty()).map(parse_key_spec).collect()
+}
+
+/// Written the way help texts show keys, e.g. `Ctrl+Shift+P` or `F5`.
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [(self.ctrl, "Ctrl+"), (self.alt, "Alt+"), (self.shift, "Shift+")] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        match self.key {
+            KeyName::Char(' ') => f.write_str("Space"),
+            KeyName::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyName::F(n) => write!(f, "F{n}"),
+            other => write!(f, "{other:?}"),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use clap::Args;
+use std::fs;
+use std::io::{self, Read, Write};
+
+use crate::{BrainfuckFormatter, FormatOptions};
+use crate::cli_util::print_reader_error;
+
+#[derive(Args, Debug)]
+#[command(disable_help_flag = true)]
+pub struct FmtArgs {
+    /// Read Brainfuck code from PATH instead of positional "<code>" or STDIN
+    #[arg(short = 'f', long = "file")]
+    pub file: Option<String>,
+
+    /// Exit with status 1 if the code is not already formatted; print nothing else
+    #[arg(long = "check")]
+    pub check: bool,
+
+    /// Rewrite --file in place instead of printing to stdout
+    #[arg(short = 'w', long = "write")]
+    pub write: bool,
+
+    /// Maximum line width (default 80)
+    #[arg(long = "width", value_name = "N")]
+    pub width: Option<usize>,
+
+    /// Spaces of indentation per loop level (default 2)
+    #[arg(long = "indent", value_name = "N")]
+    pub indent: Option<usize>,
+
+    /// Concatenated Brainfuck code parts
+    #[arg(value_name = "code", trailing_var_arg = true)]
+    pub code: Vec<String>,
+
+    /// Show this help
+    #[arg(short = 'h', long = "help", action = clap::ArgAction::SetTrue)]
+    pub help: bool,
+}
+
+pub fn run(program: &str, args: FmtArgs) -> i32 {
+    if args.help {
+        usage_and_exit(program, 0);
+    }
+
+    let FmtArgs {
+        file,
+        check,
+        write,
+        width,
+        indent,
+        code,
+        ..
+    } = args;
+
+    if file.is_some() && !code.is_empty() {
+        eprintln!("{program}: cannot use positional code together with --file");
+        usage_and_exit(program, 2);
+    }
+
+    if write && file.is_none() {
+        eprintln!("{program}: --write requires --file");
+        usage_and_exit(program, 2);
+    }
+
+    if check && write {
+        eprintln!("{program}: cannot use --check together with --write");
+        usage_and_exit(program, 2);
+    }
+
+    if width == Some(0) {
+        eprintln!("{program}: --width must be at least 1");
+        usage_and_exit(program, 2);
+    }
+
+    let source = if let Some(path) = &file {
+        match fs::read_to_string(path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("{program}: failed to read code file as UTF-8: {e}");
+                let _ = io::stderr().flush();
+                return 1;
+            }
+        }
+    } else if !code.is_empty() {
+        code.join("")
+    } else {
+        let mut s = String::new();
+        if let Err(e) = io::stdin().read_to_string(&mut s) {
+            eprintln!("{program}: failed reading UTF-8 from stdin: {e}");
+            let _ = io::stderr().flush();
+            return 1;
+        }
+        s
+    };
+
+    let defaults = FormatOptions::default();
+    let options = FormatOptions {
+        width: width.unwrap_or(defaults.width),
+        indent: indent.unwrap_or(defaults.indent),
+        ..defaults
+    };
+
+    let formatted = match BrainfuckFormatter::with_options(&source, options).format() {
+        Ok(f) => f,
+        Err(err) => {
+            print_reader_error(Some(program), &source, &err);
+            return 1;
+        }
+    };
+
+    // Formatted files end with exactly one newline
+    let formatted = if formatted.is_empty() { formatted } else { formatted + "\n" };
+
+    if check {
+        // Code given on the command line or piped in need not end with the newline files get
+        let unchanged = if file.is_some() {
+            formatted == source
+        } else {
+            formatted.trim_end_matches('\n') == source.trim_end_matches('\n')
+        };
+        if unchanged {
+            return 0;
+        }
+        let name = file.as_deref().unwrap_or("<code>");
+        eprintln!("{program}: {name} is not formatted");
+        let _ = io::stderr().flush();
+        return 1;
+    }
+
+    if write {
+        let path = file.expect("checked above");
+        if formatted != source
+            && let Err(e) = fs::write(&path, &formatted)
+        {
+            eprintln!("{program}: failed to write {path}: {e}");
+            let _ = io::stderr().flush();
+            return 1;
+        }
+        return 0;
+    }
+
+    print!("{formatted}");
+    let _ = io::stdout().flush();
+    0
+}
+
+fn usage_and_exit(program: &str, code: i32) -> ! {
+    eprintln!(
+        r#"Usage:
+  {0} fmt [--width N] [--indent N] "<code>"
+  {0} fmt [--width N] [--indent N] [--check | --write] --file <PATH>
+  {0} fmt [--width N] [--indent N] [--check] < program.bf
+
+Options:
+  --file,  -f <PATH>  Read Brainfuck code from PATH instead of positional "<code>" or STDIN
+  --check             Exit 1 if the code is not already formatted (for CI)
+  --write, -w         Rewrite --file in place instead of printing to stdout
+  --width <N>         Maximum line width (default 80)
+  --indent <N>        Spaces of indentation per loop level (default 2)
+  --help,  -h         Show this help
+
+Description:
+  Re-lays out Brainfuck source without changing its commands: loop bodies are
+  indented on their own lines, commands are grouped at each pointer move, lines
+  wrap at the configured width, and comments stay where they were.
+
+Notes:
+- Short loops without nested loops or comments (e.g. [-], [->+<]) stay inline.
+- Formatted output ends with a single newline; formatting is idempotent.
+
+Examples:
+- Fail a CI job when a file needs formatting:
+    {0} fmt --check --file ./program.bf
+"#,
+        program
+    );
+    let _ = io::stderr().flush();
+    std::process::exit(code);
+}
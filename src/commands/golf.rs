@@ -0,0 +1,145 @@
+use clap::Args;
+use std::fs;
+use std::io::{self, Write};
+
+use crate::{default_verify_samples, BrainfuckGolfer};
+use crate::cli_util::print_reader_error;
+
+#[derive(Args, Debug)]
+#[command(disable_help_flag = true)]
+pub struct GolfArgs {
+    /// Read Brainfuck code from PATH instead of positional "<code>"
+    #[arg(short = 'f', long = "file")]
+    pub file: Option<String>,
+
+    /// Run original and golfed programs on sample inputs and compare their output
+    #[arg(long = "verify")]
+    pub verify: bool,
+
+    /// Sample input for --verify (repeatable; replaces the built-in samples)
+    #[arg(long = "input", value_name = "TEXT")]
+    pub inputs: Vec<String>,
+
+    /// Concatenated Brainfuck code parts
+    #[arg(value_name = "code", trailing_var_arg = true)]
+    pub code: Vec<String>,
+
+    /// Show this help
+    #[arg(short = 'h', long = "help", action = clap::ArgAction::SetTrue)]
+    pub help: bool,
+}
+
+pub fn run(program: &str, args: GolfArgs) -> i32 {
+    if args.help {
+        usage_and_exit(program, 0);
+    }
+
+    let GolfArgs {
+        file,
+        verify,
+        inputs,
+        code,
+        ..
+    } = args;
+
+    if file.is_none() && code.is_empty() {
+        usage_and_exit(program, 2);
+    }
+
+    if file.is_some() && !code.is_empty() {
+        eprintln!("{program}: cannot use positional code together with --file");
+        usage_and_exit(program, 2);
+    }
+
+    if !inputs.is_empty() && !verify {
+        eprintln!("{program}: --input requires --verify");
+        usage_and_exit(program, 2);
+    }
+
+    let code_str = if let Some(path) = file {
+        match fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("{program}: failed to read code file as UTF-8: {e}");
+                let _ = io::stderr().flush();
+                return 1;
+            }
+        }
+    } else {
+        code.join("")
+    };
+
+    let golfer = BrainfuckGolfer::new(&code_str);
+    let golfed = match golfer.golf() {
+        Ok(g) => g,
+        Err(err) => {
+            print_reader_error(Some(program), &code_str, &err);
+            return 1;
+        }
+    };
+
+    println!("{golfed}");
+    let _ = io::stdout().flush();
+
+    if !verify {
+        return 0;
+    }
+
+    let samples: Vec<Vec<u8>> = if inputs.is_empty() {
+        default_verify_samples()
+    } else {
+        inputs.into_iter().map(String::into_bytes).collect()
+    };
+
+    match golfer.verify(&golfed, &samples) {
+        Ok(report) => {
+            eprintln!(
+                "verified: {} of {} samples match ({} skipped: step limit)",
+                report.checked,
+                samples.len(),
+                report.skipped
+            );
+            let _ = io::stderr().flush();
+            0
+        }
+        Err(err) => {
+            eprintln!("{program}: verification failed: {err}");
+            let _ = io::stderr().flush();
+            1
+        }
+    }
+}
+
+fn usage_and_exit(program: &str, code: i32) -> ! {
+    eprintln!(
+        r#"Usage:
+  {0} golf [--verify [--input TEXT]...] "<code>"
+  {0} golf [--verify [--input TEXT]...] --file <PATH>
+
+Options:
+  --file,  -f <PATH>  Read Brainfuck code from PATH instead of positional "<code>"
+  --verify            Run both programs on sample inputs and compare their output
+  --input <TEXT>      Sample input for --verify (repeatable; replaces the built-in samples)
+  --help,  -h         Show this help
+
+Description:
+  Prints a shorter program with the same behavior: comments are stripped, opposing
+  +-/<> runs cancel, loops that can never run are removed, and constant-building
+  segments are re-encoded when that is shorter.
+
+Notes:
+- The golfed program assumes 8-bit wrapping cells and a tape that starts zeroed.
+- Moves that would take the pointer off the tape are kept, so '<>' at the start
+  still fails with "pointer out of bounds".
+- Built-in --verify samples: empty input, "Hello, World!\n", and bytes 1..=255.
+- Samples where the original exceeds the verification step limit are skipped.
+
+Examples:
+- Golf a file and check the result:
+    {0} golf --verify --file ./program.bf
+"#,
+        program
+    );
+    let _ = io::stderr().flush();
+    std::process::exit(code);
+}
@@ -47,11 +47,36 @@ Notes:
     - Non-Brainfuck characters are ignored; only valid instructions are executed.
     - Ctrl+R executes the current buffer
     - Ctrl+S saves the current buffer to a file
-    - Ctrl+O opens a file into the current buffer
+    - Ctrl+O opens a file in its own buffer (or switches to it if it is already open). The file browser
+      lists .bf/.b files (Ctrl+B: all files) and filters as you type; Tab shows recently used files
     - Ctrl+L toggles line numbers on/off (on by default)
-    - Ctrl+N creates a new empty buffer
+    - Ctrl+N creates a new empty buffer; Ctrl+W closes the current one
+    - Ctrl+PageDown / Ctrl+PageUp switch to the next or previous buffer (Vi normal mode: gt / gT),
+      Alt+1..9 go to a buffer by number. Each buffer keeps its own cursor, undo history and last output
     - Ctrl+P jumps to matching bracket
-    - Ctrl+Q exits the IDE; if there are unsaved changes, you will be asked to confirm.
+    - Ctrl+F searches incrementally (Vi normal mode: /); F3 / Shift+F3 (n / N) go to the next or previous
+      match. In the search bar, Tab switches to the replace field, where Enter replaces all matches, and
+      Ctrl+B matches Brainfuck instructions only (ignoring comments, across lines)
+    - Ctrl+Z / Ctrl+Y undo and redo edits (Vi normal mode: u / Ctrl+R); typing and deletes are grouped
+    - Shift+arrows select text; Ctrl+C / Ctrl+X / Ctrl+V copy, cut and paste, Ctrl+A selects all
+      (Vi: v / V for visual and visual line mode, y / d to yank or cut, yy / dd for lines, p / P to paste)
+    - With a selection, Ctrl+R runs only the selection, formatting applies to the selected lines and
+      replace-all in the search bar is limited to the selection
+    - Copies also go to the terminal clipboard (OSC 52) when [ide] clipboard = "osc52" is set in bf.toml
+    - Ctrl+D edits the buffer's prepared input: text and/or an attached input file (Ctrl+O in the panel)
+      that ',' reads before asking with the input dialog. Once it is used up the IDE either asks or
+      reports EOF (Ctrl+P), and EOF stores 0, leaves the cell unchanged or stores 255 (Ctrl+E).
+      Defaults come from [ide] eof = "zero|unchanged|max" and input-exhausted = "prompt|eof" in bf.toml
+    - F2 opens the settings: timeout (or none), max steps, memory size, EOF value and whether runs start
+      on a fresh tape or keep the last one. Enter applies them for the session, Ctrl+S also saves them
+      to [ide] in bf.toml (BF_TIMEOUT_MS / BF_MAX_STEPS still override at startup)
+    - Ctrl+Shift+P or F4 (Vi normal mode: :) opens the command palette, listing every action with its
+      keys; keys can be remapped in [ide.keys] in bf.toml, e.g. run = "f9, ctrl+r"
+    - F6 pauses and resumes the running program; time paused does not count toward the timeout
+    - The tape pane browses the whole tape: arrows, [ / ] and Home/End move, g goes to an address,
+      / and n / N find a value, z / Z jump between non-zero regions, f switches hex/decimal/char and
+      c follows the data pointer. While paused, e / Enter and + / - edit the inspected cell
+    - Ctrl+Q exits the IDE; if any buffer has unsaved changes, you will be asked to confirm.
 "#,
         program
     );
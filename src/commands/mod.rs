@@ -2,3 +2,5 @@ pub mod read;
 pub mod repl;
 pub mod write;
 pub mod ide;
+pub mod golf;
+pub mod fmt;
@@ -1,7 +1,8 @@
 use std::io::{self, IsTerminal, Write};
+use std::path::Path;
 use clap::Args;
 
-use crate::repl::{execute_bare_once, repl_loop, select_mode, ReplMode, ModeFlagOverride};
+use crate::repl::{execute_bare_once, execute_script, repl_loop, select_mode, ReplMode, ReplOptions, ModeFlagOverride};
 
 #[derive(Args, Debug)]
 #[command(disable_help_flag = true)]
@@ -14,14 +15,88 @@ pub struct ReplArgs {
     #[arg(long = "editor", conflicts_with = "bare")]
     pub editor: bool,
 
+    /// Replay a transcript (e.g. from `:record`) non-interactively, then exit
+    #[arg(long = "script", value_name = "FILE", conflicts_with = "editor")]
+    pub script: Option<String>,
+
+    /// Keep tape, pointer and pending input across submissions
+    #[arg(long = "persist")]
+    pub persist: bool,
+
+    /// Wall-clock timeout per run in milliseconds, or "none"
+    #[arg(long = "timeout", value_name = "MS")]
+    pub timeout: Option<String>,
+
+    /// Maximum interpreter steps per run (e.g. 1000000 or 1e6), or "none"
+    #[arg(long = "max-steps", value_name = "N")]
+    pub max_steps: Option<String>,
+
+    /// Tape size in cells
+    #[arg(long = "memory", value_name = "CELLS")]
+    pub memory: Option<String>,
+
+    /// What ',' stores at EOF: zero, unchanged or max
+    #[arg(long = "eof", value_name = "MODE")]
+    pub eof: Option<String>,
+
+    /// Use Vi key bindings in the line editor
+    #[arg(long = "vi", conflicts_with = "emacs")]
+    pub vi: bool,
+
+    /// Use Emacs key bindings in the line editor (the default; overrides edit-mode in the config file)
+    #[arg(long = "emacs", conflicts_with = "vi")]
+    pub emacs: bool,
+
+    /// History file, or "none" to keep history for this session only
+    #[arg(long = "history-file", value_name = "PATH")]
+    pub history_file: Option<String>,
+
+    /// Maximum number of history entries to keep
+    #[arg(long = "history-size", value_name = "N")]
+    pub history_size: Option<String>,
+
     /// Show this help
     #[arg(short = 'h', long = "help", action = clap::ArgAction::SetTrue)]
-    help: bool,
+    pub help: bool,
 }
 
 
+/// Build REPL options from flags on top of the config file and environment settings.
+/// Exits with usage on an invalid setting value.
+pub fn options_from_args(program: &str, args: &ReplArgs) -> ReplOptions {
+    let mut options = ReplOptions::load();
+    options.persist = args.persist;
+    if args.vi || args.emacs {
+        options.vi_mode = args.vi;
+    }
+    let flags = [
+        ("timeout", &args.timeout),
+        ("max-steps", &args.max_steps),
+        ("memory", &args.memory),
+        ("eof", &args.eof),
+    ];
+    for (name, value) in flags {
+        if let Some(value) = value
+            && let Err(e) = options.settings.set(name, value)
+        {
+            eprintln!("{program}: --{name}: {e}");
+            usage_and_exit(program, 2);
+        }
+    }
+    let history_flags = [("history-file", &args.history_file), ("history-size", &args.history_size)];
+    for (name, value) in history_flags {
+        if let Some(value) = value
+            && let Err(e) = options.history.set(name, value)
+        {
+            eprintln!("{program}: --{name}: {e}");
+            usage_and_exit(program, 2);
+        }
+    }
+    options
+}
+
 // Public entry point for the REPL from main.rs
-pub fn run(program: &str, help: bool, mode_flag: ModeFlagOverride) -> i32 {
+pub fn run(program: &str, help: bool, mode_flag: ModeFlagOverride, options: ReplOptions) -> i32 {
     if help {
         usage_and_exit(program, 0);
     }
@@ -56,7 +131,7 @@ pub fn run(program: &str, help: bool, mode_flag: ModeFlagOverride) -> i32 {
                 let _ = io::stderr().flush();
             }
 
-            if let Err(e) = repl_loop() {
+            if let Err(e) = repl_loop(options) {
                 eprintln!("{program}: REPL error: {e}");
                 let _ = io::stderr().flush();
                 return 1;
@@ -66,7 +141,7 @@ pub fn run(program: &str, help: bool, mode_flag: ModeFlagOverride) -> i32 {
         }
         ReplMode::Bare => {
             // Bare mode: read stdin until EOF, execute once, exit 0
-            match execute_bare_once() {
+            match execute_bare_once(options) {
                 Ok(_) => 0,
                 Err(e) => {
                     eprintln!("{program}: REPL error: {e}");
@@ -78,6 +153,18 @@ pub fn run(program: &str, help: bool, mode_flag: ModeFlagOverride) -> i32 {
     }
 }
 
+/// Entry point for `bf repl --script <file>`: run the script like bare-mode input, whatever stdin is.
+pub fn run_script(program: &str, path: &str, options: ReplOptions) -> i32 {
+    match execute_script(Path::new(path), options) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{program}: failed to run script {path}: {e}");
+            let _ = io::stderr().flush();
+            1
+        }
+    }
+}
+
 fn usage_and_exit(program: &str, code: i32) -> ! {
     eprintln!(
         r#"Usage:
@@ -87,25 +174,46 @@ Options:
   --help,   -h        Show this help
   --bare              Force non-interactive bare mode
   --editor            Force interactive editor mode (errors if stdin is not a TTY)
+  --script <FILE>     Replay a transcript (see :record) non-interactively, then exit
+  --persist           Keep tape, pointer and pending input across submissions
+  --timeout <MS>      Wall-clock timeout per run in milliseconds, or "none" (default 2000)
+  --max-steps <N>     Step limit per run, e.g. 1000000 or 1e6, or "none" (default)
+  --memory <CELLS>    Tape size in cells (default 30000)
+  --eof <MODE>        What ',' stores at EOF: zero (default), unchanged or max
+  --vi                Use Vi key bindings in the line editor
+  --emacs             Use Emacs key bindings (default; overrides edit-mode in bf.toml)
+  --history-file <PATH>  History file, or "none" for session-only history
+                      (default: $XDG_DATA_HOME/bf/repl_history)
+  --history-size <N>  Maximum number of history entries (default 1000)
 
 Description:
   Starts a REPL where you can enter Brainfuck code and execute it live.
 
 Meta commands (line starts with ":")
-  :exit            Exit immediately (code 0)
-  :help            Show this help
-  :reset           Clear current buffer (history is preserved)
-  :dump            Print buffer (content → stdout; framing → stderr)
-    -n             Include line numbers (stdout)
-    --stderr       Send everything to stderr
-
+{1}
 Notes:
     - While editing, non-Brainfuck characters are ignored; only valid instructions are executed.
-    - Ctrl+D executes the current buffer on *nix/macOS.
+    - Ctrl+D executes the current buffer on *nix/macOS. Code with unbalanced brackets is not
+      submitted: a newline is inserted instead and the first unmatched bracket is shown.
+    - Unmatched brackets are highlighted; the bracket pair at the cursor is marked.
     - Ctrl+Z and Enter will execute the current buffer on Windows.
     - Ctrl+C exits the REPL immediately.
+    - Tab completes meta-command names, their arguments and flags, and file paths after :load,
+      :run, :save and :input-file; Right arrow accepts the inline hint for a partly typed command.
+    - Key bindings for submit, newline and history navigation can be remapped in the
+      [repl.keys] table of bf.toml, e.g. history-previous = "alt+up, ctrl+p".
+    - Unknown meta-commands are errors with a "did you mean" suggestion.
+    - In bare mode, lines starting with ":" are meta commands; the code before each one runs first.
+    - A transcript holds code as submitted, meta commands, `:input` lines for text typed at the
+      input prompt, and `:#` between consecutive submissions. `--script` replays it with program
+      output on stdout and messages on stderr, so a session can be kept as a regression test.
     - The REPL will print a newline after each execution for readability.
-    - Each execution starts with a fresh memory and pointer.
+    - Each execution starts with a fresh memory and pointer, unless --persist or `:persist on`
+      is active; then tape, pointer and unread input carry over until `:reset`.
+    - ',' reads queued input first; when the queue is empty an `input> ` prompt reads a line
+      (Ctrl+D at the prompt is EOF). Time spent at the prompt does not count toward --timeout.
+    - Settings are resolved as: defaults, then [repl] in bf.toml, then the environment
+      (BF_TIMEOUT_MS, BF_MAX_STEPS), then flags, then `:set` for the rest of the session.
     - The REPL will exit after a single execution if the environment variable `BF_REPL_ONCE` is set to `1`.
     - Mode selection:
         * Flags: --bare|--editor override environment and auto-detection.
@@ -114,7 +222,8 @@ Notes:
         * Prompts/banners suppressed if stderr is not a TTY.
 
 "#,
-        program
+        program,
+        crate::repl::meta_help_lines()
     );
     let _ = io::stderr().flush();
     std::process::exit(code);
@@ -2,7 +2,7 @@ use clap::Args;
 use std::fs;
 use std::io::{self, Read, Write};
 
-use crate::BrainfuckWriter;
+use crate::{BrainfuckFormatter, BrainfuckWriter, CellWidth, FormatOptions, WriterOptions, WriterTarget, WriterTemplate};
 
 #[derive(Args, Debug)]
 #[command(disable_help_flag = true)]
@@ -15,6 +15,34 @@ pub struct WriteArgs {
     #[arg(short = 'f', long = "file")]
     pub file: Option<String>,
 
+    /// Emit a ready-made program that transforms its input (e.g. cat, upper, rot13)
+    #[arg(short = 't', long = "template", value_name = "NAME")]
+    pub template: Option<String>,
+
+    /// Lay out the generated code on indented, wrapped lines (see `fmt`)
+    #[arg(long = "tidy")]
+    pub tidy: bool,
+
+    /// Put each byte's code on its own line with a comment naming the byte and how it was built
+    #[arg(long = "annotate")]
+    pub annotate: bool,
+
+    /// Target interpreter cell width in bits: 8, 16, or 32 (default 8)
+    #[arg(long = "cell-bits", value_name = "N")]
+    pub cell_bits: Option<u32>,
+
+    /// Target interpreter does not wrap cell values
+    #[arg(long = "no-wrap")]
+    pub no_wrap: bool,
+
+    /// Cells the generated code may use (default 30000; 1 disables loop constructions)
+    #[arg(long = "max-cells", value_name = "N")]
+    pub max_cells: Option<usize>,
+
+    /// Target interpreter does not start with zeroed cells
+    #[arg(long = "unknown-start")]
+    pub unknown_start: bool,
+
     /// Positional text (UTF-8). If omitted, reads from STDIN.
     #[arg(value_name = "TEXT", trailing_var_arg = true)]
     pub text: Vec<String>,
@@ -32,10 +60,57 @@ pub fn run(program: &str, args: WriteArgs) -> i32 {
     let WriteArgs {
         bytes,
         file,
+        template,
+        tidy,
+        annotate,
+        cell_bits,
+        no_wrap,
+        max_cells,
+        unknown_start,
         text,
         ..
     } = args;
 
+    let cell_width = match cell_bits {
+        None | Some(8) => CellWidth::Bits8,
+        Some(16) => CellWidth::Bits16,
+        Some(32) => CellWidth::Bits32,
+        Some(n) => {
+            eprintln!("{program}: unsupported --cell-bits {n} (expected 8, 16, or 32)");
+            usage_and_exit(program, 2);
+        }
+    };
+    if max_cells == Some(0) {
+        eprintln!("{program}: --max-cells must be at least 1");
+        usage_and_exit(program, 2);
+    }
+    let target = WriterTarget {
+        max_cells: max_cells.unwrap_or(WriterTarget::STANDARD.max_cells),
+        cell_width,
+        wrapping: !no_wrap,
+        zeroed_start: !unknown_start,
+    };
+
+    if let Some(name) = template {
+        if file.is_some() || !text.is_empty() || bytes || annotate {
+            eprintln!("{program}: cannot use --template together with TEXT, --file, --bytes, or --annotate");
+            usage_and_exit(program, 2);
+        }
+        let Some(template) = WriterTemplate::from_name(&name) else {
+            eprintln!("{program}: unknown template '{name}'");
+            usage_and_exit(program, 2);
+        };
+        let code = template.generate();
+        let code = if tidy {
+            BrainfuckFormatter::new(&code).format().unwrap_or(code)
+        } else {
+            code
+        };
+        println!("{}", code);
+        let _ = io::stdout().flush();
+        return 0;
+    }
+
     if file.is_some() && !text.is_empty() {
         eprintln!("{program}: cannot use positional TEXT together with --file");
         usage_and_exit(program, 2);
@@ -90,7 +165,13 @@ pub fn run(program: &str, args: WriteArgs) -> i32 {
         }
     };
 
-    let writer = BrainfuckWriter::new(&input_bytes);
+    let options = WriterOptions {
+        tidy: tidy.then(FormatOptions::default),
+        annotate,
+        target,
+        ..WriterOptions::default()
+    };
+    let writer = BrainfuckWriter::with_options(&input_bytes, options);
     match writer.generate() {
         Ok(code) => {
             println!("{}", code);
@@ -106,27 +187,49 @@ pub fn run(program: &str, args: WriteArgs) -> i32 {
 }
 
 fn usage_and_exit(program: &str, code: i32) -> ! {
+    let templates: String = WriterTemplate::ALL
+        .iter()
+        .map(|t| format!("  {:<10}{}\n", t.name(), t.description()))
+        .collect();
     eprintln!(
         r#"Usage:
   {0} write [--bytes] [TEXT...]           # Read UTF-8 TEXT args (preferred) or from STDIN if no TEXT is given
   {0} write [--bytes] --file <PATH>       # Read from file instead of STDIN
+  {0} write --template <NAME>             # Emit a ready-made program that transforms its input
+  {0} write --tidy [TEXT...]              # Same output, laid out on indented lines
+  {0} write --annotate [TEXT...]          # One line per byte, commented with its value and encoding
 
 Options:
-  --file,  -f <PATH>  Read input from file at PATH (otherwise reads from TEXT or STDIN)
-  --bytes             Treat input as raw bytes (no UTF-8 required)
-  --help,   -h        Show this help
+  --file,     -f <PATH>  Read input from file at PATH (otherwise reads from TEXT or STDIN)
+  --bytes                Treat input as raw bytes (no UTF-8 required)
+  --template, -t <NAME>  Emit the named template program instead of encoding input
+  --tidy                 Format the generated code (loops indented, wrapped at 80 columns)
+  --annotate             Emit each byte's code on its own line with a comment such as
+                         'H' 0x48 loop 8x9 (comments contain no Brainfuck commands)
+  --cell-bits <N>        Target cell width: 8, 16, or 32 (default 8)
+  --no-wrap              Target does not wrap cell values; avoid wrap-around arithmetic
+  --max-cells <N>        Cells the code may use (default 30000; 1 disables loop constructions)
+  --unknown-start        Target cells may not start at zero; clear before building values
+  --help,     -h         Show this help
 
 Description:
   Generates Brainfuck code that, when executed, will output the provided input bytes.
 
+Templates (read input with `,` until EOF or a 0 byte):
+{1}
 Input modes:
   - Default (string-like): expects UTF-8 text from positional TEXT, STDIN, or file and uses its bytes.
   - --bytes (byte-like): reads raw bytes from STDIN or file; positional TEXT is still accepted as UTF-8 and used as bytes.
 
 Notes:
   - Output is Brainfuck code printed to stdout followed by a newline.
+  - Template programs assume 8-bit wrapping cells; the target options apply to TEXT/file input only.
+
+Examples:
+  - Uppercase a file:
+      {0} read "$({0} write --template upper)" < input.txt
 "#,
-        program
+        program, templates
     );
     let _ = io::stderr().flush();
     std::process::exit(code);
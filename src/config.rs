@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::fs;
+use std::io;
 use std::path::PathBuf;
 use std::sync::OnceLock;
 use cross_xdg::BaseDirs;
@@ -35,6 +36,8 @@ pub struct Colors {
     pub editor_op_input: Color,     // ','
     pub editor_op_bracket: Color,   // '[' and ']'
     pub editor_non_bf: Color,
+    pub editor_bracket_unmatched: Color,
+    pub editor_bracket_match: Color, // background behind the bracket pair at the cursor
 }
 
 impl Default for Colors {
@@ -69,6 +72,8 @@ impl Default for Colors {
             editor_op_input: Color::Magenta,
             editor_op_bracket: Color::LightMagenta,
             editor_non_bf: Color::Gray,
+            editor_bracket_unmatched: Color::Red,
+            editor_bracket_match: Color::DarkGray,
         }
     }
 }
@@ -116,31 +121,62 @@ fn parse_color(value: &str) -> Option<Color> {
     None
 }
 
-fn load_from_toml() -> Option<Colors> {
-    // Look for ./config.toml in CWD
-    let base_dirs = BaseDirs::new().unwrap();
+/// Path of the user config file, `bf.toml` in the XDG config home.
+pub fn config_path() -> Option<PathBuf> {
+    let base_dirs = BaseDirs::new().ok()?;
 
     // On Linux: resolves to /home/<user>/.config
     // On Windows: resolves to C:\Users\<user>\.config
     // On macOS: resolves to /Users/<user>/.config
-    let config_home = base_dirs.config_home();
-
-    let mut path = PathBuf::from(config_home);
+    let mut path = PathBuf::from(base_dirs.config_home());
     path.push("bf.toml");
+    Some(path)
+}
 
-    let content = fs::read_to_string(path).ok()?;
-    // Very small hand-rolled parser: look for [colors] section and key = value pairs
-    // Values are strings like "#RRGGBB" or named colors.
-    let mut in_colors = false;
+/// Directory for data files such as the REPL history: `bf` in the XDG data home.
+pub fn data_dir() -> Option<PathBuf> {
+    let base_dirs = BaseDirs::new().ok()?;
+
+    // On Linux: resolves to /home/<user>/.local/share/bf
+    let mut path = PathBuf::from(base_dirs.data_home());
+    path.push("bf");
+    Some(path)
+}
+
+/// Key/value pairs of the `[section]` table in `bf.toml`; empty if the file or section is missing.
+pub fn read_section(section: &str) -> HashMap<String, String> {
+    config_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| parse_section(&content, section))
+        .unwrap_or_default()
+}
+
+/// Set `entries` in the `[section]` table of `bf.toml`, keeping other keys and the rest of
+/// the file. The table stays where it is, or is added at the end. String values are quoted;
+/// returns the path written.
+pub fn write_section(section: &str, entries: &[(&str, String)]) -> io::Result<PathBuf> {
+    let path = config_path().ok_or_else(|| io::Error::other("cannot determine the config directory"))?;
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, replace_section(&content, section, entries))?;
+    Ok(path)
+}
+
+// Very small hand-rolled parser: find the [section] header and collect key = value pairs
+// until the next header. Values may be quoted or unquoted; see `strip_comment` for comments.
+pub(crate) fn parse_section(content: &str, section: &str) -> HashMap<String, String> {
+    let mut in_section = false;
     let mut map: HashMap<String, String> = HashMap::new();
     for line in content.lines() {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') { continue; }
+        let line = strip_comment(line).trim();
+        if line.is_empty() { continue; }
         if line.starts_with('[') && line.ends_with(']') {
-            in_colors = &line[1..line.len()-1] == "colors";
+            in_section = &line[1..line.len()-1] == section;
             continue;
         }
-        if !in_colors { continue; }
+        if !in_section { continue; }
         if let Some(eq) = line.find('=') {
             let key = line[..eq].trim().to_string();
             let val_raw = line[eq+1..].trim();
@@ -151,6 +187,96 @@ fn load_from_toml() -> Option<Colors> {
             map.insert(key, val);
         }
     }
+    map
+}
+
+/// The line up to its comment: a `#` outside double quotes that starts the line or follows
+/// whitespace. A `#` opening an unquoted value, as in `gutter_text = #ff0000`, is kept.
+fn strip_comment(line: &str) -> &str {
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let mut seen_eq = false;
+    let mut in_value = false;
+    let mut after_space = true;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            '=' if !in_quotes && !seen_eq => seen_eq = true,
+            '#' if !in_quotes && after_space && (in_value || !seen_eq) => return &line[..i],
+            _ => {}
+        }
+        after_space = c.is_whitespace();
+        in_value |= seen_eq && !after_space && c != '=';
+    }
+    line
+}
+
+fn replace_section(content: &str, section: &str, entries: &[(&str, String)]) -> String {
+    let mut out = String::new();
+    // Lines of the table being rewritten that are not overwritten, e.g. other keys and comments
+    let mut kept: Vec<&str> = Vec::new();
+    let mut in_section = false;
+    let mut found = false;
+    for line in content.lines() {
+        let trimmed = strip_comment(line).trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            if in_section {
+                // Entries go to the first table of that name; a repeat only loses the overwritten keys
+                push_table_body(&mut out, &kept, if found { &[] } else { entries });
+                found = true;
+                kept.clear();
+            }
+            in_section = &trimmed[1..trimmed.len()-1] == section;
+        } else if in_section {
+            let key = trimmed.split('=').next().unwrap_or("").trim();
+            if !entries.iter().any(|(name, _)| *name == key) {
+                kept.push(line);
+            }
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    if in_section {
+        push_table_body(&mut out, &kept, if found { &[] } else { entries });
+    } else if !found {
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+        out.push_str(&format!("[{section}]\n"));
+        push_table_body(&mut out, &[], entries);
+    }
+    out
+}
+
+// The kept lines with `entries` after the last of them that is not blank, so the blank
+// lines separating the table from the next one stay in place
+fn push_table_body(out: &mut String, kept: &[&str], entries: &[(&str, String)]) {
+    let split = kept.iter().rposition(|line| !line.trim().is_empty()).map_or(0, |i| i + 1);
+    for line in &kept[..split] {
+        out.push_str(line);
+        out.push('\n');
+    }
+    for (key, value) in entries {
+        if value.parse::<f64>().is_ok() {
+            out.push_str(&format!("{key} = {value}\n"));
+        } else {
+            out.push_str(&format!("{key} = \"{value}\"\n"));
+        }
+    }
+    for line in &kept[split..] {
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+fn load_from_toml() -> Option<Colors> {
+    let path = config_path()?;
+    let content = fs::read_to_string(path).ok()?;
+    // Values are strings like "#RRGGBB" or named colors.
+    let map = parse_section(&content, "colors");
 
     let mut cfg = Colors::default();
 
@@ -188,6 +314,62 @@ fn load_from_toml() -> Option<Colors> {
     set!(editor_op_input, "editor_op_input");
     set!(editor_op_bracket, "editor_op_bracket");
     set!(editor_non_bf, "editor_non_bf");
+    set!(editor_bracket_unmatched, "editor_bracket_unmatched");
+    set!(editor_bracket_match, "editor_bracket_match");
 
     Some(cfg)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_section_keeps_other_tables() {
+        let content = "[colors]\ngutter_text = \"gray\"\n\n[repl]\ntimeout = 10\nhistory-size = 50\n\n[other]\nx = 1\n";
+        let entries = [("timeout", "5000".to_string()), ("eof", "unchanged".to_string())];
+        let updated = replace_section(content, "repl", &entries);
+        assert_eq!(
+            updated,
+            "[colors]\ngutter_text = \"gray\"\n\n[repl]\nhistory-size = 50\ntimeout = 5000\neof = \"unchanged\"\n\n[other]\nx = 1\n"
+        );
+        // Saving again leaves the file as it is
+        assert_eq!(replace_section(&updated, "repl", &entries), updated);
+
+        let repl = parse_section(&updated, "repl");
+        assert_eq!(repl.get("timeout").map(String::as_str), Some("5000"));
+        assert_eq!(repl.get("eof").map(String::as_str), Some("unchanged"));
+        assert_eq!(repl.get("history-size").map(String::as_str), Some("50"));
+        assert_eq!(parse_section(&updated, "colors").get("gutter_text").map(String::as_str), Some("gray"));
+    }
+
+    #[test]
+    fn trailing_comments_are_stripped_outside_quotes() {
+        let content = "# bf settings\n[repl.keys]  # remapped keys\nsubmit = \"ctrl+d, ctrl+z\"   # default\nnewline = enter # bare\n[other]\n\
+                       label = \"a # b\"  # quoted hash\n";
+        let keys = parse_section(content, "repl.keys");
+        assert_eq!(keys.get("submit").map(String::as_str), Some("ctrl+d, ctrl+z"));
+        assert_eq!(keys.get("newline").map(String::as_str), Some("enter"));
+        assert_eq!(keys.len(), 2);
+        assert_eq!(parse_section(content, "other").get("label").map(String::as_str), Some("a # b"));
+
+        // An unquoted hex color starts with '#' but is not a comment
+        let colors = parse_section("[colors]\ngutter_text = #ff0000\nbackground = #000000 # black\n", "colors");
+        assert_eq!(colors.get("gutter_text").map(String::as_str), Some("#ff0000"));
+        assert_eq!(colors.get("background").map(String::as_str), Some("#000000"));
+    }
+
+    #[test]
+    fn replace_section_keeps_comments_inside_the_table() {
+        let content = "[repl]\n# slow machine\ntimeout = 10  # ms\nhistory-size = 50 # lines\n";
+        let updated = replace_section(content, "repl", &[("timeout", "5000".to_string())]);
+        assert_eq!(updated, "[repl]\n# slow machine\nhistory-size = 50 # lines\ntimeout = 5000\n");
+    }
+
+    #[test]
+    fn replace_section_appends_a_missing_table() {
+        let entries = [("timeout", "5000".to_string())];
+        assert_eq!(replace_section("[colors]\nx = 1\n", "repl", &entries), "[colors]\nx = 1\n\n[repl]\ntimeout = 5000\n");
+        assert_eq!(replace_section("", "repl", &entries), "[repl]\ntimeout = 5000\n");
+    }
+}
@@ -0,0 +1,355 @@
+//! A Brainfuck source formatter.
+//!
+//! Re-lays out code without changing its command sequence:
+//! - Loop bodies go on their own lines, indented one level per nesting depth.
+//!   Short loops with no nested loops or comments (e.g. `[-]`, `[->+<]`) stay inline.
+//! - Commands are grouped into words that start at each pointer move (`+++ >++ <<-.`).
+//! - Lines wrap at a configurable width.
+//! - Comments (non-command text) stay where they are relative to the surrounding commands.
+//!   A comment that ended a line in the source still ends a line, and whole-line comments
+//!   stay on their own lines.
+//!
+//! Formatting is idempotent, so `bf fmt --check` can be used in CI.
+//!
+//! Quick start:
+//!
+//! ```no_run
+//! use rust_bf::BrainfuckFormatter;
+//!
+//! let formatted = BrainfuckFormatter::new("++[>+[-]<-]").format().unwrap();
+//! assert_eq!(formatted, "++\n[\n  >+[-] <-\n]");
+//! ```
+
+use crate::{BrainfuckReaderError, UnmatchedBracketKind};
+
+/// Layout settings for [`BrainfuckFormatter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Maximum line width, including indentation. Comment words may exceed it.
+    pub width: usize,
+    /// Spaces of indentation per loop nesting level.
+    pub indent: usize,
+    /// Loops with at most this many commands in their body (and no nested loops or
+    /// comments) are kept inline.
+    pub inline_loop_max: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            width: 80,
+            indent: 2,
+            inline_loop_max: 8,
+        }
+    }
+}
+
+pub struct BrainfuckFormatter<'code> {
+    code: &'code str,
+    options: FormatOptions,
+}
+
+impl<'code> BrainfuckFormatter<'code> {
+    pub fn new(code: &'code str) -> Self {
+        Self { code, options: FormatOptions::default() }
+    }
+
+    pub fn with_options(code: &'code str, options: FormatOptions) -> Self {
+        Self { code, options }
+    }
+
+    /// Format the code. Lines are joined with `\n` and there is no trailing newline.
+    ///
+    /// Unmatched brackets are reported with `ip` indexing into the original source.
+    pub fn format(&self) -> Result<String, BrainfuckReaderError> {
+        let tokens = tokenize(self.code)?;
+        let mut layout = Layout::new(self.options);
+
+        let mut i = 0;
+        while i < tokens.len() {
+            match &tokens[i] {
+                Token::Comment(text) => layout.comment(text),
+                Token::Cmd('[') => {
+                    if let Some(end) = inline_loop_end(&tokens, i, self.options.inline_loop_max) {
+                        let text: String = tokens[i..=end]
+                            .iter()
+                            .filter_map(|t| match t { Token::Cmd(c) => Some(*c), _ => None })
+                            .collect();
+                        layout.code_atomic(&text);
+                        i = end + 1;
+                        continue;
+                    }
+                    layout.open_loop();
+                }
+                Token::Cmd(']') => layout.close_loop(),
+                Token::Cmd(c) => layout.code(*c),
+            }
+            i += 1;
+        }
+
+        Ok(layout.finish())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Cmd(char),
+    /// Raw non-command text, including any newlines; never whitespace-only.
+    Comment(String),
+}
+
+fn is_command(c: char) -> bool {
+    matches!(c, '>' | '<' | '+' | '-' | '.' | ',' | '[' | ']')
+}
+
+fn is_move(c: char) -> bool {
+    matches!(c, '>' | '<')
+}
+
+fn tokenize(code: &str) -> Result<Vec<Token>, BrainfuckReaderError> {
+    let mut tokens = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    let mut segment = String::new();
+
+    for (ip, ch) in code.chars().enumerate() {
+        if !is_command(ch) {
+            segment.push(ch);
+            continue;
+        }
+        end_segment(&mut segment, &mut tokens);
+        match ch {
+            '[' => stack.push(ip),
+            ']' if stack.pop().is_none() => {
+                return Err(BrainfuckReaderError::UnmatchedBrackets {
+                    ip,
+                    kind: UnmatchedBracketKind::Close,
+                });
+            }
+            _ => {}
+        }
+        tokens.push(Token::Cmd(ch));
+    }
+    end_segment(&mut segment, &mut tokens);
+
+    if let Some(ip) = stack.last() {
+        return Err(BrainfuckReaderError::UnmatchedBrackets {
+            ip: *ip,
+            kind: UnmatchedBracketKind::Open,
+        });
+    }
+    Ok(tokens)
+}
+
+// Whitespace-only text is layout, not a comment, and is dropped.
+fn end_segment(segment: &mut String, tokens: &mut Vec<Token>) {
+    if !segment.trim().is_empty() {
+        tokens.push(Token::Comment(std::mem::take(segment)));
+    }
+    segment.clear();
+}
+
+/// Index of the `]` closing the loop at `open` if that loop should stay inline.
+fn inline_loop_end(tokens: &[Token], open: usize, max_body: usize) -> Option<usize> {
+    let mut body = 0usize;
+    for (j, token) in tokens.iter().enumerate().skip(open + 1) {
+        match token {
+            Token::Cmd(']') => return Some(j),
+            Token::Cmd('[') | Token::Comment(_) => return None,
+            Token::Cmd(_) => {
+                body += 1;
+                if body > max_body {
+                    return None;
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Line builder: words are placed onto indented lines and wrapped at the configured width.
+struct Layout {
+    options: FormatOptions,
+    lines: Vec<String>,
+    line: String,
+    word: String,
+    depth: usize,
+    // After `[` or `]` the next code starts on a fresh line; comments may still trail.
+    break_before_code: bool,
+}
+
+impl Layout {
+    fn new(options: FormatOptions) -> Self {
+        Self {
+            options,
+            lines: Vec::new(),
+            line: String::new(),
+            word: String::new(),
+            depth: 0,
+            break_before_code: false,
+        }
+    }
+
+    fn code(&mut self, c: char) {
+        let starts_move = is_move(c) && self.word.chars().last().is_some_and(|last| !is_move(last));
+        if starts_move {
+            self.end_word();
+        }
+        self.word.push(c);
+    }
+
+    fn code_atomic(&mut self, text: &str) {
+        self.word.push_str(text);
+    }
+
+    fn open_loop(&mut self) {
+        self.end_word();
+        self.flush();
+        self.place("[", false);
+        self.depth += 1;
+        self.break_before_code = true;
+    }
+
+    fn close_loop(&mut self) {
+        self.end_word();
+        self.flush();
+        self.depth = self.depth.saturating_sub(1);
+        self.place("]", false);
+        self.break_before_code = true;
+    }
+
+    fn comment(&mut self, text: &str) {
+        self.end_word();
+        let pieces: Vec<&str> = text.split('\n').collect();
+        let last = pieces.len() - 1;
+        for (n, piece) in pieces.iter().enumerate() {
+            for word in piece.split_whitespace() {
+                self.place(word, false);
+            }
+            // Every newline in the comment ends the current line
+            if n < last {
+                self.flush();
+            }
+        }
+    }
+
+    fn end_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+        let word = std::mem::take(&mut self.word);
+        if self.break_before_code {
+            self.flush();
+            self.break_before_code = false;
+        }
+        self.place(&word, true);
+    }
+
+    fn indent_width(&self) -> usize {
+        self.depth * self.options.indent
+    }
+
+    /// Place a word, wrapping first if it does not fit. Code words longer than a whole
+    /// line are split; comment words are left intact.
+    /// Widths are counted in characters, so comments in any script wrap alike.
+    fn place(&mut self, word: &str, split_if_long: bool) {
+        let word_width = word.chars().count();
+        if !self.line.is_empty() && self.line.chars().count() + 1 + word_width > self.options.width {
+            self.flush();
+        }
+
+        if self.line.is_empty() {
+            let available = self.options.width.saturating_sub(self.indent_width()).max(1);
+            if split_if_long && word_width > available {
+                let chars: Vec<char> = word.chars().collect();
+                for chunk in chars.chunks(available) {
+                    self.flush();
+                    self.line = " ".repeat(self.indent_width());
+                    self.line.extend(chunk);
+                }
+                return;
+            }
+            self.line = " ".repeat(self.indent_width());
+            self.line.push_str(word);
+        } else {
+            self.line.push(' ');
+            self.line.push_str(word);
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.line.trim().is_empty() {
+            self.lines.push(std::mem::take(&mut self.line));
+        }
+        self.line.clear();
+    }
+
+    fn finish(mut self) -> String {
+        self.end_word();
+        self.flush();
+        self.lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bf_only;
+
+    fn fmt(code: &str) -> String {
+        BrainfuckFormatter::new(code).format().expect("balanced")
+    }
+
+    #[test]
+    fn indents_loop_bodies_and_groups_moves() {
+        assert_eq!(
+            fmt("++++++++[>++++[>++>+++<<-]>+<<-]>>."),
+            "++++++++\n[\n  >++++\n  [\n    >++ >+++ <<-\n  ]\n  >+ <<-\n]\n>>."
+        );
+        assert_eq!(fmt("+++[-]>[->+<]"), "+++[-] >[->+<]");
+    }
+
+    #[test]
+    fn wraps_at_width() {
+        let options = FormatOptions { width: 10, ..FormatOptions::default() };
+        let out = BrainfuckFormatter::with_options(&"+>".repeat(8), options).format().unwrap();
+        assert_eq!(out, "+ >+ >+ >+\n>+ >+ >+\n>+ >");
+        assert!(out.lines().all(|l| l.len() <= 10));
+
+        let long = BrainfuckFormatter::with_options(&"+".repeat(25), options).format().unwrap();
+        assert_eq!(long, "++++++++++\n++++++++++\n+++++");
+    }
+
+    #[test]
+    fn width_counts_characters() {
+        // Each word is 4 characters but 8 bytes; two fit on a 9-column line
+        let options = FormatOptions { width: 9, ..FormatOptions::default() };
+        let out = BrainfuckFormatter::with_options("äöüß äöüß äöüß", options).format().unwrap();
+        assert_eq!(out, "äöüß äöüß\näöüß");
+    }
+
+    #[test]
+    fn keeps_comments_in_place() {
+        let src = "print H\n+++ set up [ loop start\n  - decrement\n] done\n.";
+        let out = fmt(src);
+        assert_eq!(out, "print H\n+++ set up\n[ loop start\n  - decrement\n] done\n.");
+        assert_eq!(bf_only(&out), bf_only(src));
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let options = FormatOptions { width: 16, ..FormatOptions::default() };
+        let src = "a header comment that is long\n++[>+++ inline words that wrap <-]>.[-]>,[.,]";
+        let once = BrainfuckFormatter::with_options(src, options).format().unwrap();
+        let twice = BrainfuckFormatter::with_options(&once, options).format().unwrap();
+        assert_eq!(once, twice);
+        assert_eq!(bf_only(&once), bf_only(src));
+    }
+
+    #[test]
+    fn unmatched_brackets_are_errors() {
+        let err = BrainfuckFormatter::new("x]").format().unwrap_err();
+        assert!(matches!(err, BrainfuckReaderError::UnmatchedBrackets { ip: 1, kind: UnmatchedBracketKind::Close }));
+        let err = BrainfuckFormatter::new("[").format().unwrap_err();
+        assert!(matches!(err, BrainfuckReaderError::UnmatchedBrackets { ip: 0, kind: UnmatchedBracketKind::Open }));
+    }
+}
@@ -0,0 +1,548 @@
+//! A small Brainfuck minifier ("golfer").
+//!
+//! Produces a semantically equivalent, usually shorter program for `BrainfuckReader`
+//! (8-bit wrapping cells, tape starting at zero). A program that moves the pointer off
+//! the tape still fails the same way once golfed.
+//!
+//! Transformations:
+//! - Comments (non-command characters) are stripped.
+//! - Adjacent `+`/`-` and `<`/`>` runs are merged, cancelling opposite pairs. Moves are
+//!   only merged when every cell they pass is known to be on the tape (or on the way to
+//!   where they end), so `<>` at program start is kept.
+//! - Loops that can never execute are removed: a `[` at program start, directly after `]`,
+//!   or anywhere else the current cell is known to be zero.
+//! - Segments that build a known constant are re-encoded with the writer's delta and
+//!   loop-multiplication encodings when that is shorter.
+//! - Trailing cell changes, and trailing pointer moves that stay on the tape, are dropped.
+//!
+//! Quick start:
+//!
+//! ```no_run
+//! use rust_bf::BrainfuckGolfer;
+//!
+//! let golfer = BrainfuckGolfer::new("[comment] +++++ +++++ - > < .");
+//! let golfed = golfer.golf().unwrap();
+//! assert_eq!(golfed, "+++++++++.");
+//! ```
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+use crate::reader::StepControl;
+use crate::{bf_only, BrainfuckReader, BrainfuckReaderError, BrainfuckWriter, UnmatchedBracketKind};
+
+/// Step limit per sample when verifying; samples hitting it are skipped, not failed.
+const VERIFY_MAX_STEPS: usize = 10_000_000;
+
+/// Tape size assumed when deciding whether the cell right of the pointer exists.
+const DEFAULT_MEMORY: isize = 30_000;
+
+/// Errors reported by [`BrainfuckGolfer::verify`].
+#[derive(Debug, thiserror::Error)]
+pub enum GolfVerifyError {
+    /// Both programs ran to the same outcome but printed different bytes.
+    #[error("output differs on sample {sample} (original printed {} bytes, golfed printed {} bytes)", original.len(), golfed.len())]
+    OutputMismatch { sample: usize, original: Vec<u8>, golfed: Vec<u8> },
+
+    /// One program finished while the other failed, or they failed differently.
+    #[error("outcome differs on sample {sample}: original {original}, golfed {golfed}")]
+    OutcomeMismatch { sample: usize, original: String, golfed: String },
+
+    /// The original program itself could not be parsed.
+    #[error("original program is invalid: {0}")]
+    Invalid(#[from] BrainfuckReaderError),
+}
+
+/// Summary of a successful verification run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GolfVerifyReport {
+    /// Samples where both programs produced identical output and outcome.
+    pub checked: usize,
+    /// Samples skipped because the original did not finish within the step limit.
+    pub skipped: usize,
+}
+
+/// Sample inputs used by `bf golf --verify` when none are given.
+pub fn default_verify_samples() -> Vec<Vec<u8>> {
+    vec![
+        Vec::new(),
+        b"Hello, World!\n".to_vec(),
+        (1..=255u8).collect(),
+    ]
+}
+
+pub struct BrainfuckGolfer {
+    code: String,
+}
+
+impl BrainfuckGolfer {
+    pub fn new(code: &str) -> Self {
+        Self { code: code.to_string() }
+    }
+
+    /// Produce the minified program.
+    ///
+    /// Unmatched brackets are reported with `ip` indexing into the original source.
+    pub fn golf(&self) -> Result<String, BrainfuckReaderError> {
+        let ops = parse(&self.code)?;
+
+        let mut ops = optimize(ops, &mut Facts::start());
+        drop_trailing(&mut ops);
+
+        let writer = BrainfuckWriter::new(&[]);
+        let mut out = String::new();
+        emit(&ops, &mut Facts::start(), &writer, &mut out);
+        Ok(out)
+    }
+
+    /// Run the original and `golfed` programs on each sample input and compare results.
+    pub fn verify(&self, golfed: &str, samples: &[Vec<u8>]) -> Result<GolfVerifyReport, GolfVerifyError> {
+        parse(&self.code)?;
+        let original = bf_only(&self.code);
+
+        let mut report = GolfVerifyReport { checked: 0, skipped: 0 };
+        for (sample, input) in samples.iter().enumerate() {
+            let (original_out, original_res) = run_sample(&original, input);
+            if matches!(original_res, Err(BrainfuckReaderError::StepLimitExceeded { .. })) {
+                report.skipped += 1;
+                continue;
+            }
+            let (golfed_out, golfed_res) = run_sample(golfed, input);
+
+            let original_outcome = describe_outcome(&original_res);
+            let golfed_outcome = describe_outcome(&golfed_res);
+            if original_outcome != golfed_outcome {
+                return Err(GolfVerifyError::OutcomeMismatch {
+                    sample,
+                    original: original_outcome,
+                    golfed: golfed_outcome,
+                });
+            }
+            if original_out != golfed_out {
+                return Err(GolfVerifyError::OutputMismatch {
+                    sample,
+                    original: original_out,
+                    golfed: golfed_out,
+                });
+            }
+            report.checked += 1;
+        }
+        Ok(report)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Op {
+    /// Wrapping change to the current cell (never zero).
+    Add(u8),
+    /// Pointer movement (never zero).
+    Move(isize),
+    Output,
+    Input,
+    Loop(Vec<Op>),
+}
+
+fn push_add(ops: &mut Vec<Op>, delta: u8) {
+    if let Some(Op::Add(d)) = ops.last_mut() {
+        *d = d.wrapping_add(delta);
+        if *d == 0 {
+            ops.pop();
+        }
+    } else if delta != 0 {
+        ops.push(Op::Add(delta));
+    }
+}
+
+fn parse(code: &str) -> Result<Vec<Op>, BrainfuckReaderError> {
+    // Each open loop keeps its '[' position and the ops preceding it
+    let mut stack: Vec<(usize, Vec<Op>)> = Vec::new();
+    let mut current: Vec<Op> = Vec::new();
+
+    for (ip, ch) in code.chars().enumerate() {
+        match ch {
+            '+' => push_add(&mut current, 1),
+            '-' => push_add(&mut current, u8::MAX),
+            // Merged by `optimize`, which knows whether that is safe
+            '>' => current.push(Op::Move(1)),
+            '<' => current.push(Op::Move(-1)),
+            '.' => current.push(Op::Output),
+            ',' => current.push(Op::Input),
+            '[' => stack.push((ip, std::mem::take(&mut current))),
+            ']' => {
+                let Some((_, outer)) = stack.pop() else {
+                    return Err(BrainfuckReaderError::UnmatchedBrackets {
+                        ip,
+                        kind: UnmatchedBracketKind::Close,
+                    });
+                };
+                let body = std::mem::replace(&mut current, outer);
+                current.push(Op::Loop(body));
+            }
+            _ => {} // comment
+        }
+    }
+
+    if let Some((ip, _)) = stack.last() {
+        return Err(BrainfuckReaderError::UnmatchedBrackets {
+            ip: *ip,
+            kind: UnmatchedBracketKind::Open,
+        });
+    }
+    Ok(current)
+}
+
+/// What is statically known about the tape at a point in the program.
+///
+/// Offsets are relative to an origin; while `default` is `Some(0)` we are still tracking
+/// from program start, so the origin is cell 0 and untouched cells are known zero.
+#[derive(Clone)]
+struct Facts {
+    ptr: isize,
+    cells: HashMap<isize, Option<u8>>,
+    default: Option<u8>,
+    /// Lowest and highest cell the pointer has reached, so known to be on the tape.
+    reached: (isize, isize),
+}
+
+impl Facts {
+    fn start() -> Self {
+        Self { ptr: 0, cells: HashMap::new(), default: Some(0), reached: (0, 0) }
+    }
+
+    fn unknown() -> Self {
+        Self { ptr: 0, cells: HashMap::new(), default: None, reached: (0, 0) }
+    }
+
+    fn moved(&mut self, delta: isize) {
+        self.ptr += delta;
+        self.reached = (self.reached.0.min(self.ptr), self.reached.1.max(self.ptr));
+    }
+
+    /// Cells known to exist: the whole tape from program start, else those reached.
+    fn on_tape(&self) -> (isize, isize) {
+        if self.is_absolute() { (0, DEFAULT_MEMORY - 1) } else { self.reached }
+    }
+
+    /// Follow the pointer through already optimized `ops`.
+    fn follow(&mut self, ops: &[Op]) {
+        for op in ops {
+            match op {
+                Op::Move(n) => self.moved(*n),
+                Op::Loop(body) => self.after_loop(body),
+                Op::Add(_) | Op::Output | Op::Input => {}
+            }
+        }
+    }
+
+    fn get(&self, offset: isize) -> Option<u8> {
+        self.cells.get(&offset).copied().unwrap_or(self.default)
+    }
+
+    fn set(&mut self, offset: isize, value: Option<u8>) {
+        self.cells.insert(offset, value);
+    }
+
+    fn is_absolute(&self) -> bool {
+        self.default.is_some()
+    }
+
+    /// Facts valid at the start of every iteration of `body`.
+    fn loop_entry(&self, body: &[Op]) -> Facts {
+        match touched_offsets(body) {
+            Some(touched) => {
+                let mut inner = self.clone();
+                for off in touched {
+                    inner.set(self.ptr + off, None);
+                }
+                inner
+            }
+            None => Facts::unknown(),
+        }
+    }
+
+    /// Update facts for having run (or skipped) a loop over `body`.
+    fn after_loop(&mut self, body: &[Op]) {
+        match touched_offsets(body) {
+            Some(touched) => {
+                for off in touched {
+                    self.set(self.ptr + off, None);
+                }
+            }
+            None => *self = Facts::unknown(),
+        }
+        // A loop only exits when the current cell is zero
+        self.set(self.ptr, Some(0));
+    }
+}
+
+/// Cell offsets a loop body may modify, or `None` if the body moves the pointer by a
+/// non-zero net amount (so nothing can be said about where it ends up).
+fn touched_offsets(body: &[Op]) -> Option<HashSet<isize>> {
+    let mut touched = HashSet::new();
+    let mut offset = 0isize;
+    for op in body {
+        match op {
+            Op::Add(_) | Op::Input => {
+                touched.insert(offset);
+            }
+            Op::Move(n) => offset += n,
+            Op::Output => {}
+            Op::Loop(inner) => {
+                for off in touched_offsets(inner)? {
+                    touched.insert(offset + off);
+                }
+            }
+        }
+    }
+    if offset == 0 { Some(touched) } else { None }
+}
+
+/// Moves emitted as one `Op::Move`: the cells they pass, and the cells known to be on
+/// the tape before the first of them.
+struct MoveRun {
+    low: isize,
+    high: isize,
+    on_tape: (isize, isize),
+}
+
+impl MoveRun {
+    /// Whether the run can be extended to end at `to`: a direct move there then fails
+    /// exactly when the original moves would, as every cell they pass is on the tape or
+    /// on the way to `to`.
+    fn extends_to(&self, to: isize) -> bool {
+        self.low.min(to) >= self.on_tape.0.min(to) && self.high.max(to) <= self.on_tape.1.max(to)
+    }
+}
+
+/// Remove dead loops and merge runs, including the runs on either side of a dead loop.
+fn optimize(ops: Vec<Op>, facts: &mut Facts) -> Vec<Op> {
+    let mut out = Vec::with_capacity(ops.len());
+    let mut run: Option<MoveRun> = None;
+    for op in ops {
+        match op {
+            Op::Add(d) => {
+                let cur = facts.get(facts.ptr);
+                facts.set(facts.ptr, cur.map(|v| v.wrapping_add(d)));
+                push_add(&mut out, d);
+            }
+            Op::Move(n) => {
+                let on_tape = facts.on_tape();
+                let from = facts.ptr;
+                facts.moved(n);
+                let to = facts.ptr;
+                if let Some(current) = run.as_mut()
+                    && current.extends_to(to)
+                    && let Some(Op::Move(d)) = out.last_mut()
+                {
+                    current.low = current.low.min(to);
+                    current.high = current.high.max(to);
+                    *d += n;
+                    if *d == 0 {
+                        out.pop();
+                        run = None;
+                    }
+                } else {
+                    run = Some(MoveRun { low: from.min(to), high: from.max(to), on_tape });
+                    out.push(Op::Move(n));
+                }
+                continue;
+            }
+            Op::Output => out.push(Op::Output),
+            Op::Input => {
+                facts.set(facts.ptr, None);
+                out.push(Op::Input);
+            }
+            Op::Loop(body) => {
+                if facts.get(facts.ptr) == Some(0) {
+                    continue; // never entered
+                }
+                let mut entry = facts.loop_entry(&body);
+                let body = optimize(body, &mut entry);
+                facts.after_loop(&body);
+                out.push(Op::Loop(body));
+            }
+        }
+        if !matches!(out.last(), Some(Op::Move(_))) {
+            run = None;
+        }
+    }
+    out
+}
+
+/// Drop the trailing cell changes, and the trailing moves too unless one could leave the
+/// tape: the original would then fail there.
+fn drop_trailing(ops: &mut Vec<Op>) {
+    let tail = ops.iter().rposition(|op| !matches!(op, Op::Add(_) | Op::Move(_))).map_or(0, |i| i + 1);
+    let mut facts = Facts::start();
+    facts.follow(&ops[..tail]);
+    let on_tape = facts.on_tape();
+    let (mut ptr, mut low, mut high) = (facts.ptr, facts.ptr, facts.ptr);
+    for op in &ops[tail..] {
+        if let Op::Move(n) = op {
+            ptr += n;
+            low = low.min(ptr);
+            high = high.max(ptr);
+        }
+    }
+    if low >= on_tape.0 && high <= on_tape.1 {
+        ops.truncate(tail);
+    }
+    while matches!(ops.last(), Some(Op::Add(_))) {
+        ops.pop();
+    }
+}
+
+fn emit(ops: &[Op], facts: &mut Facts, writer: &BrainfuckWriter, out: &mut String) {
+    for op in ops {
+        match op {
+            Op::Add(d) => {
+                let prior = facts.get(facts.ptr);
+                out.push_str(&encode_add(*d, prior, facts, writer));
+                facts.set(facts.ptr, prior.map(|v| v.wrapping_add(*d)));
+            }
+            Op::Move(n) => {
+                let step = if *n > 0 { ">" } else { "<" };
+                out.push_str(&step.repeat(n.unsigned_abs()));
+                facts.ptr += n;
+            }
+            Op::Output => out.push('.'),
+            Op::Input => {
+                out.push(',');
+                facts.set(facts.ptr, None);
+            }
+            Op::Loop(body) => {
+                out.push('[');
+                emit(body, &mut facts.loop_entry(body), writer, out);
+                out.push(']');
+                facts.after_loop(body);
+            }
+        }
+    }
+}
+
+/// Shortest encoding of a wrapping `delta` to the current cell.
+///
+/// When the resulting value is known and the cell to the right is known to be zero,
+/// the writer's loop multiplication is considered as well.
+fn encode_add(delta: u8, prior: Option<u8>, facts: &Facts, writer: &BrainfuckWriter) -> String {
+    let mut best = writer.encode_delta(0, delta);
+
+    let temp = facts.ptr + 1;
+    let temp_is_free = facts.is_absolute() && temp < DEFAULT_MEMORY && facts.get(temp) == Some(0);
+    if let (Some(prior), true) = (prior, temp_is_free) {
+        let target = prior.wrapping_add(delta);
+        if let Some(product) = writer.encode_product(target) {
+            let candidate = if prior == 0 { product } else { format!("[-]{product}") };
+            if candidate.len() < best.len() {
+                best = candidate;
+            }
+        }
+    }
+    best
+}
+
+fn run_sample(code: &str, input: &[u8]) -> (Vec<u8>, Result<(), BrainfuckReaderError>) {
+    let pending = Arc::new(Mutex::new(input.iter().copied().collect::<VecDeque<u8>>()));
+    let output = Arc::new(Mutex::new(Vec::new()));
+
+    let mut bf = BrainfuckReader::new(code.to_string());
+    bf.set_input_provider(move || pending.lock().expect("input mutex poisoned").pop_front());
+    let sink = output.clone();
+    bf.set_output_sink(move |bytes: &[u8]| sink.lock().expect("output mutex poisoned").extend_from_slice(bytes));
+
+    let ctrl = StepControl::new(Some(VERIFY_MAX_STEPS), Arc::new(AtomicBool::new(false)));
+    let res = bf.run_with_control(ctrl);
+    let out = output.lock().expect("output mutex poisoned").clone();
+    (out, res)
+}
+
+fn describe_outcome(res: &Result<(), BrainfuckReaderError>) -> String {
+    match res {
+        Ok(()) => "finished".to_string(),
+        Err(BrainfuckReaderError::PointerOutOfBounds { .. }) => "pointer out of bounds".to_string(),
+        Err(BrainfuckReaderError::StepLimitExceeded { .. }) => "step limit exceeded".to_string(),
+        Err(other) => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn golf(code: &str) -> String {
+        BrainfuckGolfer::new(code).golf().expect("valid program")
+    }
+
+    #[test]
+    fn strips_comments_and_cancels_pairs() {
+        assert_eq!(golf("add two: ++ +- then move ><> and print ."), "++>.");
+        assert_eq!(golf("+-><"), "");
+    }
+
+    #[test]
+    fn removes_loops_that_never_run() {
+        assert_eq!(golf("[this is a comment loop, with . and ,]+."), "+.");
+        assert_eq!(golf("+[-][>+<-].>[+.]"), "+[-].");
+        // A loop with an unbalanced body loses track of the tape, but not of the exit cell
+        assert_eq!(golf("+[>]+[<]."), "+[>]+[<].");
+        assert_eq!(golf("+[>][<]."), "+[>].");
+    }
+
+    #[test]
+    fn reencodes_constants() {
+        let code = format!("{}.", "+".repeat(72));
+        let golfed = golf(&code);
+        assert!(golfed.len() < code.len(), "expected shorter than {}, got {golfed}", code.len());
+
+        // Wrapping delta is shorter than counting up
+        assert_eq!(golf(&format!(",[-]{}.", "+".repeat(250))), ",[-]------.");
+        let golfer = BrainfuckGolfer::new(&code);
+        let report = golfer.verify(&golfed, &default_verify_samples()).unwrap();
+        assert_eq!(report.checked, 3);
+    }
+
+    #[test]
+    fn drops_trailing_effects() {
+        assert_eq!(golf(",.>>+++<"), ",.");
+    }
+
+    #[test]
+    fn out_of_bounds_moves_keep_their_error() {
+        // The original fails on its first '<', so the golfed one must too
+        let golfed = golf("<>+.");
+        assert_eq!(golfed, "<>+.");
+        let report = BrainfuckGolfer::new("<>+.").verify(&golfed, &[Vec::new()]).unwrap();
+        assert_eq!(report.checked, 1);
+        assert_eq!(golf("+.<<>"), "+.<<>");
+        assert_eq!(golf("+.><<"), "+.<");
+        // Cells already reached are on the tape, even after the position is lost
+        assert_eq!(golf(",[>]>.<>."), ",[>]>..");
+        assert_eq!(golf(",[>]>><<."), ",[>]>><<.");
+    }
+
+    #[test]
+    fn verify_catches_mismatch() {
+        let golfer = BrainfuckGolfer::new(",[.,]");
+        let err = golfer.verify(",.", &[b"ab".to_vec()]).unwrap_err();
+        assert!(matches!(err, GolfVerifyError::OutputMismatch { sample: 0, .. }));
+    }
+
+    #[test]
+    fn verify_roundtrips_writer_output() {
+        let input = b"Hello World!\n";
+        let code = BrainfuckWriter::new(input).generate().unwrap();
+        let golfer = BrainfuckGolfer::new(&code);
+        let golfed = golfer.golf().unwrap();
+        assert!(golfed.len() <= code.len());
+        assert!(golfer.verify(&golfed, &default_verify_samples()).is_ok());
+    }
+
+    #[test]
+    fn unmatched_brackets_report_original_position() {
+        let err = BrainfuckGolfer::new("ab]").golf().unwrap_err();
+        assert!(matches!(err, BrainfuckReaderError::UnmatchedBrackets { ip: 2, kind: UnmatchedBracketKind::Close }));
+        let err = BrainfuckGolfer::new("[[]").golf().unwrap_err();
+        assert!(matches!(err, BrainfuckReaderError::UnmatchedBrackets { ip: 0, kind: UnmatchedBracketKind::Open }));
+    }
+}
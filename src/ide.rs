@@ -1,7 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::{self};
 use std::sync::{mpsc, Arc, Mutex};
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{fs, thread};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
@@ -15,10 +15,12 @@ use crossterm::{
 };
 use ratatui::prelude::*;
 use ratatui::{backend::CrosstermBackend, layout::{Constraint, Direction, Layout, Rect}, style::{Color, Modifier, Style}, text::{Line, Span}, widgets::{Block, Borders, Paragraph, Wrap}, Frame, Terminal};
-use ratatui::widgets::{Cell, Clear, Row, Table};
-use crate::{BrainfuckReader, BrainfuckReaderError, bf_only};
+use ratatui::widgets::Clear;
+use crate::{BrainfuckFormatter, BrainfuckReader, BrainfuckReaderError, EofBehavior, bf_only};
 use crate::reader::StepControl;
 use crate::config::colors;
+use crate::repl::ReplSettings;
+use crate::cli_util::{parse_key_list, KeyName, KeySpec};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum Focus {
@@ -38,17 +40,47 @@ enum OutputMode {
 enum ViMode {
     Insert,
     Normal,
+    Visual,
+    VisualLine,
+}
+
+// How a selection extends from its anchor to the cursor
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum SelectionKind {
+    // Shift+arrows: the cursor is a boundary between characters
+    Exclusive,
+    // Vi visual mode: the character under the cursor is included
+    Inclusive,
+    // Vi visual line mode: whole lines
+    Lines,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Selection {
+    anchor: (usize, usize),
+    kind: SelectionKind,
+}
+
+// Yanked or cut text; linewise text is pasted as whole lines
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Register {
+    text: String,
+    linewise: bool,
 }
 
 // Runner wiring: messages and commands between UI and runner
 #[derive(Debug)]
 enum RunnerMsg {
-    // Program produced output bytes (batch as needed)
+    // Output bytes collected since the last batch
     Output(Vec<u8>),
-    // Snapshot of current tape state (ptr index and 128-cell window)
-    Tape { ptr: usize, base: usize, window: [u8; 128] },
+    // Cells `start..start + cells.len()` and the data pointer: the window the tape pane shows
+    // when the UI asks for a snapshot or the program is paused, and the whole tape on halt
+    Tape { ptr: usize, start: usize, cells: Vec<u8> },
     // Runner is awaiting input for `,` instruction
     NeedsInput,
+    // Runner stopped between instructions after a pause request; cells may be edited
+    Paused,
+    Resumed,
     // Program finished (Ok) or errored
     Halted(Result<(), BrainfuckReaderError>),
 }
@@ -59,6 +91,261 @@ enum UiCmd {
     ProvideInput(Option<u8>),
     // Request to stop the program
     Stop,
+    // Continue a paused program
+    Resume,
+    // Change a cell while paused
+    SetCell { addr: usize, value: u8 },
+    // Send the tape pane's window again while paused, after it scrolled
+    Snapshot,
+}
+
+// How the tape pane shows cell values
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum CellFormat {
+    Hex,
+    Dec,
+    Char,
+}
+
+// What the tape pane's prompt line is asking for
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TapePrompt {
+    Goto,
+    Search,
+    Edit,
+}
+
+// Cells in the IDE's tape, the interpreter's default
+const TAPE_CELLS: usize = 30_000;
+
+// Output is sent early once this much is waiting, even between tape snapshots
+const OUTPUT_BATCH: usize = 64 * 1024;
+
+// Named actions: bound to keys by the keymap and listed in the command palette
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Command {
+    Run,
+    Stop,
+    Pause,
+    Settings,
+    PreparedInput,
+    Palette,
+    Help,
+    Quit,
+    NewBuffer,
+    Open,
+    Save,
+    CloseBuffer,
+    NextBuffer,
+    PreviousBuffer,
+    Find,
+    FindNext,
+    FindPrevious,
+    Format,
+    ToggleOutputMode,
+    ToggleLineNumbers,
+    MatchBracket,
+    Undo,
+    Redo,
+    Copy,
+    Cut,
+    Paste,
+    SelectAll,
+    FocusNext,
+    FocusPrevious,
+    // Alt+1..9: the buffer at this index
+    GoToBuffer(usize),
+    TapeGoto,
+    TapeFind,
+    TapeFindNext,
+    TapeFindPrevious,
+    TapeNextNonzero,
+    TapePreviousNonzero,
+    TapeFormat,
+    TapeFollowPointer,
+}
+
+// Where a command's keys work; the palette runs any command from anywhere
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Scope {
+    // Whatever has focus, once no dialog is open
+    Global,
+    // The editor has focus
+    Editor,
+    // The editor has focus and takes text (not in Vi normal or visual mode)
+    EditorText,
+    // The tape pane has focus
+    Tape,
+}
+
+struct CommandInfo {
+    command: Command,
+    // Key of the command in the [ide.keys] table of bf.toml
+    name: &'static str,
+    title: &'static str,
+    keys: &'static str,
+    scope: Scope,
+}
+
+const COMMANDS: [CommandInfo; 46] = [
+    CommandInfo { command: Command::Run, name: "run", title: "Run the program (or the selection)", keys: "f5, ctrl+r", scope: Scope::Global },
+    CommandInfo { command: Command::Stop, name: "stop", title: "Stop the running program", keys: "ctrl+., shift+f5", scope: Scope::Global },
+    CommandInfo { command: Command::Pause, name: "pause", title: "Pause or resume the running program", keys: "f6", scope: Scope::Global },
+    CommandInfo { command: Command::Settings, name: "settings", title: "Settings (limits, memory, EOF, tape)", keys: "f2", scope: Scope::Global },
+    CommandInfo { command: Command::PreparedInput, name: "prepared-input", title: "Edit prepared input", keys: "ctrl+d", scope: Scope::Global },
+    CommandInfo { command: Command::Palette, name: "command-palette", title: "Command palette", keys: "ctrl+shift+p, f4", scope: Scope::Global },
+    CommandInfo { command: Command::Help, name: "help", title: "Toggle help", keys: "f1, ctrl+h", scope: Scope::Global },
+    CommandInfo { command: Command::Quit, name: "quit", title: "Quit", keys: "ctrl+q", scope: Scope::Global },
+    CommandInfo { command: Command::NewBuffer, name: "new-buffer", title: "New buffer", keys: "ctrl+n", scope: Scope::Global },
+    CommandInfo { command: Command::Open, name: "open", title: "Open file", keys: "ctrl+o", scope: Scope::Global },
+    CommandInfo { command: Command::Save, name: "save", title: "Save", keys: "ctrl+s", scope: Scope::Global },
+    CommandInfo { command: Command::CloseBuffer, name: "close-buffer", title: "Close buffer", keys: "ctrl+w", scope: Scope::Global },
+    CommandInfo { command: Command::NextBuffer, name: "next-buffer", title: "Next buffer", keys: "ctrl+pagedown", scope: Scope::Global },
+    CommandInfo { command: Command::PreviousBuffer, name: "previous-buffer", title: "Previous buffer", keys: "ctrl+pageup", scope: Scope::Global },
+    CommandInfo { command: Command::Find, name: "find", title: "Find / replace", keys: "ctrl+f", scope: Scope::Editor },
+    CommandInfo { command: Command::FindNext, name: "find-next", title: "Find next match", keys: "f3", scope: Scope::Editor },
+    CommandInfo { command: Command::FindPrevious, name: "find-previous", title: "Find previous match", keys: "shift+f3", scope: Scope::Editor },
+    CommandInfo { command: Command::Format, name: "format", title: "Format buffer (or the selected lines)", keys: "ctrl+t", scope: Scope::Global },
+    CommandInfo { command: Command::ToggleOutputMode, name: "toggle-output-mode", title: "Toggle output mode (raw / escaped)", keys: "ctrl+e", scope: Scope::Global },
+    CommandInfo { command: Command::ToggleLineNumbers, name: "toggle-line-numbers", title: "Toggle line numbers", keys: "ctrl+l", scope: Scope::EditorText },
+    CommandInfo { command: Command::MatchBracket, name: "match-bracket", title: "Jump to matching bracket", keys: "ctrl+p", scope: Scope::Editor },
+    CommandInfo { command: Command::Undo, name: "undo", title: "Undo", keys: "ctrl+z", scope: Scope::EditorText },
+    CommandInfo { command: Command::Redo, name: "redo", title: "Redo", keys: "ctrl+y", scope: Scope::EditorText },
+    CommandInfo { command: Command::Copy, name: "copy", title: "Copy selection", keys: "ctrl+c", scope: Scope::EditorText },
+    CommandInfo { command: Command::Cut, name: "cut", title: "Cut selection", keys: "ctrl+x", scope: Scope::EditorText },
+    CommandInfo { command: Command::Paste, name: "paste", title: "Paste", keys: "ctrl+v", scope: Scope::EditorText },
+    CommandInfo { command: Command::SelectAll, name: "select-all", title: "Select all", keys: "ctrl+a", scope: Scope::EditorText },
+    CommandInfo { command: Command::FocusNext, name: "focus-next", title: "Focus next pane", keys: "tab", scope: Scope::Global },
+    CommandInfo { command: Command::FocusPrevious, name: "focus-previous", title: "Focus previous pane", keys: "shift+tab", scope: Scope::Global },
+    CommandInfo { command: Command::GoToBuffer(0), name: "buffer-1", title: "Go to buffer 1", keys: "alt+1", scope: Scope::Global },
+    CommandInfo { command: Command::GoToBuffer(1), name: "buffer-2", title: "Go to buffer 2", keys: "alt+2", scope: Scope::Global },
+    CommandInfo { command: Command::GoToBuffer(2), name: "buffer-3", title: "Go to buffer 3", keys: "alt+3", scope: Scope::Global },
+    CommandInfo { command: Command::GoToBuffer(3), name: "buffer-4", title: "Go to buffer 4", keys: "alt+4", scope: Scope::Global },
+    CommandInfo { command: Command::GoToBuffer(4), name: "buffer-5", title: "Go to buffer 5", keys: "alt+5", scope: Scope::Global },
+    CommandInfo { command: Command::GoToBuffer(5), name: "buffer-6", title: "Go to buffer 6", keys: "alt+6", scope: Scope::Global },
+    CommandInfo { command: Command::GoToBuffer(6), name: "buffer-7", title: "Go to buffer 7", keys: "alt+7", scope: Scope::Global },
+    CommandInfo { command: Command::GoToBuffer(7), name: "buffer-8", title: "Go to buffer 8", keys: "alt+8", scope: Scope::Global },
+    CommandInfo { command: Command::GoToBuffer(8), name: "buffer-9", title: "Go to buffer 9", keys: "alt+9", scope: Scope::Global },
+    CommandInfo { command: Command::TapeGoto, name: "tape-goto", title: "Tape: go to address", keys: "g", scope: Scope::Tape },
+    CommandInfo { command: Command::TapeFind, name: "tape-find", title: "Tape: find value", keys: "/", scope: Scope::Tape },
+    CommandInfo { command: Command::TapeFindNext, name: "tape-find-next", title: "Tape: next cell with the value", keys: "n", scope: Scope::Tape },
+    CommandInfo { command: Command::TapeFindPrevious, name: "tape-find-previous", title: "Tape: previous cell with the value", keys: "shift+n", scope: Scope::Tape },
+    CommandInfo { command: Command::TapeNextNonzero, name: "tape-next-nonzero", title: "Tape: next non-zero region", keys: "z", scope: Scope::Tape },
+    CommandInfo { command: Command::TapePreviousNonzero, name: "tape-previous-nonzero", title: "Tape: previous non-zero region", keys: "shift+z", scope: Scope::Tape },
+    CommandInfo { command: Command::TapeFormat, name: "tape-format", title: "Tape: cycle hex / dec / char", keys: "f", scope: Scope::Tape },
+    CommandInfo { command: Command::TapeFollowPointer, name: "tape-follow-pointer", title: "Tape: follow the data pointer", keys: "c", scope: Scope::Tape },
+];
+
+fn command_info(command: Command) -> &'static CommandInfo {
+    COMMANDS.iter().find(|info| info.command == command).expect("every command is listed")
+}
+
+// Keys for every command: the defaults, with commands listed in [ide.keys] replaced
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Keymap {
+    bindings: Vec<(Command, Vec<KeySpec>)>,
+    // Commands given keys by [ide.keys], in the order they were set
+    remapped: Vec<Command>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = COMMANDS
+            .iter()
+            .map(|info| (info.command, parse_key_list(info.keys).expect("default keys are valid")))
+            .collect();
+        Self { bindings, remapped: Vec::new() }
+    }
+}
+
+impl Keymap {
+    // Replace the keys of the command called `name`; an empty list unbinds it. The keys are
+    // taken away from any command that has them by default, but not from one remapped earlier
+    fn set(&mut self, name: &str, keys: &str) -> Result<(), String> {
+        let parsed = parse_key_list(keys)?;
+        let Some(info) = COMMANDS.iter().find(|info| info.name == name) else {
+            return Err(format!("unknown command '{name}'"));
+        };
+        for key in &parsed {
+            if let Some((other, _)) = self.bindings.iter().find(|(command, bound)| {
+                *command != info.command && self.remapped.contains(command) && bound.contains(key)
+            }) {
+                return Err(format!("{key} is already bound to '{}'", command_info(*other).name));
+            }
+        }
+        for (command, bound) in &mut self.bindings {
+            if *command == info.command {
+                *bound = parsed.clone();
+            } else {
+                bound.retain(|key| !parsed.contains(key));
+            }
+        }
+        if !self.remapped.contains(&info.command) {
+            self.remapped.push(info.command);
+        }
+        Ok(())
+    }
+
+    fn keys(&self, command: Command) -> &[KeySpec] {
+        self.bindings.iter().find(|(c, _)| *c == command).map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    // The first command bound to `key` whose scope applies right now
+    fn lookup(&self, key: KeySpec, applies: impl Fn(Scope) -> bool) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|(command, keys)| keys.contains(&key) && applies(command_info(*command).scope))
+            .map(|(command, _)| *command)
+    }
+}
+
+// Which field of the search bar receives typing
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum SearchField {
+    Find,
+    Replace,
+}
+
+// A search hit as the (row, col) of each matched character, in order. Plain matches are
+// contiguous within a line; code-only matches skip comments and may span lines
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct SearchMatch {
+    cells: Vec<(usize, usize)>,
+}
+
+// How an edit joins undo groups: consecutive edits of the same kind that continue where the
+// last one left the cursor are undone together; `Other` edits always stand alone
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum EditKind {
+    Insert,
+    Delete,
+    Other,
+}
+
+// One buffer change: lines `row..row + old.len()` were replaced by `new`
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct LineEdit {
+    row: usize,
+    old: Vec<String>,
+    new: Vec<String>,
+}
+
+// Edits undone and redone as one step, with the cursor positions around them
+#[derive(Clone, Debug)]
+struct UndoGroup {
+    edits: Vec<LineEdit>,
+    cursor_before: (usize, usize),
+    cursor_after: (usize, usize),
+}
+
+#[derive(Default, Debug)]
+struct EditHistory {
+    undo: Vec<UndoGroup>,
+    redo: Vec<UndoGroup>,
+    // Kind and cursor at the end of the newest group while later edits may still join it
+    open_group: Option<(EditKind, (usize, usize))>,
+    // Undo depth matching the saved file; None once that state can no longer be reached
+    saved_at: Option<usize>,
 }
 
 struct RunnerHandle {
@@ -68,9 +355,97 @@ struct RunnerHandle {
     rx_msg: mpsc::Receiver<RunnerMsg>,
     // Cooperative cancellation flag (also flipped by Stop)
     cancel: Arc<AtomicBool>,
+    // Ask for a tape snapshot (and the output collected so far) after the next instruction
+    snapshot: Arc<AtomicBool>,
+    // Cells the tape pane shows (start..end); snapshots during the run carry only these
+    view: Arc<Mutex<(usize, usize)>>,
+    // Ask the runner to pause after the next instruction
+    pause: Arc<AtomicBool>,
     // Join handle is kept in worker (detached); we just hold channels and flag
 }
 
+// One row of the file browser
+#[derive(Debug, Clone, PartialEq)]
+struct BrowserEntry {
+    // Name as shown ("../" for the parent, a trailing '/' for directories); lossy for non-UTF-8 names
+    label: String,
+    path: PathBuf,
+    is_dir: bool,
+}
+
+// Ctrl+O file picker: the listing of `dir`, or the recent files, narrowed by a fuzzy filter
+#[derive(Debug, Default)]
+struct FileBrowser {
+    // Picking an input file for the prepared input panel rather than a file to edit
+    attach_input: bool,
+    dir: PathBuf,
+    entries: Vec<BrowserEntry>,
+    filter: String,
+    // Index into the visible (filtered) entries
+    selected: usize,
+    // List only .bf/.b files; directories are always listed
+    bf_only: bool,
+    show_recent: bool,
+    error: Option<String>,
+}
+
+// What `,` reads before the input dialog is used: the attached file, then the typed text
+#[derive(Debug, Default, Clone, PartialEq)]
+struct PreparedInput {
+    file: Option<PathBuf>,
+    text: String,
+}
+
+// Command palette: a fuzzy filter over the titles of all commands
+#[derive(Debug, Default)]
+struct Palette {
+    filter: String,
+    selected: usize,
+}
+
+// Settings dialog: the value of each setting as typed, applied together with Enter
+#[derive(Debug, Default)]
+struct SettingsDialog {
+    values: Vec<(&'static str, String)>,
+    selected: usize,
+    error: Option<String>,
+}
+
+// An open document. The active buffer's state lives directly on App, so the editing code
+// works on app.buffer; the others wait in App::buffers and are swapped in by switch_buffer
+struct BufferState {
+    buffer: Vec<String>,
+    cursor_row: usize,
+    cursor_col: usize,
+    scroll_row: usize,
+    filename: Option<String>,
+    dirty: bool,
+    history: EditHistory,
+    // Result of the last run of this buffer
+    output: Vec<u8>,
+    tape_ptr: usize,
+    tape: Vec<u8>,
+    input: PreparedInput,
+}
+
+impl Default for BufferState {
+    fn default() -> Self {
+        Self {
+            buffer: vec![String::new()],
+            cursor_row: 0,
+            cursor_col: 0,
+            scroll_row: 0,
+            filename: None,
+            dirty: false,
+            history: EditHistory { saved_at: Some(0), ..EditHistory::default() },
+            output: Vec::new(),
+            tape_ptr: 0,
+            tape: vec![0; TAPE_CELLS],
+            input: PreparedInput::default(),
+        }
+    }
+}
+
 pub struct App {
     // Editor
     buffer: Vec<String>,
@@ -81,10 +456,23 @@ pub struct App {
     // Output pane
     output: Vec<u8>,
 
-    // Tape pane
+    // Tape pane: the last snapshot of the active buffer's run
     tape_ptr: usize,
-    tape_window_base: usize,
-    tape_window: [u8; 128],
+    tape: Vec<u8>,
+    // Inspected cell; it tracks the data pointer while tape_follow is set
+    tape_cursor: usize,
+    tape_follow: bool,
+    tape_format: CellFormat,
+    // Columns of the last drawn tape grid, so Up/Down move by one row
+    tape_cols: std::cell::Cell<usize>,
+    // Cells of the last drawn tape grid (start..end), the window run snapshots are taken of
+    tape_view: std::cell::Cell<(usize, usize)>,
+    tape_prompt: Option<(TapePrompt, String)>,
+    tape_error: Option<String>,
+    // Value of the last tape search, repeated by n/N
+    tape_search: Option<u8>,
+    // The run is stopped between instructions (F6)
+    paused: bool,
 
     // Status
     focused: Focus,
@@ -107,21 +495,65 @@ pub struct App {
     save_name_input: String,
     save_error: Option<String>,
 
-    // Open dialog
-    show_open_dialog: bool,
-    open_name_input: String,
-    open_error: Option<String>,
+    // File browser (Ctrl+O)
+    show_file_browser: bool,
+    browser: FileBrowser,
+    // Most recently opened or saved files, newest first, and where they are kept between sessions
+    recent_files: Vec<PathBuf>,
+    recent_store: Option<PathBuf>,
 
-    // Confirm dialog (for destructive actions like "open" with unsaved changes)
+    // Confirm dialog (for destructive actions like quitting with unsaved changes)
     show_confirm_dialog: bool,
     confirm_message: String,
-    confirm_pending_open: Option<PathBuf>,
 
     // Input dialog (for `,` instruction)
     show_input_dialog: bool,
     input_buffer: String,
     input_error: Option<String>,
 
+    // Prepared input of the active buffer (Ctrl+D panel)
+    show_input_panel: bool,
+    prepared_input: PreparedInput,
+    // Once prepared input is used up: ask with the input dialog, or report EOF right away
+    prompt_when_exhausted: bool,
+
+    // Run settings: timeout, step limit, tape size and what `,` stores at EOF (F2 dialog)
+    settings: ReplSettings,
+    // Start each run from the buffer's last tape and pointer instead of a fresh tape
+    keep_tape: bool,
+    show_settings: bool,
+    settings_dialog: SettingsDialog,
+    // Settings changed in the dialog this session; Ctrl+S saves only these, so values that came
+    // from the environment (BF_TIMEOUT_MS, BF_MAX_STEPS) are not written to bf.toml
+    edited_settings: HashSet<&'static str>,
+
+    // Key bindings of named commands ([ide.keys]) and the palette listing them
+    keymap: Keymap,
+    show_palette: bool,
+    palette: Palette,
+
+    // Selection and yank register
+    selection: Option<Selection>,
+    register: Option<Register>,
+    // Also copy yanked text to the terminal clipboard with OSC 52 (`[ide] clipboard = "osc52"`)
+    osc52_clipboard: bool,
+
+    // Search bar (find / replace)
+    show_search: bool,
+    search_query: String,
+    replace_text: String,
+    search_field: SearchField,
+    // Match Brainfuck instructions only: comments are skipped and matches may span lines
+    search_code_only: bool,
+    // Cursor when the bar opened; Esc returns there
+    search_origin: (usize, usize),
+    // Selection when the bar opened; replace-all stays inside it
+    search_scope: Option<((usize, usize), (usize, usize))>,
+    // Keep highlighting matches after the bar closes (cleared by Esc)
+    search_highlight: bool,
+    // The previous query is shown selected when the bar opens; typing replaces it
+    search_query_selected: bool,
+
     // Line numbers toggle
     show_line_numbers: bool,
 
@@ -137,8 +569,16 @@ pub struct App {
     should_quit: bool,
     confirm_pending_quit: bool,
 
-    // New file
-    confirm_pending_new: bool,
+    // Undo/redo
+    history: EditHistory,
+
+    // Open buffers (tabs); the slot at active_buffer is a placeholder while its state is on App
+    buffers: Vec<BufferState>,
+    active_buffer: usize,
+    // Buffer whose program is running; its output and tape snapshots are routed there
+    run_buffer: usize,
+    // Close the active buffer despite unsaved changes
+    confirm_pending_close: bool,
 }
 
 impl Default for App {
@@ -150,8 +590,16 @@ impl Default for App {
             scroll_row: 0,
             output: Vec::new(),
             tape_ptr: 0,
-            tape_window_base: 0,
-            tape_window: [0u8; 128],
+            tape: vec![0; TAPE_CELLS],
+            tape_cursor: 0,
+            tape_follow: true,
+            tape_format: CellFormat::Hex,
+            tape_cols: std::cell::Cell::new(1),
+            tape_view: std::cell::Cell::new((0, 0)),
+            tape_prompt: None,
+            tape_error: None,
+            tape_search: None,
+            paused: false,
             focused: Focus::Editor,
             dirty: false,
             filename: None,
@@ -165,18 +613,46 @@ impl Default for App {
             save_name_input: String::new(),
             save_error: None,
 
-            show_open_dialog: false,
-            open_name_input: String::new(),
-            open_error: None,
+            show_file_browser: false,
+            browser: FileBrowser { bf_only: true, ..FileBrowser::default() },
+            recent_files: Vec::new(),
+            recent_store: None,
 
             show_confirm_dialog: false,
             confirm_message: String::new(),
-            confirm_pending_open: None,
 
             show_input_dialog: false,
             input_buffer: String::new(),
             input_error: None,
 
+            show_input_panel: false,
+            prepared_input: PreparedInput::default(),
+            prompt_when_exhausted: true,
+
+            settings: ReplSettings::default(),
+            keep_tape: false,
+            show_settings: false,
+            settings_dialog: SettingsDialog::default(),
+            edited_settings: HashSet::new(),
+
+            keymap: Keymap::default(),
+            show_palette: false,
+            palette: Palette::default(),
+
+            selection: None,
+            register: None,
+            osc52_clipboard: false,
+
+            show_search: false,
+            search_query: String::new(),
+            replace_text: String::new(),
+            search_field: SearchField::Find,
+            search_code_only: false,
+            search_origin: (0, 0),
+            search_scope: None,
+            search_highlight: false,
+            search_query_selected: false,
+
             show_line_numbers: true,
 
             status_message: None,
@@ -188,7 +664,12 @@ impl Default for App {
             should_quit: false,
             confirm_pending_quit: false,
 
-            confirm_pending_new: false,
+            history: EditHistory { saved_at: Some(0), ..EditHistory::default() },
+
+            buffers: vec![BufferState::default()],
+            active_buffer: 0,
+            run_buffer: 0,
+            confirm_pending_close: false,
         }
     }
 }
@@ -222,6 +703,22 @@ pub fn run_with_options(initial_file: Option<PathBuf>, vi_enabled: bool) -> io::
     res
 }
 
+// Settings and policies from the [ide] table of bf.toml; problems go to the status bar
+fn apply_ide_config(app: &mut App, ide_config: &HashMap<String, String>) {
+    app.osc52_clipboard = ide_config.get("clipboard").is_some_and(|v| v == "osc52");
+    let (settings, problems) = ReplSettings::from_table(ide_config);
+    app.settings = settings;
+    match ide_config.get("tape").map(String::as_str) {
+        None | Some("reset") => {}
+        Some("keep") => app.keep_tape = true,
+        Some(other) => set_status(app, &format!("bf.toml [ide]: invalid tape policy '{other}' (reset or keep)")),
+    }
+    if let Some(problem) = problems.first() {
+        set_status(app, &format!("bf.toml [ide]: {problem}"));
+    }
+    app.prompt_when_exhausted = ide_config.get("input-exhausted").is_none_or(|v| v != "eof");
+}
+
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     initial_file: Option<PathBuf>,
@@ -230,6 +727,18 @@ fn run_app(
     let mut app = App::default();
     app.vi_enabled = vi_enabled;
     app.vi_mode = if vi_enabled { ViMode::Normal } else { ViMode::Insert };
+    apply_ide_config(&mut app, &crate::config::read_section("ide"));
+    // Sorted, so a conflict between two entries is reported the same way every time
+    let mut remaps: Vec<_> = crate::config::read_section("ide.keys").into_iter().collect();
+    remaps.sort();
+    for (name, keys) in remaps {
+        if let Err(err) = app.keymap.set(&name, &keys) {
+            set_status(&mut app, &format!("bf.toml [ide.keys]: ignoring {name} = {keys}: {err}"));
+        }
+    }
+    app.tape = vec![0; app.settings.memory];
+    app.recent_store = crate::config::data_dir().map(|dir| dir.join("ide_recent"));
+    app.recent_files = app.recent_store.as_deref().map(load_recent).unwrap_or_default();
     let tick_rate = Duration::from_millis(33);
 
     // If an initial file was provided, attempt to open it
@@ -258,62 +767,16 @@ fn run_app(
             }
         }
 
-        let mut should_clear_runner = false;
-
-        // We store deferred actions here
-        let mut deferred_status: Option<String> = None;
-        let mut saw_halted: bool = false;
-
-        // Drain runner messages without blocking
-        if let Some(handle) = app.runner.as_mut() {
-            while let Ok(msg) = handle.rx_msg.try_recv() {
-                match msg {
-                    RunnerMsg::Output(bytes) => {
-                        app.output.extend_from_slice(&bytes);
-                    }
-                    RunnerMsg::Tape { ptr, base, window } => {
-                        app.tape_ptr = ptr;
-                        app.tape_window_base = base;
-                        app.tape_window.copy_from_slice(&window);
-                        app.dirty = true;
-                    }
-                    RunnerMsg::NeedsInput => {
-                        // Show input dialog; the runner is blocked until we respond
-                        app.show_input_dialog = true;
-                        app.input_buffer.clear();
-                        app.input_error = None;
-                        deferred_status = Some("Program requested input (auto-EOF sent)".to_string());
-                    }
-                    RunnerMsg::Halted(res) => {
-                        app.running = false;
-                        should_clear_runner = true;
-                        saw_halted = true;
-                        match res {
-                            Ok(()) => {
-                                deferred_status = Some("Program finished".to_string());
-                            }
-                            Err(e) => {
-                                deferred_status = Some(format!("Error: {}", e));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        // Now, with no active mutable borrow of app.runner, perform deferred actions
-        if let Some(msg) = deferred_status.take() {
-            set_status(&mut app, &msg);
-        }
-
-        if should_clear_runner || saw_halted {
-            // Now it's safe to clear the runner
-            app.runner = None;
-        }
+        process_runner_messages(&mut app);
 
         if app.last_tick.elapsed() >= tick_rate {
             app.last_tick = Instant::now();
 
+            // One snapshot of the visible cells and output batch per tick while the program runs
+            if let Some(h) = app.runner.as_ref() {
+                request_tape_view(h, app.tape_view.get(), app.paused);
+            }
+
             // Expire status messages after 5 seconds
             if let Some((_, since)) = app.status_message.as_ref() {
                 if since.elapsed() >= Duration::from_secs(5) {
@@ -329,6 +792,103 @@ fn run_app(
     Ok(())
 }
 
+// Point the runner's snapshots at the cells the tape pane shows. A paused runner only answers
+// commands, so it is asked directly when the pane has scrolled
+fn request_tape_view(h: &RunnerHandle, view: (usize, usize), paused: bool) {
+    let mut shared = h.view.lock().expect("tape view mutex poisoned");
+    let moved = *shared != view;
+    *shared = view;
+    if !paused {
+        h.snapshot.store(true, Ordering::Relaxed);
+    } else if moved {
+        let _ = h.tx_cmd.send(UiCmd::Snapshot);
+    }
+}
+
+// Copy a snapshot window into a tape; a window covering the whole tape replaces it
+fn apply_tape_snapshot(tape: &mut Vec<u8>, start: usize, cells: Vec<u8>) {
+    if start == 0 && cells.len() >= tape.len() {
+        *tape = cells;
+    } else if let Some(dest) = tape.get_mut(start..start + cells.len()) {
+        dest.copy_from_slice(&cells);
+    }
+}
+
+// Drain runner messages without blocking. Output and tape snapshots belong to the buffer
+// that started the run, which may no longer be the active one
+fn process_runner_messages(app: &mut App) {
+    let target = app.run_buffer;
+    let mut should_clear_runner = false;
+
+    // We store deferred actions here
+    let mut deferred_status: Option<String> = None;
+    let mut saw_halted: bool = false;
+
+    // Drain runner messages without blocking
+    if let Some(handle) = app.runner.as_mut() {
+        while let Ok(msg) = handle.rx_msg.try_recv() {
+            match msg {
+                RunnerMsg::Output(bytes) if target == app.active_buffer => {
+                    app.output.extend_from_slice(&bytes);
+                }
+                RunnerMsg::Output(bytes) => {
+                    app.buffers[target].output.extend_from_slice(&bytes);
+                }
+                RunnerMsg::Tape { ptr, start, cells } if target == app.active_buffer => {
+                    app.tape_ptr = ptr;
+                    apply_tape_snapshot(&mut app.tape, start, cells);
+                    if app.tape_follow {
+                        app.tape_cursor = ptr;
+                    }
+                }
+                RunnerMsg::Tape { ptr, start, cells } => {
+                    let buf = &mut app.buffers[target];
+                    buf.tape_ptr = ptr;
+                    apply_tape_snapshot(&mut buf.tape, start, cells);
+                }
+                RunnerMsg::Paused => {
+                    app.paused = true;
+                    deferred_status = Some("Paused: edit cells in the tape pane, F6 resumes".to_string());
+                }
+                RunnerMsg::Resumed => {
+                    app.paused = false;
+                }
+                RunnerMsg::NeedsInput => {
+                    // Show input dialog; the runner is blocked until we respond
+                    app.show_input_dialog = true;
+                    app.input_buffer.clear();
+                    app.input_error = None;
+                    deferred_status = Some("Program requested input (auto-EOF sent)".to_string());
+                }
+                RunnerMsg::Halted(res) => {
+                    app.running = false;
+                    app.paused = false;
+                    should_clear_runner = true;
+                    saw_halted = true;
+                    match res {
+                        Ok(()) => {
+                            deferred_status = Some("Program finished".to_string());
+                        }
+                        Err(e) => {
+                            deferred_status = Some(format!("Error: {}", e));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Now, with no active mutable borrow of app.runner, perform deferred actions
+    if let Some(msg) = deferred_status.take() {
+        set_status(app, &msg);
+    }
+
+    if should_clear_runner || saw_halted {
+        // Now it's safe to clear the runner
+        app.runner = None;
+    }
+}
+
 fn ui(f: &mut Frame, app: &App) {
     let size = f.area();
 
@@ -375,6 +935,9 @@ fn ui(f: &mut Frame, app: &App) {
     let output_area = left_rows[1];
 
     draw_editor(f, editor_area, app);
+    if app.show_search {
+        draw_search_bar(f, editor_area, app);
+    }
     draw_output(f, output_area, app);
     draw_tape(f, right, app);
     draw_status(f, status_bar, app);
@@ -385,8 +948,17 @@ fn ui(f: &mut Frame, app: &App) {
     if app.show_save_dialog {
         draw_save_dialog(f, size, app);
     }
-    if app.show_open_dialog {
-        draw_open_dialog(f, size, app);
+    if app.show_input_panel {
+        draw_input_panel(f, size, app);
+    }
+    if app.show_settings {
+        draw_settings_dialog(f, size, app);
+    }
+    if app.show_palette {
+        draw_palette(f, size, app);
+    }
+    if app.show_file_browser {
+        draw_file_browser(f, size, app);
     }
     if app.show_confirm_dialog {
         draw_confirm_dialog(f, size, app);
@@ -397,15 +969,35 @@ fn ui(f: &mut Frame, app: &App) {
 }
 
 fn draw_editor(f: &mut Frame, area: Rect, app: &App) {
-    let title = match app.filename.as_deref() {
-        Some(path) => format!("Editor - {}{}", path, if app.dirty { " *" } else { "" }),
-        None => format!("Editor - <untitled>{}", if app.dirty { " *" } else { "" },),
+    let title_style = Style::default().fg(if app.focused == Focus::Editor { colors().editor_title_focused } else { colors().editor_title_unfocused });
+    let title = if app.buffers.len() > 1 {
+        // One tab per buffer: "1:name*", the active one highlighted
+        let mut spans = vec![Span::styled("Editor -", title_style)];
+        for i in 0..app.buffers.len() {
+            let (filename, dirty) = if i == app.active_buffer {
+                (app.filename.as_deref(), app.dirty)
+            } else {
+                (app.buffers[i].filename.as_deref(), app.buffers[i].dirty)
+            };
+            let tab = format!(" {}:{}{} ", i + 1, buffer_label(filename), if dirty { "*" } else { "" });
+            let style = if i == app.active_buffer {
+                title_style.add_modifier(Modifier::REVERSED)
+            } else {
+                title_style
+            };
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(tab, style));
+        }
+        Line::from(spans)
+    } else {
+        let title = match app.filename.as_deref() {
+            Some(path) => format!("Editor - {}{}", path, if app.dirty { " *" } else { "" }),
+            None => format!("Editor - <untitled>{}", if app.dirty { " *" } else { "" },),
+        };
+        Line::from(Span::styled(title, title_style))
     };
     let block = Block::default()
-        .title(Span::styled(
-            title,
-            Style::default().fg(if app.focused == Focus::Editor { colors().editor_title_focused } else { colors().editor_title_unfocused }),
-        ))
+        .title(title)
         .borders(Borders::ALL);
 
     let inner = block.inner(area);
@@ -446,9 +1038,11 @@ fn draw_editor(f: &mut Frame, area: Rect, app: &App) {
     }
 
     // Prepare highlighted lines within visible window
+    let hits = search_hits(app);
+    let selected = selection_range(app);
     let mut lines: Vec<Line> = Vec::with_capacity(end.saturating_sub(start));
     for (idx, line) in app.buffer[start..end].iter().enumerate() {
-        lines.push(highlight_bf_line(line, app, start + idx));
+        lines.push(highlight_bf_line(line, app, start + idx, &hits, selected));
     }
 
     let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
@@ -503,74 +1097,83 @@ fn draw_tape(f: &mut Frame, area: Rect, app: &App) {
     } else {
         Style::default().fg(colors().tape_border_unfocused)
     };
+    let format = match app.tape_format {
+        CellFormat::Hex => "Hex",
+        CellFormat::Dec => "Dec",
+        CellFormat::Char => "Char",
+    };
     let block = Block::default()
-        .title(Line::raw("Tape (128 cells)"))
+        .title(Line::raw(format!("Tape ({} cells, {format}){}", app.tape.len(), if app.paused { " - Paused" } else { "" })))
         .borders(Borders::ALL)
         .border_style(border_style);
-
-    // Compute inner area to estimate available width/height for the table
     let inner = block.inner(area);
+    f.render_widget(block, area);
+    if app.tape.is_empty() || inner.height == 0 {
+        return;
+    }
 
-    // "Responsive" grid sizing
-    // Each cell renders like "[XX]" (4 chars). We give it width 4 and 1 column space between cells
-    let cell_content_width: u16 = 4;
-
-    let mut cols = (inner.width / cell_content_width).max(1) as usize;
-    cols = cols.min(128);
-    if cols == 0 { cols = 1; }
-
-    let rows = ((128 + cols - 1) / cols).max(1);
-
-    // Build rows
-    let mut table_rows: Vec<Row> = Vec::with_capacity(rows);
-    for r in 0..rows {
-        let mut cells: Vec<Cell> = Vec::with_capacity(cols + 1);
-        for c in 0..cols {
-            let idx = r * cols + c;
-            if idx < 128 {
-                let byte = app.tape_window[idx];
-                let abs_idx = app.tape_window_base + idx;
-
-                let mut style = Style::default().fg(colors().tape_cell_empty).add_modifier(Modifier::BOLD);
-                if byte > 0 {
-                    style = style.fg(colors().tape_cell_nonzero);
-                }
-
-                if abs_idx == app.tape_ptr {
-                    style = style.fg(colors().tape_cell_pointer);
-                }
+    // Header: the inspected cell, then the prompt, an error or the non-zero regions
+    let cursor = app.tape_cursor.min(app.tape.len() - 1);
+    let value = app.tape[cursor];
+    let mut header = vec![Line::raw(format!("@{cursor}: {value} 0x{value:02X} {}  ptr @{}", char_label(value), app.tape_ptr))];
+    header.push(match (&app.tape_prompt, &app.tape_error) {
+        (Some((prompt, input)), _) => {
+            let label = match prompt {
+                TapePrompt::Goto => "Go to cell".to_string(),
+                TapePrompt::Search => "Find value".to_string(),
+                TapePrompt::Edit => format!("Set @{cursor} to"),
+            };
+            Line::raw(format!("{label}: {input}"))
+        }
+        (None, Some(err)) => Line::from(Span::styled(err.clone(), Style::default().fg(Color::Red))),
+        (None, None) => Line::from(Span::styled(
+            format!("Non-zero: {}", describe_regions(&nonzero_regions(&app.tape), 3)),
+            Style::default().fg(colors().help_hint),
+        )),
+    });
+    let header_h = (header.len() as u16).min(inner.height);
+    f.render_widget(Paragraph::new(header), Rect { height: header_h, ..inner });
+    if let Some((_, input)) = &app.tape_prompt {
+        let label_w = match app.tape_prompt { Some((TapePrompt::Edit, _)) => 10 + cursor.to_string().len(), _ => 12 };
+        let x = inner.x + (label_w + input.chars().count()) as u16;
+        f.set_cursor_position(Position::new(x.min(inner.x + inner.width.saturating_sub(1)), inner.y + 1));
+    }
 
-                cells.push(Cell::from(format!("[{byte:02X}]")).style(style));
-            } else {
-                // Pad remaining cells in the last row to keep grid aligned
-                cells.push(Cell::from("    "));
+    // Grid of 4-character cells, each row labelled with the address of its first cell
+    let grid = Rect { y: inner.y + header_h, height: inner.height - header_h, ..inner };
+    let label_w: u16 = if grid.width >= 14 { 6 } else { 0 };
+    let cols = ((grid.width.saturating_sub(label_w)) / 4).max(1) as usize;
+    app.tape_cols.set(cols);
+    let visible_rows = (grid.height as usize).max(1);
+    let total_rows = app.tape.len().div_ceil(cols);
+    // Keep the inspected cell near the middle
+    let top = (cursor / cols).saturating_sub(visible_rows / 2).min(total_rows.saturating_sub(visible_rows));
+    app.tape_view.set((top * cols, ((top + visible_rows) * cols).min(app.tape.len())));
+
+    let mut lines: Vec<Line> = Vec::with_capacity(visible_rows);
+    for row in top..(top + visible_rows).min(total_rows) {
+        let start = row * cols;
+        let mut spans: Vec<Span> = Vec::with_capacity(cols + 1);
+        if label_w > 0 {
+            spans.push(Span::styled(format!("{start:>5} "), Style::default().fg(colors().gutter_text)));
+        }
+        for (addr, &byte) in app.tape.iter().enumerate().skip(start).take(cols) {
+            let mut style = Style::default().fg(colors().tape_cell_empty).add_modifier(Modifier::BOLD);
+            if byte > 0 {
+                style = style.fg(colors().tape_cell_nonzero);
             }
-
+            if addr == app.tape_ptr {
+                style = style.fg(colors().tape_cell_pointer);
+            }
+            if addr == cursor && app.focused == Focus::Tape {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            spans.push(Span::styled(cell_text(byte, app.tape_format), style));
         }
-        table_rows.push(Row::new(cells));
+        lines.push(Line::from(spans));
     }
-
-    // Constraints
-    // - First cols - columns are fixed width
-    // - Last column expands by the exact leftover (ignoring spacing)
-    let base_width_no_spacing = (cols as u16) * cell_content_width;
-    let leftover_no_spacing = inner.width.saturating_sub(base_width_no_spacing);
-
-    // Column width constraints for the table
-    let mut constraints: Vec<Constraint> =
-        std::iter::repeat(Constraint::Length(cell_content_width))
-            .take(cols.saturating_sub(1))
-            .collect();
-
-    let last_width = cell_content_width + leftover_no_spacing;
-    constraints.push(Constraint::Length(last_width));
-
-    let table = Table::new(table_rows, constraints)
-        .block(block)
-        .column_spacing(0);
-    f.render_widget(table, area);
+    f.render_widget(Paragraph::new(lines), grid);
 }
-
 fn draw_status(f: &mut Frame, area: Rect, app: &App) {
     let filename = app
         .filename
@@ -594,12 +1197,36 @@ fn draw_status(f: &mut Frame, area: Rect, app: &App) {
         match app.vi_mode {
             ViMode::Insert => " | Vi: Insert",
             ViMode::Normal => " | Vi: Normal",
+            ViMode::Visual => " | Vi: Visual",
+            ViMode::VisualLine => " | Vi: Visual Line",
         }
     } else { "" };
 
+    let input = &app.prepared_input;
+    let settings = &app.settings;
+    let settings_str = format!(
+        " | Run: {}, {}, {} cells, EOF {}, tape {}",
+        settings.timeout_ms.map_or("no timeout".to_string(), |ms| format!("{ms} ms")),
+        settings.max_steps.map_or("no step limit".to_string(), |n| format!("{n} steps")),
+        settings.memory,
+        settings.eof,
+        if app.keep_tape { "kept" } else { "reset" },
+    );
+    let input_str = match (&input.file, input.text.len()) {
+        (None, 0) => String::new(),
+        (None, n) => format!(" | Input: {n} bytes"),
+        (Some(_), 0) => " | Input: file".to_string(),
+        (Some(_), n) => format!(" | Input: file + {n} bytes"),
+    };
+    let buffer_pos = if app.buffers.len() > 1 {
+        format!("[{}/{}] ", app.active_buffer + 1, app.buffers.len())
+    } else {
+        String::new()
+    };
+
     let status = format!(
-        " {}{} | {} | Ptr: {} | Cell: {} | Output: {}{} | {} ",
-        filename, dirty, run_state, app.tape_ptr, cell_val, output_mode, vi_str, msg
+        " {}{}{} | {} | Ptr: {} | Cell: {} | Output: {}{}{}{} | {} ",
+        buffer_pos, filename, dirty, run_state, app.tape_ptr, cell_val, output_mode, settings_str, input_str, vi_str, msg
     );
     let block = Block::default().borders(Borders::TOP);
     f.render_widget(block, area);
@@ -627,17 +1254,29 @@ fn draw_help_overlay(f: &mut Frame, area: Rect) {
 
     let mut text = vec![
         Line::raw("F5/Ctrl+R: Run"),
-        Line::raw("Ctrl+N: New file  Ctrl+O: Open  Ctrl+S: Save"),
+        Line::raw("Ctrl+N: New buffer  Ctrl+O: Open (in its own buffer)  Ctrl+S: Save  Ctrl+W: Close buffer"),
+        Line::raw("Ctrl+PageDown/Ctrl+PageUp: Next/previous buffer  Alt+1..9: Go to buffer"),
+        Line::raw("File browser: type to filter, Enter opens, Backspace/Left: parent, Tab: recent, Ctrl+B: all files"),
         Line::raw("Tab/Shift+Tab: Switch pane focus"),
         Line::raw("Ctrl+E: Toggle output mode (Raw/Esc)"),
         Line::raw("F1/Ctrl+H: Toggle this help"),
         Line::raw("Ctrl+L: Toggle line numbers"),
         Line::raw("Ctrl+P: Jump to matching bracket, [ or ]"),
+        Line::raw("Ctrl+T: Format buffer (indent loops, wrap long lines)"),
+        Line::raw("Ctrl+Z/Ctrl+Y: Undo/redo (typing and deletes are grouped)"),
+        Line::raw("Shift+arrows: Select  Ctrl+A: Select all  Ctrl+C/Ctrl+X/Ctrl+V: Copy/cut/paste"),
+        Line::raw("With a selection: Ctrl+R/F5 runs it, Ctrl+T formats its lines, replace-all stays inside it"),
+        Line::raw("Ctrl+F: Find/replace (Tab: replace field, Ctrl+B: code only); F3/Shift+F3: next/previous"),
         Line::raw(""),
         Line::raw("Editor: Arrows, PageUp/PageDown, Home/End, typing, Enter, Backspace"),
-        Line::raw("Tape pane: [ and ] to shift window"),
+        Line::raw("Ctrl+Shift+P/F4: Command palette (Vi normal mode: :); remap keys in [ide.keys] of bf.toml"),
+        Line::raw("F2: Settings (timeout, max steps, memory, EOF, tape policy); Ctrl+S in the dialog saves your changes to bf.toml"),
+        Line::raw("F6: Pause/resume the running program"),
+        Line::raw("Tape pane: arrows, [ ] and Home/End move, c follows the pointer, g goes to an address, f cycles hex/dec/char"),
+        Line::raw("Tape pane: / finds a value, n/N next/previous, z/Z non-zero regions; when paused e/Enter and +/- edit"),
         Line::raw(""),
-        Line::raw("Input on ',': prompts for input; Esc at prompt sends EOF"),
+        Line::raw("Ctrl+D: Prepared input for ',' (typed text and/or an input file), read before prompting"),
+        Line::raw("Input on ',' once prepared input is used up: prompts (or EOF, see Ctrl+D); Esc at prompt sends EOF"),
         Line::raw("Output Raw mode may render control bytes; switch to Escaped mode if your terminal glitches"),
         Line::raw(""),
         Line::raw("Ctrl+q/Esc: Quit"),
@@ -650,6 +1289,10 @@ fn draw_help_overlay(f: &mut Frame, area: Rect) {
         text.push(Line::raw("  Normal: h j k l to move, 0/$ line start/end, gg/G top/end"));
         text.push(Line::raw("  i insert, a append, o/O new line below/above"));
         text.push(Line::raw("  x delete char, dd delete line, Esc -> Normal"));
+        text.push(Line::raw("  u undo, Ctrl+R redo (F5 runs)"));
+        text.push(Line::raw("  / search, n/N next/previous match"));
+        text.push(Line::raw("  v/V visual/visual line, y yank, d cut, yy yank line, p/P paste"));
+        text.push(Line::raw("  gt/gT next/previous buffer"));
     }
 
     let inner = Rect {
@@ -741,82 +1384,269 @@ fn draw_save_dialog(f: &mut Frame, area: Rect, app: &App) {
     f.set_cursor_position(Position::new(cursor_x, cursor_y));
 }
 
-fn draw_open_dialog(f: &mut Frame, area: Rect, app: &App) {
-    // Content to display
-    let title = "Open File";
-    let prompt = "Enter file name to open (Esc to cancel):";
-    let input_line = format!("> {}", app.open_name_input);
-    let err_line = app.open_error.as_deref().unwrap_or("");
-
-    // Compute minimal dialog size based on content
-    let mut longest = prompt.len().max(input_line.len()).max(title.len());
-    if !err_line.is_empty() {
-        longest = longest.max(err_line.len());
-    }
+// Prepared input of the active buffer: attached file, what happens once it is used up, then the text
+fn draw_input_panel(f: &mut Frame, area: Rect, app: &App) {
+    let w = (area.width.saturating_mul(6) / 10).max(50).min(area.width);
+    let h = (area.height / 2).max(10).min(area.height);
+    let rect = Rect {
+        x: area.x + (area.width - w) / 2,
+        y: area.y + (area.height - h) / 2,
+        width: w,
+        height: h,
+    };
+    f.render_widget(Clear, rect);
 
-    // Borders add 2 columns; add a tiny horizontal padding of 1 char per side
-    let horizontal_padding = 2u16;
-    let min_w = 10u16;
-    let max_w = area.width.saturating_sub(2);
-    let w = ((longest as u16) + 2 /* borders */ + horizontal_padding).clamp(min_w, max_w);
+    let title = format!(" Prepared Input - {} ", buffer_label(app.filename.as_deref()));
+    let block = Block::default()
+        .title(Span::styled(title, Style::default().fg(Color::White)))
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::Black).fg(Color::White));
+    let inner = block.inner(rect);
+    f.render_widget(block, rect);
 
-    // Lines:
-    // - 1: prompt
-    // - 2: input
-    // - 3: error(optional)
+    let hint = Style::default().fg(Color::Gray);
+    let input = &app.prepared_input;
+    let file = match &input.file {
+        Some(path) => format!(" File: {} (read before the text)", path.display()),
+        None => " File: none".to_string(),
+    };
+    let exhausted = if app.prompt_when_exhausted { "ask with the input dialog" } else { "EOF" };
+    let mut lines = vec![
+        Line::raw(file),
+        Line::raw(format!(" When used up: {exhausted}  EOF stores: {}", app.settings.eof)),
+        Line::from(Span::styled(
+            " Ctrl+O: attach file  Ctrl+X: detach  Ctrl+K: clear text  Ctrl+P: ask/EOF  Ctrl+E: EOF value  Esc: close",
+            hint,
+        )),
+        Line::from(Span::styled("─".repeat(inner.width as usize), hint)),
+    ];
 
-    let base_lines = 2u16;
-    let lines = base_lines + if err_line.is_empty() { 0 } else { 1 };
-    // Borders add 2 rows; add a tiny vertical padding of 0 (keep minimal)
-    let min_h = 4u16;
-    let max_h = area.height.saturating_sub(2);
-    let h = (lines + 2 /* borders */).clamp(min_h, max_h);
+    // Text, scrolled so its end (where typing goes) stays visible
+    let text_h = (inner.height as usize).saturating_sub(lines.len()).max(1);
+    let text_lines: Vec<&str> = input.text.split('\n').collect();
+    let skip = text_lines.len().saturating_sub(text_h);
+    for line in &text_lines[skip..] {
+        lines.push(Line::raw(format!(" {line}")));
+    }
+    f.render_widget(Paragraph::new(lines), inner);
 
-    // Center dialog
-    let x = area.x + (area.width.saturating_sub(w)) / 2;
-    let y = area.y + (area.height.saturating_sub(h)) / 2;
-    let rect = Rect { x, y, width: w, height: h };
+    let last = text_lines.last().map_or(0, |line| line.chars().count()) as u16;
+    let cursor_x = (inner.x + 1 + last).min(inner.x + inner.width.saturating_sub(1));
+    let cursor_y = inner.y + 4 + (text_lines.len() - skip - 1) as u16;
+    f.set_cursor_position(Position::new(cursor_x, cursor_y.min(inner.y + inner.height.saturating_sub(1))));
+}
 
-    // Ensure a solid background and then draw the block
+// Command palette near the top of the screen: the filter, then matching commands with their keys
+fn draw_palette(f: &mut Frame, area: Rect, app: &App) {
+    let entries = palette_entries(app);
+    let w = 72.min(area.width);
+    let h = (entries.len() as u16 + 3).clamp(4, (area.height * 2 / 3).max(4)).min(area.height);
+    let rect = Rect {
+        x: area.x + (area.width - w) / 2,
+        y: area.y + area.height / 8,
+        width: w,
+        height: h,
+    };
     f.render_widget(Clear, rect);
 
     let block = Block::default()
-        .title(Span::styled(title, Style::default().fg(Color::White)))
+        .title(Span::styled(" Commands ", Style::default().fg(Color::White)))
         .borders(Borders::ALL)
-        .style(Style::default().bg(Color::Black));
-    f.render_widget(block.clone(), rect);
-
-    // Inner area
+        .style(Style::default().bg(Color::Black).fg(Color::White));
     let inner = block.inner(rect);
+    f.render_widget(block, rect);
 
-    // Render content with minimal padding: one leading space
-    let left_pad = " ";
-    let mut lines_vec: Vec<Line> = Vec::new();
-    lines_vec.push(Line::raw(format!("{left_pad}{prompt}")));
-    lines_vec.push(Line::raw(format!("{left_pad}{input_line}")));
-    if !err_line.is_empty() {
-        lines_vec.push(Line::from(Span::styled(
-            format!("{left_pad}{err_line}"),
-            Style::default().fg(Color::Red),
-        )));
+    let mut lines = vec![Line::raw(format!("> {}", app.palette.filter))];
+    if entries.is_empty() {
+        lines.push(Line::from(Span::styled(" No matching command", Style::default().fg(Color::Gray))));
+    }
+    // Keep the selection in view
+    let visible = (inner.height as usize).saturating_sub(1).max(1);
+    let top = app.palette.selected.saturating_sub(visible - 1);
+    for (i, info) in entries.iter().enumerate().skip(top).take(visible) {
+        let keys = command_keys(app, info.command);
+        let pad = (inner.width as usize).saturating_sub(info.title.chars().count() + keys.chars().count() + 2);
+        let style = if i == app.palette.selected {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(format!(" {}{}", info.title, " ".repeat(pad)), style),
+            Span::styled(format!("{keys} "), style.fg(Color::Gray)),
+        ]));
     }
+    f.render_widget(Paragraph::new(lines), inner);
 
-    let paragraph = Paragraph::new(lines_vec)
-        .wrap(Wrap { trim: false })
+    let x = inner.x + 2 + app.palette.filter.chars().count() as u16;
+    f.set_cursor_position(Position::new(x.min(inner.x + inner.width.saturating_sub(1)), inner.y));
+}
+
+// Run settings, one per line with the selected value highlighted, then hints or an error
+fn draw_settings_dialog(f: &mut Frame, area: Rect, app: &App) {
+    let dialog = &app.settings_dialog;
+    let w = 64.min(area.width);
+    let h = (dialog.values.len() as u16 + 5).min(area.height);
+    let rect = Rect {
+        x: area.x + (area.width - w) / 2,
+        y: area.y + (area.height - h) / 2,
+        width: w,
+        height: h,
+    };
+    f.render_widget(Clear, rect);
+
+    let block = Block::default()
+        .title(Span::styled(" Settings ", Style::default().fg(Color::White)))
+        .borders(Borders::ALL)
         .style(Style::default().bg(Color::Black).fg(Color::White));
-    f.render_widget(paragraph, inner);
+    let inner = block.inner(rect);
+    f.render_widget(block, rect);
 
-    let cursor_x = inner
-        .x
-        .saturating_add(1)
-        .saturating_add(2)
-        .saturating_add(app.open_name_input.len() as u16)
-        .min(inner.x.saturating_add(inner.width.saturating_sub(1)));
-    let cursor_y = inner
-        .y
-        .saturating_add(1)
-        .min(inner.y.saturating_add(area.height.saturating_sub(1)));
-    f.set_cursor_position(Position::new(cursor_x, cursor_y));
+    let mut lines: Vec<Line> = Vec::with_capacity(dialog.values.len() + 3);
+    for (i, (name, value)) in dialog.values.iter().enumerate() {
+        let style = if i == dialog.selected {
+            Style::default().add_modifier(Modifier::REVERSED)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::raw(format!(" {:<28}", setting_label(name))),
+            Span::styled(value.clone(), style),
+        ]));
+    }
+    lines.push(Line::raw(""));
+    lines.push(match &dialog.error {
+        Some(err) => Line::from(Span::styled(format!(" {err}"), Style::default().fg(Color::Red))),
+        None => Line::from(Span::styled(
+            " Up/Down: select  Left/Right: choose  Enter: apply  Ctrl+S: save to bf.toml  Esc: cancel",
+            Style::default().fg(Color::Gray),
+        )),
+    });
+    f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), inner);
+
+    if let Some((_, value)) = dialog.values.get(dialog.selected) {
+        let x = inner.x + 29 + value.chars().count() as u16;
+        let y = inner.y + dialog.selected as u16;
+        f.set_cursor_position(Position::new(x.min(inner.x + inner.width.saturating_sub(1)), y));
+    }
+}
+
+fn setting_label(name: &str) -> &'static str {
+    match name {
+        "timeout" => "Timeout (ms, none)",
+        "max-steps" => "Max steps (count, none)",
+        "memory" => "Memory (cells)",
+        "eof" => "EOF (zero, unchanged, max)",
+        "tape" => "Tape between runs (reset, keep)",
+        _ => "",
+    }
+}
+
+// File browser over the middle of the screen: filter line, hints or error, then the listing
+fn draw_file_browser(f: &mut Frame, area: Rect, app: &App) {
+    let w = (area.width.saturating_mul(7) / 10).max(40).min(area.width);
+    let h = (area.height.saturating_mul(7) / 10).max(8).min(area.height);
+    let rect = Rect {
+        x: area.x + (area.width - w) / 2,
+        y: area.y + (area.height - h) / 2,
+        width: w,
+        height: h,
+    };
+    f.render_widget(Clear, rect);
+
+    let browser = &app.browser;
+    let action = if browser.attach_input { "Attach Input File" } else { "Open File" };
+    let title = if browser.show_recent {
+        format!(" {action} - Recent files ")
+    } else {
+        format!(" {action} - {} ", browser.dir.display())
+    };
+    let block = Block::default()
+        .title(Span::styled(title, Style::default().fg(Color::White)))
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::Black).fg(Color::White));
+    let inner = block.inner(rect);
+    f.render_widget(block, rect);
+
+    let mut lines: Vec<Line> = vec![Line::raw(format!(" > {}", browser.filter))];
+    lines.push(match &browser.error {
+        Some(err) => Line::from(Span::styled(format!(" {err}"), Style::default().fg(Color::Red))),
+        None => Line::from(Span::styled(
+            format!(
+                " Enter: open  Tab: {}  Ctrl+B: {}  Esc: cancel",
+                if browser.show_recent { "folder" } else { "recent files" },
+                if browser.bf_only { "all files" } else { ".bf/.b only" },
+            ),
+            Style::default().fg(Color::Gray),
+        )),
+    });
+
+    // Scroll just enough to keep the selected entry on screen
+    let visible = browser_visible(app);
+    let list_h = (inner.height as usize).saturating_sub(2).max(1);
+    let offset = browser.selected.saturating_sub(list_h - 1);
+    if visible.is_empty() {
+        lines.push(Line::from(Span::styled("   (no matching files)", Style::default().fg(Color::Gray))));
+    }
+    for (i, entry) in visible.iter().enumerate().skip(offset).take(list_h) {
+        let mut style = Style::default().fg(if entry.is_dir { Color::LightBlue } else { Color::White });
+        if i == browser.selected {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        lines.push(Line::from(Span::styled(format!("   {}", entry.label), style)));
+    }
+    f.render_widget(Paragraph::new(lines), inner);
+
+    let cursor_x = (inner.x + 3 + browser.filter.chars().count() as u16).min(inner.x + inner.width.saturating_sub(1));
+    f.set_cursor_position(Position::new(cursor_x, inner.y));
+}
+
+// Find/replace bar over the bottom of the editor
+fn draw_search_bar(f: &mut Frame, area: Rect, app: &App) {
+    let h = 4u16;
+    if area.height < h + 2 || area.width < 20 {
+        return;
+    }
+    let rect = Rect { x: area.x + 1, y: area.y + area.height - h - 1, width: area.width - 2, height: h };
+    f.render_widget(Clear, rect);
+
+    let matches = find_matches(&app.buffer, &app.search_query, app.search_code_only);
+    let count = match matches.iter().position(|m| m.cells[0] == (app.cursor_row, app.cursor_col)) {
+        Some(i) => format!("{}/{}", i + 1, matches.len()),
+        None if app.search_query.is_empty() => String::new(),
+        None => format!("{} matches", matches.len()),
+    };
+    let mode = match (app.search_code_only, app.search_scope.is_some()) {
+        (false, false) => "text",
+        (true, false) => "code only",
+        (false, true) => "text, in selection",
+        (true, true) => "code only, in selection",
+    };
+    let title = format!("Find [{mode}] (Tab: replace, Ctrl+B: code only, Up/Down: prev/next)");
+    let block = Block::default()
+        .title(Span::styled(title, Style::default().fg(Color::White)))
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::Black));
+    f.render_widget(block.clone(), rect);
+    let inner = block.inner(rect);
+
+    let label = |field: SearchField, text: &str| {
+        let style = if app.search_field == field { Style::default().fg(Color::Yellow) } else { Style::default().fg(Color::Gray) };
+        Span::styled(text.to_string(), style)
+    };
+    let lines = vec![
+        Line::from(vec![label(SearchField::Find, " Find:    "),
+            Span::styled(app.search_query.clone(), if app.search_query_selected { Style::default().add_modifier(Modifier::REVERSED) } else { Style::default() }), Span::styled(format!("  {count}"), Style::default().fg(Color::Gray))]),
+        Line::from(vec![label(SearchField::Replace, " Replace: "), Span::raw(app.replace_text.clone())]),
+    ];
+    f.render_widget(Paragraph::new(lines).style(Style::default().bg(Color::Black).fg(Color::White)), inner);
+
+    let (row, text) = match app.search_field {
+        SearchField::Find => (0, &app.search_query),
+        SearchField::Replace => (1, &app.replace_text),
+    };
+    let cursor_x = (inner.x + 10 + text.chars().count() as u16).min(inner.x + inner.width.saturating_sub(1));
+    f.set_cursor_position(Position::new(cursor_x, inner.y + row));
 }
 
 fn draw_confirm_dialog(f: &mut Frame, area: Rect, app: &App) {
@@ -930,10 +1760,21 @@ fn handle_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
         handle_save_dialog_key(app, key)?;
         return Ok(false);
     }
-    if app.show_open_dialog {
-        handle_open_dialog_key(app, key)?;
+    if app.show_file_browser {
+        handle_file_browser_key(app, key);
+        return Ok(false);
+    }
+    if app.show_input_panel {
+        handle_input_panel_key(app, key);
+        return Ok(false);
+    }
+    if app.show_settings {
+        handle_settings_key(app, key);
         return Ok(false);
     }
+    if app.show_palette {
+        return Ok(handle_palette_key(app, key));
+    }
     if app.show_confirm_dialog {
         handle_confirm_dialog_key(app, key)?;
         return Ok(false);
@@ -942,131 +1783,50 @@ fn handle_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
         handle_input_dialog_key(app, key)?;
         return Ok(false);
     }
+    if app.show_search {
+        handle_search_key(app, key);
+        return Ok(false);
+    }
 
-    // Global keys
-    if key.modifiers.contains(KeyModifiers::CONTROL) {
-        match key.code {
-            KeyCode::Char('q') => {
-                if app.dirty {
-                    app.show_confirm_dialog = true;
-                    app.confirm_message = "You have unsaved changes. Quit anyway?".to_string();
-                    app.confirm_pending_quit = true;
-                    // Wait for confirmation
-                    return Ok(false);
-                }
-                return Ok(true)
-            }, // Quit
-            KeyCode::Char('h') | KeyCode::F(1) => {
-                app.show_help = !app.show_help;
-                return Ok(false);
-            }
-            KeyCode::Char('r') | KeyCode::F(5) => {
-                // Start runner
-                start_runner(app);
-                return Ok(false);
-            }
-            KeyCode::Char('o') => {
-                // Open file: show path prompt
-                app.show_open_dialog = true;
-                app.open_error = None;
-                // Prefill with current filename if present
-                app.open_name_input = app.filename.clone().unwrap_or_default();
-                return Ok(false);
-            }
-            KeyCode::Char('s') => {
-                // Save current file
-                if app.filename.is_none() {
-                    app.show_save_dialog = true;
-                    app.save_name_input = "untitled.bf".to_string();
-                    app.save_error = None;
-                } else {
-                    match app_save_current(app) {
-                        Ok(_) => { /* saved; dirty cleared */ }
-                        Err(err) => {
-                            // TODO: show status message
-                            eprintln!("Save failed: {}", err);
-                        }
-                    }
-                }
-                return Ok(false);
-            }
-            KeyCode::Char('e') => {
-                app.output_mode = match app.output_mode {
-                    OutputMode::Raw => OutputMode::Escaped,
-                    OutputMode::Escaped => OutputMode::Raw,
-                };
-                return Ok(false);
-            }
-            KeyCode::Char('n') => {
-                if app.dirty {
-                    app.show_confirm_dialog = true;
-                    app.confirm_message = "You have unsaved changes. Discard and create new file?".to_string();
-                    app.confirm_pending_new = true;
-                } else {
-                    app_new_file(app);
-                }
-                return Ok(false);
-            }
-            _ => {}
-        }
+    // An open tape prompt takes Esc, Enter and plain keys before the pane bindings
+    if app.focused == Focus::Tape && app.tape_prompt.is_some() {
+        handle_tape_key(app, key);
+        return Ok(false);
+    }
+
+    // Vi normal mode keeps Ctrl+R for redo; F5 still runs
+    if key.code == KeyCode::Char('r') && key.modifiers == KeyModifiers::CONTROL
+        && app.focused == Focus::Editor && app.vi_enabled && app.vi_mode == ViMode::Normal
+    {
+        app_redo(app);
+        return Ok(false);
+    }
+
+    // Named commands, through the keymap
+    if let Some(spec) = key_spec(&key)
+        && let Some(command) = app.keymap.lookup(spec, |scope| scope_applies(app, scope))
+    {
+        return Ok(run_command(app, command));
     }
 
     match key.code {
-        KeyCode::F(1) => {
-            app.show_help = !app.show_help;
-            Ok(false)
-        }
-        KeyCode::F(5) => {
-            // Start runner
-            start_runner(app);
-            Ok(false)
-        }
-        KeyCode::Char('.') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-            if let Some(h) = app.runner.as_ref() {
-                h.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
-                let _ = h.tx_cmd.send(UiCmd::Stop);
-            }
-            app.running = false;
-            Ok(false)
-        }
-        KeyCode::F(17) /* Shift+F5 */ => {
-            if let Some(h) = app.runner.as_ref() {
-                h.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
-                let _ = h.tx_cmd.send(UiCmd::Stop);
-            }
-            app.running = false;
-            Ok(false)
-        }
-        KeyCode::Tab => {
-            app.focused = match app.focused {
-                Focus::Editor => Focus::Output,
-                Focus::Output => Focus::Tape,
-                Focus::Tape => Focus::Editor,
-            };
-            Ok(false)
-        }
-        KeyCode::BackTab => {
-            app.focused = match app.focused {
-                Focus::Editor => Focus::Tape,
-                Focus::Output => Focus::Editor,
-                Focus::Tape => Focus::Output,
-            };
-            Ok(false)
-        }
         KeyCode::Esc => {
             // In Vi mode within editor, Esc leaves Insert -> Normal (or no-op)
             if app.focused == Focus::Editor && app.vi_enabled {
                 handle_editor_key_vi(app, key);
                 return Ok(false);
             }
-            // Hide help if it's open; otherwise confirm-or-quit
-            if app.show_help {
+            // Hide help if it's open; drop a selection; otherwise confirm-or-quit
+            if app.selection.is_some() {
+                app.selection = None;
+                Ok(false)
+            } else if app.show_help {
                 app.show_help = false;
                 Ok(false)
             } else {
-                if app.dirty {
+                if any_buffer_dirty(app) {
                     app.show_confirm_dialog = true;
-                    app.confirm_message = "You have unsaved changes. Quit anyway?".to_string();
+                    app.confirm_message = unsaved_on_quit_message(app);
                     app.confirm_pending_quit = true;
                     // Wait for confirmation
                     Ok(false)
@@ -1093,144 +1853,597 @@ fn handle_key(app: &mut App, key: KeyEvent) -> io::Result<bool> {
     }
 }
 
-fn handle_tape_key(app: &mut App, key: KeyEvent) {
-    match key.code {
-        // Page to previous/next 128-cell chunk
-        KeyCode::Char('[') | KeyCode::Left | KeyCode::PageUp => {
-            let page = 128usize;
-            let new_base = app.tape_window_base.saturating_sub(page);
-            if new_base != app.tape_window_base {
-                app.tape_window_base = new_base;
-                app.dirty = true;
-            }
-        }
-        KeyCode::Char(']') | KeyCode::Right | KeyCode::PageDown => {
-            let page = 128usize;
-            // avoid overflow / beyond memory size; if you have memory size available, clamp to it
-            app.tape_window_base = app.tape_window_base.saturating_add(page);
-            app.dirty = true;
-        }
-        // Center current pointer into its page
-        KeyCode::Char('c') if key.modifiers.is_empty() => {
-            let page = 128usize;
-            app.tape_window_base = app.tape_ptr - (app.tape_ptr % page);
-            app.dirty = true;
+// The key of a terminal event in the form bf.toml uses. Letters typed with Shift arrive
+// upper-case, Shift+Tab as BackTab and, in some terminals, Shift+F1..F12 as F13..F24;
+// for other characters Shift is already part of the character
+fn key_spec(key: &KeyEvent) -> Option<KeySpec> {
+    let mut shift = key.modifiers.contains(KeyModifiers::SHIFT);
+    let name = match key.code {
+        KeyCode::Char(c) if c.is_ascii_alphabetic() => {
+            shift |= c.is_ascii_uppercase();
+            KeyName::Char(c.to_ascii_lowercase())
         }
-        _ => {}
+        KeyCode::Char(c) => {
+            shift = false;
+            KeyName::Char(c)
+        }
+        KeyCode::F(n @ 13..=24) => {
+            shift = true;
+            KeyName::F(n - 12)
+        }
+        KeyCode::F(n) => KeyName::F(n),
+        KeyCode::BackTab => {
+            shift = true;
+            KeyName::Tab
+        }
+        KeyCode::Enter => KeyName::Enter,
+        KeyCode::Tab => KeyName::Tab,
+        KeyCode::Esc => KeyName::Esc,
+        KeyCode::Backspace => KeyName::Backspace,
+        KeyCode::Delete => KeyName::Delete,
+        KeyCode::Insert => KeyName::Insert,
+        KeyCode::Up => KeyName::Up,
+        KeyCode::Down => KeyName::Down,
+        KeyCode::Left => KeyName::Left,
+        KeyCode::Right => KeyName::Right,
+        KeyCode::Home => KeyName::Home,
+        KeyCode::End => KeyName::End,
+        KeyCode::PageUp => KeyName::PageUp,
+        KeyCode::PageDown => KeyName::PageDown,
+        _ => return None,
+    };
+    Some(KeySpec {
+        ctrl: key.modifiers.contains(KeyModifiers::CONTROL),
+        alt: key.modifiers.contains(KeyModifiers::ALT),
+        shift,
+        key: name,
+    })
+}
+
+fn scope_applies(app: &App, scope: Scope) -> bool {
+    match scope {
+        Scope::Global => true,
+        Scope::Editor => app.focused == Focus::Editor,
+        Scope::EditorText => app.focused == Focus::Editor && (!app.vi_enabled || app.vi_mode == ViMode::Insert),
+        Scope::Tape => app.focused == Focus::Tape,
     }
 }
 
-fn handle_editor_key(app: &mut App, key: KeyEvent) {
-    match key.code {
-        KeyCode::Left => {
-            if app.cursor_col > 0 {
-                app.cursor_col -= 1;
-            } else if app.cursor_row > 0 {
-                app.cursor_row -= 1;
-                app.cursor_col = app.buffer[app.cursor_row].len();
-                ensure_cursor_visible(app);
+// Run a named command, from its keys or the palette; true when the IDE should quit
+fn run_command(app: &mut App, command: Command) -> bool {
+    match command {
+        Command::Run => start_runner(app),
+        Command::Stop => {
+            if let Some(h) = app.runner.as_ref() {
+                h.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+                let _ = h.tx_cmd.send(UiCmd::Stop);
             }
+            app.running = false;
         }
-        KeyCode::Right => {
-            let len = app.buffer[app.cursor_row].len();
-            if app.cursor_col < len {
-                app.cursor_col += 1;
-            } else if app.cursor_row + 1 < app.buffer.len() {
-                app.cursor_row += 1;
-                app.cursor_col = 0;
-                ensure_cursor_visible(app);
+        Command::Pause => toggle_pause(app),
+        Command::Settings => open_settings(app),
+        Command::PreparedInput => app.show_input_panel = true,
+        Command::Palette => {
+            app.palette = Palette::default();
+            app.show_palette = true;
+        }
+        Command::Help => app.show_help = !app.show_help,
+        Command::Quit => {
+            if !any_buffer_dirty(app) {
+                return true;
             }
+            app.show_confirm_dialog = true;
+            app.confirm_message = unsaved_on_quit_message(app);
+            app.confirm_pending_quit = true;
         }
-        KeyCode::Up => {
-            if app.cursor_row > 0 {
-                app.cursor_row -= 1;
-                app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].len());
-                ensure_cursor_visible(app);
+        Command::NewBuffer => {
+            // New untitled buffer next to the open ones
+            add_buffer(app);
+            set_status(app, "New File");
+        }
+        Command::Open => open_file_browser(app),
+        Command::Save => {
+            if app.filename.is_none() {
+                app.show_save_dialog = true;
+                app.save_name_input = "untitled.bf".to_string();
+                app.save_error = None;
+            } else if let Err(err) = app_save_current(app) {
+                set_status(app, &format!("Save failed: {err}"));
             }
         }
-        KeyCode::Down => {
-            if app.cursor_row + 1 < app.buffer.len() {
-                app.cursor_row += 1;
-                app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].len());
-                ensure_cursor_visible(app);
+        Command::CloseBuffer => {
+            if app.dirty {
+                app.show_confirm_dialog = true;
+                app.confirm_message = format!(
+                    "{} has unsaved changes. Close anyway?",
+                    buffer_label(app.filename.as_deref())
+                );
+                app.confirm_pending_close = true;
+            } else {
+                close_active_buffer(app);
             }
         }
-        KeyCode::Home => { app.cursor_col = 0; }
-        KeyCode::End => { app.cursor_col = app.buffer[app.cursor_row].len(); }
-        KeyCode::PageUp => {
-            let jump = 10usize;
-            app.cursor_row = app.cursor_row.saturating_sub(jump);
-            app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].len());
-            ensure_cursor_visible(app);
+        Command::NextBuffer => switch_buffer(app, (app.active_buffer + 1) % app.buffers.len()),
+        Command::PreviousBuffer => {
+            let count = app.buffers.len();
+            switch_buffer(app, (app.active_buffer + count - 1) % count);
         }
-        KeyCode::PageDown => {
-            let jump = 10usize;
-            app.cursor_row = (app.cursor_row + jump).min(app.buffer.len().saturating_sub(1));
-            app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].len());
+        Command::Find => {
+            app.focused = Focus::Editor;
+            open_search(app);
+        }
+        Command::FindNext => search_step(app, true),
+        Command::FindPrevious => search_step(app, false),
+        Command::Format => app_format_buffer(app),
+        Command::ToggleOutputMode => {
+            app.output_mode = match app.output_mode {
+                OutputMode::Raw => OutputMode::Escaped,
+                OutputMode::Escaped => OutputMode::Raw,
+            };
+        }
+        Command::ToggleLineNumbers => app.show_line_numbers = !app.show_line_numbers,
+        Command::MatchBracket => {
+            if !jump_to_matching_bracket(app) {
+                set_status(app, "No matching bracket at cursor")
+            }
+        }
+        Command::Undo => app_undo(app),
+        Command::Redo => app_redo(app),
+        Command::Copy => copy_selection(app),
+        Command::Cut => cut_selection(app),
+        Command::Paste => paste_register(app, false),
+        Command::GoToBuffer(index) => {
+            if index < app.buffers.len() {
+                switch_buffer(app, index);
+            }
+        }
+        Command::TapeGoto
+        | Command::TapeFind
+        | Command::TapeFindNext
+        | Command::TapeFindPrevious
+        | Command::TapeNextNonzero
+        | Command::TapePreviousNonzero
+        | Command::TapeFormat
+        | Command::TapeFollowPointer => run_tape_command(app, command),
+        Command::SelectAll => {
+            app.selection = Some(Selection { anchor: (0, 0), kind: SelectionKind::Exclusive });
+            app.cursor_row = app.buffer.len() - 1;
+            app.cursor_col = app.buffer[app.cursor_row].chars().count();
             ensure_cursor_visible(app);
         }
+        Command::FocusNext => {
+            app.focused = match app.focused {
+                Focus::Editor => Focus::Output,
+                Focus::Output => Focus::Tape,
+                Focus::Tape => Focus::Editor,
+            };
+        }
+        Command::FocusPrevious => {
+            app.focused = match app.focused {
+                Focus::Editor => Focus::Tape,
+                Focus::Output => Focus::Editor,
+                Focus::Tape => Focus::Output,
+            };
+        }
+    }
+    false
+}
+
+// Commands matching the palette filter, best first; the palette itself is left out
+fn palette_entries(app: &App) -> Vec<&'static CommandInfo> {
+    let mut scored: Vec<(i32, &'static CommandInfo)> = COMMANDS
+        .iter()
+        .filter(|info| info.command != Command::Palette)
+        .filter_map(|info| {
+            if app.palette.filter.is_empty() {
+                return Some((0, info));
+            }
+            fuzzy_score(info.title, &app.palette.filter).map(|score| (score, info))
+        })
+        .collect();
+    // Stable, so equal scores keep the registry order
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, info)| info).collect()
+}
+
+// Keys of a command as shown to the user, e.g. "F5, Ctrl+R"
+fn command_keys(app: &App, command: Command) -> String {
+    app.keymap.keys(command).iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+}
+
+// true when the chosen command asks the IDE to quit
+fn handle_palette_key(app: &mut App, key: KeyEvent) -> bool {
+    let count = palette_entries(app).len();
+    match key.code {
+        KeyCode::Esc => app.show_palette = false,
+        KeyCode::Up => app.palette.selected = app.palette.selected.saturating_sub(1),
+        KeyCode::Down => app.palette.selected = (app.palette.selected + 1).min(count.saturating_sub(1)),
+        KeyCode::PageUp => app.palette.selected = app.palette.selected.saturating_sub(10),
+        KeyCode::PageDown => app.palette.selected = (app.palette.selected + 10).min(count.saturating_sub(1)),
         KeyCode::Enter => {
-            let line = app.buffer[app.cursor_row].clone();
-            let (left, right) = line.split_at(app.cursor_col);
-            app.buffer[app.cursor_row] = left.to_string();
-            app.buffer.insert(app.cursor_row + 1, right.to_string());
-            app.cursor_row += 1;
-            app.cursor_col = 0;
-            app.dirty = true;
-            ensure_cursor_visible(app);
+            let Some(info) = palette_entries(app).get(app.palette.selected).copied() else {
+                return false;
+            };
+            app.show_palette = false;
+            return run_command(app, info.command);
         }
         KeyCode::Backspace => {
-            if app.cursor_col > 0 {
-                let line = &mut app.buffer[app.cursor_row];
-                let prev_byte_idx = nth_char_to_byte_idx(line, app.cursor_col - 1);
-                line.drain(prev_byte_idx..nth_char_to_byte_idx(line, app.cursor_col));
-                app.cursor_col -= 1;
-                app.dirty = true;
-            } else if app.cursor_row > 0 {
-                let cur = app.buffer.remove(app.cursor_row);
-                app.cursor_row -= 1;
-                let prev_len_chars = app.buffer[app.cursor_row].chars().count();
-                app.buffer[app.cursor_row].push_str(&cur);
-                app.cursor_row = prev_len_chars;
-                app.dirty = true;
-                ensure_cursor_visible(app);
+            app.palette.filter.pop();
+            app.palette.selected = 0;
+        }
+        KeyCode::Char(ch) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+            app.palette.filter.push(ch);
+            app.palette.selected = 0;
+        }
+        _ => {}
+    }
+    false
+}
+
+fn handle_tape_key(app: &mut App, key: KeyEvent) {
+    if app.tape_prompt.is_some() {
+        handle_tape_prompt_key(app, key);
+        return;
+    }
+    app.tape_error = None;
+    let cols = app.tape_cols.get().max(1);
+    let last = app.tape.len().saturating_sub(1);
+    let cursor = app.tape_cursor.min(last);
+    match key.code {
+        KeyCode::Left => move_tape_cursor(app, cursor.saturating_sub(1)),
+        KeyCode::Right => move_tape_cursor(app, (cursor + 1).min(last)),
+        KeyCode::Up => move_tape_cursor(app, cursor.saturating_sub(cols)),
+        KeyCode::Down => move_tape_cursor(app, (cursor + cols).min(last)),
+        // Page by 128 cells
+        KeyCode::Char('[') | KeyCode::PageUp => move_tape_cursor(app, cursor.saturating_sub(128)),
+        KeyCode::Char(']') | KeyCode::PageDown => move_tape_cursor(app, (cursor + 128).min(last)),
+        KeyCode::Home => move_tape_cursor(app, 0),
+        KeyCode::End => move_tape_cursor(app, last),
+        KeyCode::Char('e') | KeyCode::Enter => {
+            if tape_editable(app) {
+                app.tape_prompt = Some((TapePrompt::Edit, app.tape[cursor].to_string()));
             } else {
-                // At start of file, do nothing
+                app.tape_error = Some("Pause the program (F6) to edit cells".to_string());
             }
         }
-        KeyCode::Delete => {
-            let len_chars = app.buffer[app.cursor_row].chars().count();
-            if app.cursor_col < len_chars {
-                let line = &mut app.buffer[app.cursor_row];
-                let start = nth_char_to_byte_idx(line, app.cursor_col);
-                let end = nth_char_to_byte_idx(line, app.cursor_col + 1);
-                line.drain(start..end);
-                app.dirty = true;
-            } else if app.cursor_row + 1 < app.buffer.len() {
-                let next = app.buffer.remove(app.cursor_row + 1);
-                app.buffer[app.cursor_row].push_str(&next);
-                app.dirty = true;
+        KeyCode::Char(op @ ('+' | '-')) => {
+            if tape_editable(app) {
+                let value = app.tape[cursor];
+                set_tape_cell(app, cursor, if op == '+' { value.wrapping_add(1) } else { value.wrapping_sub(1) });
+            } else {
+                app.tape_error = Some("Pause the program (F6) to edit cells".to_string());
             }
         }
-        KeyCode::Char('l') | KeyCode::Char('L') => {
-            if key.modifiers.contains(KeyModifiers::CONTROL) {
-                app.show_line_numbers = !app.show_line_numbers;
+        _ => {}
+    }
+}
+
+// The tape pane's named commands; from the palette they also move focus to the pane
+fn run_tape_command(app: &mut App, command: Command) {
+    app.focused = Focus::Tape;
+    app.tape_error = None;
+    let cursor = app.tape_cursor.min(app.tape.len().saturating_sub(1));
+    match command {
+        Command::TapeGoto => app.tape_prompt = Some((TapePrompt::Goto, String::new())),
+        Command::TapeFind => app.tape_prompt = Some((TapePrompt::Search, String::new())),
+        Command::TapeFindNext => search_tape(app, true),
+        Command::TapeFindPrevious => search_tape(app, false),
+        // Start of the next / previous run of non-zero cells
+        Command::TapeNextNonzero => {
+            let regions = nonzero_regions(&app.tape);
+            match regions.iter().find(|(start, _)| *start > cursor) {
+                Some(&(start, _)) => move_tape_cursor(app, start),
+                None => app.tape_error = Some("No non-zero cells after this one".to_string()),
             }
         }
-        KeyCode::Char('p') | KeyCode::Char('P') => {
-            if key.modifiers.contains(KeyModifiers::CONTROL) {
-                if !jump_to_matching_bracket(app) {
-                    set_status(app, "No matching bracket at cursor")
-                }
-                return;
+        Command::TapePreviousNonzero => {
+            let regions = nonzero_regions(&app.tape);
+            match regions.iter().rev().find(|(start, _)| *start < cursor) {
+                Some(&(start, _)) => move_tape_cursor(app, start),
+                None => app.tape_error = Some("No non-zero cells before this one".to_string()),
             }
         }
-        KeyCode::Char(ch) => {
+        Command::TapeFormat => {
+            app.tape_format = match app.tape_format {
+                CellFormat::Hex => CellFormat::Dec,
+                CellFormat::Dec => CellFormat::Char,
+                CellFormat::Char => CellFormat::Hex,
+            };
+        }
+        // Back to the data pointer, and keep following it
+        Command::TapeFollowPointer => {
+            app.tape_cursor = app.tape_ptr;
+            app.tape_follow = true;
+        }
+        _ => {}
+    }
+}
+
+fn handle_tape_prompt_key(app: &mut App, key: KeyEvent) {
+    let Some((prompt, input)) = app.tape_prompt.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Esc => app.tape_prompt = None,
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        KeyCode::Char(ch) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => input.push(ch),
+        KeyCode::Enter => {
+            let (prompt, input) = (*prompt, input.trim().to_string());
+            app.tape_prompt = None;
+            match prompt {
+                TapePrompt::Goto => match parse_number(&input).filter(|addr| *addr < app.tape.len()) {
+                    Some(addr) => move_tape_cursor(app, addr),
+                    None => app.tape_error = Some(format!("No cell '{input}' (0..{})", app.tape.len())),
+                },
+                TapePrompt::Search => match parse_cell_value(&input) {
+                    Some(value) => {
+                        app.tape_search = Some(value);
+                        search_tape(app, true);
+                    }
+                    None => app.tape_error = Some(format!("Invalid value '{input}' (0-255, 0xNN or 'c')")),
+                },
+                TapePrompt::Edit => match parse_cell_value(&input) {
+                    Some(value) if tape_editable(app) => set_tape_cell(app, app.tape_cursor, value),
+                    Some(_) => app.tape_error = Some("The program is no longer paused".to_string()),
+                    None => app.tape_error = Some(format!("Invalid value '{input}' (0-255, 0xNN or 'c')")),
+                },
+            }
+        }
+        _ => {}
+    }
+}
+
+// Manual navigation stops the cursor from following the data pointer
+fn move_tape_cursor(app: &mut App, addr: usize) {
+    app.tape_cursor = addr;
+    app.tape_follow = false;
+}
+
+// n / N: the next cell (wrapping) holding the last searched value
+fn search_tape(app: &mut App, forward: bool) {
+    let Some(value) = app.tape_search else {
+        app.tape_error = Some("Nothing to find yet (press /)".to_string());
+        return;
+    };
+    let len = app.tape.len();
+    let from = app.tape_cursor.min(len.saturating_sub(1));
+    let found = (1..=len)
+        .map(|step| if forward { (from + step) % len } else { (from + len - step % len) % len })
+        .find(|&addr| app.tape[addr] == value);
+    match found {
+        Some(addr) => move_tape_cursor(app, addr),
+        None => app.tape_error = Some(format!("No cell holds {value}")),
+    }
+}
+
+// Cells can change only while the active buffer's run is paused in the step hook
+fn tape_editable(app: &App) -> bool {
+    app.paused && app.runner.is_some() && app.run_buffer == app.active_buffer
+}
+
+fn set_tape_cell(app: &mut App, addr: usize, value: u8) {
+    if let Some(h) = app.runner.as_ref() {
+        let _ = h.tx_cmd.send(UiCmd::SetCell { addr, value });
+    }
+    // Shown right away; the runner confirms with a fresh snapshot
+    if let Some(cell) = app.tape.get_mut(addr) {
+        *cell = value;
+    }
+}
+
+// F6: ask the runner to pause after the current instruction, or resume it
+fn toggle_pause(app: &mut App) {
+    let Some(h) = app.runner.as_ref() else {
+        set_status(app, "Nothing is running");
+        return;
+    };
+    if app.paused {
+        let _ = h.tx_cmd.send(UiCmd::Resume);
+        set_status(app, "Resumed");
+    } else {
+        h.pause.store(true, Ordering::Relaxed);
+        set_status(app, "Pausing...");
+    }
+}
+
+// Runs of non-zero cells as inclusive (start, end) pairs
+fn nonzero_regions(tape: &[u8]) -> Vec<(usize, usize)> {
+    let mut regions: Vec<(usize, usize)> = Vec::new();
+    for (addr, &byte) in tape.iter().enumerate() {
+        if byte == 0 {
+            continue;
+        }
+        match regions.last_mut() {
+            Some((_, end)) if *end + 1 == addr => *end = addr,
+            _ => regions.push((addr, addr)),
+        }
+    }
+    regions
+}
+
+fn describe_regions(regions: &[(usize, usize)], max: usize) -> String {
+    if regions.is_empty() {
+        return "none".to_string();
+    }
+    let mut parts: Vec<String> = regions
+        .iter()
+        .take(max)
+        .map(|&(start, end)| if start == end { start.to_string() } else { format!("{start}-{end}") })
+        .collect();
+    if regions.len() > max {
+        parts.push(format!("(+{} more)", regions.len() - max));
+    }
+    parts.join(" ")
+}
+
+// Four columns per cell whatever the format
+fn cell_text(value: u8, format: CellFormat) -> String {
+    match format {
+        CellFormat::Hex => format!("[{value:02X}]"),
+        CellFormat::Dec => format!("{value:>3} "),
+        CellFormat::Char => format!("{:>3} ", char_label(value)),
+    }
+}
+
+// Printable ASCII as 'c', common control characters escaped, anything else as its decimal value
+fn char_label(value: u8) -> String {
+    match value {
+        0 => "\\0".to_string(),
+        b'\n' => "\\n".to_string(),
+        b'\t' => "\\t".to_string(),
+        b'\r' => "\\r".to_string(),
+        0x20..=0x7E => format!("'{}'", value as char),
+        _ => value.to_string(),
+    }
+}
+
+// A decimal or 0x-prefixed hexadecimal number; '_' separators are allowed
+fn parse_number(s: &str) -> Option<usize> {
+    let digits = s.replace('_', "");
+    match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => digits.parse().ok(),
+    }
+}
+
+// A cell value: 0-255, 0xNN, or a quoted ASCII character such as 'A'
+fn parse_cell_value(s: &str) -> Option<u8> {
+    let mut quoted = s.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')).map(|inner| inner.chars());
+    if let Some(chars) = quoted.as_mut() {
+        return match (chars.next(), chars.next()) {
+            (Some(ch), None) if ch.is_ascii() => Some(ch as u8),
+            _ => None,
+        };
+    }
+    parse_number(s).and_then(|n| u8::try_from(n).ok())
+}
+fn handle_editor_key(app: &mut App, key: KeyEvent) {
+    // Shift+motion extends the selection from where it started; a plain motion drops it
+    let is_motion = matches!(
+        key.code,
+        KeyCode::Left | KeyCode::Right | KeyCode::Up | KeyCode::Down | KeyCode::Home | KeyCode::End | KeyCode::PageUp | KeyCode::PageDown
+    );
+    if is_motion {
+        if !key.modifiers.contains(KeyModifiers::SHIFT) {
+            app.selection = None;
+        } else if app.selection.is_none() {
+            app.selection = Some(Selection { anchor: (app.cursor_row, app.cursor_col), kind: SelectionKind::Exclusive });
+        }
+    } else if app.selection.is_some() {
+        // Typing replaces the selection; Backspace/Delete just remove it
+        match key.code {
+            KeyCode::Backspace | KeyCode::Delete => {
+                delete_selection(app);
+                return;
+            }
+            KeyCode::Enter => delete_selection(app),
+            KeyCode::Char(ch) if key.modifiers.is_empty() && !ch.is_control() => delete_selection(app),
+            _ => {}
+        }
+    }
+
+    match key.code {
+        KeyCode::Left => {
+            if app.cursor_col > 0 {
+                app.cursor_col -= 1;
+            } else if app.cursor_row > 0 {
+                app.cursor_row -= 1;
+                app.cursor_col = app.buffer[app.cursor_row].len();
+                ensure_cursor_visible(app);
+            }
+        }
+        KeyCode::Right => {
+            let len = app.buffer[app.cursor_row].len();
+            if app.cursor_col < len {
+                app.cursor_col += 1;
+            } else if app.cursor_row + 1 < app.buffer.len() {
+                app.cursor_row += 1;
+                app.cursor_col = 0;
+                ensure_cursor_visible(app);
+            }
+        }
+        KeyCode::Up => {
+            if app.cursor_row > 0 {
+                app.cursor_row -= 1;
+                app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].len());
+                ensure_cursor_visible(app);
+            }
+        }
+        KeyCode::Down => {
+            if app.cursor_row + 1 < app.buffer.len() {
+                app.cursor_row += 1;
+                app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].len());
+                ensure_cursor_visible(app);
+            }
+        }
+        KeyCode::Home => { app.cursor_col = 0; }
+        KeyCode::End => { app.cursor_col = app.buffer[app.cursor_row].len(); }
+        KeyCode::PageUp => {
+            let jump = 10usize;
+            app.cursor_row = app.cursor_row.saturating_sub(jump);
+            app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].len());
+            ensure_cursor_visible(app);
+        }
+        KeyCode::PageDown => {
+            let jump = 10usize;
+            app.cursor_row = (app.cursor_row + jump).min(app.buffer.len().saturating_sub(1));
+            app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].len());
+            ensure_cursor_visible(app);
+        }
+        KeyCode::Enter => {
+            edit_buffer(app, EditKind::Insert, |app| {
+                let line = app.buffer[app.cursor_row].clone();
+                let (left, right) = line.split_at(nth_char_to_byte_idx(&line, app.cursor_col));
+                app.buffer[app.cursor_row] = left.to_string();
+                app.buffer.insert(app.cursor_row + 1, right.to_string());
+                app.cursor_row += 1;
+                app.cursor_col = 0;
+            });
+            ensure_cursor_visible(app);
+        }
+        KeyCode::Backspace => {
+            edit_buffer(app, EditKind::Delete, |app| {
+                if app.cursor_col > 0 {
+                    let line = &mut app.buffer[app.cursor_row];
+                    let prev_byte_idx = nth_char_to_byte_idx(line, app.cursor_col - 1);
+                    line.drain(prev_byte_idx..nth_char_to_byte_idx(line, app.cursor_col));
+                    app.cursor_col -= 1;
+                } else if app.cursor_row > 0 {
+                    let cur = app.buffer.remove(app.cursor_row);
+                    app.cursor_row -= 1;
+                    let prev_len_chars = app.buffer[app.cursor_row].chars().count();
+                    app.buffer[app.cursor_row].push_str(&cur);
+                    app.cursor_col = prev_len_chars;
+                }
+                // At start of file, do nothing
+            });
+            ensure_cursor_visible(app);
+        }
+        KeyCode::Delete => {
+            edit_buffer(app, EditKind::Delete, |app| {
+                let len_chars = app.buffer[app.cursor_row].chars().count();
+                if app.cursor_col < len_chars {
+                    let line = &mut app.buffer[app.cursor_row];
+                    let start = nth_char_to_byte_idx(line, app.cursor_col);
+                    let end = nth_char_to_byte_idx(line, app.cursor_col + 1);
+                    line.drain(start..end);
+                } else if app.cursor_row + 1 < app.buffer.len() {
+                    let next = app.buffer.remove(app.cursor_row + 1);
+                    app.buffer[app.cursor_row].push_str(&next);
+                }
+            });
+        }
+        KeyCode::Char(ch) => {
             // Only insert when no modifiers are held; avoid inserting on Ctrl/Alt/Shift combos
             if key.modifiers.is_empty() && !ch.is_control() {
-                app.buffer[app.cursor_row].insert(app.cursor_col, ch);
-                app.cursor_col += 1;
-                app.dirty = true;
+                edit_buffer(app, EditKind::Insert, |app| {
+                    let line = &mut app.buffer[app.cursor_row];
+                    let at = nth_char_to_byte_idx(line, app.cursor_col);
+                    line.insert(at, ch);
+                    app.cursor_col += 1;
+                });
                 ensure_cursor_visible(app);
             }
         }
@@ -1265,6 +2478,20 @@ fn handle_editor_key_vi(app: &mut App, key: KeyEvent) {
             // Clear pending op unless this is part of a chord
             let mut consumed = false;
             match key.code {
+                // gt / gT: next / previous buffer
+                KeyCode::Char('t') if app.vi_pending_op == Some('g') => {
+                    switch_buffer(app, (app.active_buffer + 1) % app.buffers.len());
+                    app.vi_pending_op = None;
+                    consumed = true;
+                }
+                KeyCode::Char('T') if app.vi_pending_op == Some('g') => {
+                    let count = app.buffers.len();
+                    switch_buffer(app, (app.active_buffer + count - 1) % count);
+                    app.vi_pending_op = None;
+                    consumed = true;
+                }
+                // Motions (h j k l 0 $ gg G) are shared with visual mode
+                _ if vi_motion(app, key) => consumed = true,
                 KeyCode::Char('i') if key.modifiers.is_empty() => {
                     app.vi_mode = ViMode::Insert;
                     app.vi_pending_op = None;
@@ -1282,111 +2509,108 @@ fn handle_editor_key_vi(app: &mut App, key: KeyEvent) {
                 }
                 KeyCode::Char('o') if key.modifiers.is_empty() => {
                     // New line below, move to it, then insert
-                    let next_idx = app.cursor_row + 1;
-                    app.buffer.insert(next_idx, String::new());
-                    app.cursor_row = next_idx;
-                    app.cursor_col = 0;
+                    edit_buffer(app, EditKind::Other, |app| {
+                        let next_idx = app.cursor_row + 1;
+                        app.buffer.insert(next_idx, String::new());
+                        app.cursor_row = next_idx;
+                        app.cursor_col = 0;
+                    });
                     app.vi_mode = ViMode::Insert;
-                    app.dirty = true;
                     ensure_cursor_visible(app);
                     app.vi_pending_op = None;
                     consumed = true;
                 }
                 KeyCode::Char('O') if key.modifiers.is_empty() => {
                     // New line above, move to it, then insert
-                    let cur_idx = app.cursor_row;
-                    app.buffer.insert(cur_idx, String::new());
-                    app.cursor_col = 0;
-                    app.dirty = true;
+                    edit_buffer(app, EditKind::Other, |app| {
+                        let cur_idx = app.cursor_row;
+                        app.buffer.insert(cur_idx, String::new());
+                        app.cursor_col = 0;
+                    });
                     ensure_cursor_visible(app);
                     app.vi_mode = ViMode::Insert;
                     app.vi_pending_op = None;
                     consumed = true;
                 }
-                KeyCode::Char('h') if key.modifiers.is_empty() => {
-                    move_left(app);
-                    consumed = true;
-                }
-                KeyCode::Char('l') if key.modifiers.is_empty() => {
-                    move_right(app);
-                    consumed = true;
-                }
-                KeyCode::Char('j') if key.modifiers.is_empty() => {
-                    move_down(app);
-                    consumed = true;
-                }
-                KeyCode::Char('k') if key.modifiers.is_empty() => {
-                    move_up(app);
-                    consumed = true;
-                }
                 KeyCode::Char('x') if key.modifiers.is_empty() => {
                     // Delete char under cursor
-                    let len_chars = app.buffer[app.cursor_row].chars().count();
-                    if app.cursor_col < len_chars {
-                        let line = &mut app.buffer[app.cursor_row];
-                        let start = nth_char_to_byte_idx(line, app.cursor_col);
-                        let end = nth_char_to_byte_idx(line, app.cursor_col + 1);
-                        line.drain(start..end);
-                        app.dirty = true;
-                    }
-                    consumed = true;
-                }
-                KeyCode::Char('0') if key.modifiers.is_empty() => {
-                    app.cursor_col = 0;
-                    ensure_cursor_visible(app);
+                    edit_buffer(app, EditKind::Delete, |app| {
+                        let len_chars = app.buffer[app.cursor_row].chars().count();
+                        if app.cursor_col < len_chars {
+                            let line = &mut app.buffer[app.cursor_row];
+                            let start = nth_char_to_byte_idx(line, app.cursor_col);
+                            let end = nth_char_to_byte_idx(line, app.cursor_col + 1);
+                            line.drain(start..end);
+                        }
+                    });
                     consumed = true;
                 }
-                KeyCode::Char('$') if key.modifiers.is_empty() => {
-                    app.cursor_col = app.buffer[app.cursor_row].chars().count();
-                    ensure_cursor_visible(app);
+                KeyCode::Char('u') if key.modifiers.is_empty() => {
+                    app_undo(app);
                     consumed = true;
                 }
                 KeyCode::Char('d') if key.modifiers.is_empty() => {
                     if matches!(app.vi_pending_op, Some('d')) {
-                        // dd: delete current line
+                        // dd: delete current line (into the register)
                         app.vi_pending_op = None;
-                        delete_current_line(app);
+                        set_register(app, app.buffer[app.cursor_row].clone(), true);
+                        edit_buffer(app, EditKind::Other, delete_current_line);
                     } else {
                         // Start d operation
                         app.vi_pending_op = Some('d');
                     }
                     consumed = true;
                 }
-                KeyCode::Char('g') if key.modifiers.is_empty() => {
-                    if matches!(app.vi_pending_op, Some('g')) {
-                        // gg: go to top
-                        app.cursor_row = 0;
-                        app.cursor_col = 0;
-                        ensure_cursor_visible(app);
+                KeyCode::Char('y') if key.modifiers.is_empty() => {
+                    if matches!(app.vi_pending_op, Some('y')) {
+                        // yy: yank current line
                         app.vi_pending_op = None;
+                        set_register(app, app.buffer[app.cursor_row].clone(), true);
+                        set_status(app, "Yanked 1 line");
                     } else {
-                        // Start g operation
-                        app.vi_pending_op = Some('g');
+                        app.vi_pending_op = Some('y');
                     }
                     consumed = true;
                 }
-                KeyCode::Char('G') if key.modifiers.is_empty() => {
-                    // End of file
-                    app.cursor_row = app.buffer.len().saturating_sub(1);
-                    app.cursor_col = app.buffer[app.cursor_row].chars().count();
-                    ensure_cursor_visible(app);
+                KeyCode::Char('p') | KeyCode::Char('P') => {
+                    // p pastes after the cursor (or below the line), P before (or above)
+                    paste_register(app, key.code == KeyCode::Char('p'));
                     consumed = true;
                 }
-                KeyCode::Char('p') | KeyCode::Char('P') => {
-                    if key.modifiers.contains(KeyModifiers::CONTROL) {
-                        if !jump_to_matching_bracket(app) {
-                            set_status(app, "No matching bracket at cursor")
-                        }
-                        consumed = true;
-                    }
+                KeyCode::Char(':') => {
+                    run_command(app, Command::Palette);
+                    consumed = true;
+                }
+                KeyCode::Char('v') if key.modifiers.is_empty() => {
+                    app.selection = Some(Selection { anchor: (app.cursor_row, app.cursor_col), kind: SelectionKind::Inclusive });
+                    app.vi_mode = ViMode::Visual;
+                    consumed = true;
+                }
+                KeyCode::Char('V') if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT => {
+                    app.selection = Some(Selection { anchor: (app.cursor_row, app.cursor_col), kind: SelectionKind::Lines });
+                    app.vi_mode = ViMode::VisualLine;
+                    consumed = true;
                 }
                 KeyCode::Enter => {
                     // In Normal mode, Enter: do nothing
                     consumed = true;
                 }
+                KeyCode::Char('/') if key.modifiers.is_empty() => {
+                    open_search(app);
+                    consumed = true;
+                }
+                KeyCode::Char('n') if key.modifiers.is_empty() => {
+                    search_step(app, true);
+                    consumed = true;
+                }
+                KeyCode::Char('N') if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT => {
+                    search_step(app, false);
+                    consumed = true;
+                }
                 KeyCode::Esc => {
-                    // Already normal; clear pending op
+                    // Already normal; clear pending op and search highlighting
                     app.vi_pending_op = None;
+                    app.search_highlight = false;
                     consumed = true;
                 }
                 _ => {}
@@ -1396,6 +2620,48 @@ fn handle_editor_key_vi(app: &mut App, key: KeyEvent) {
                 app.vi_pending_op = None;
             }
         }
+        ViMode::Visual | ViMode::VisualLine => {
+            if vi_motion(app, key) {
+                return;
+            }
+            match key.code {
+                KeyCode::Char('y') if key.modifiers.is_empty() => {
+                    // Like Vim, the cursor returns to the start of the yanked text
+                    let start = selection_range(app).map(|(start, _)| start);
+                    let linewise = app.selection.is_some_and(|s| s.kind == SelectionKind::Lines);
+                    copy_selection(app);
+                    leave_visual_mode(app);
+                    if let Some((row, col)) = start {
+                        app.cursor_row = row;
+                        if !linewise {
+                            app.cursor_col = col;
+                        }
+                    }
+                }
+                KeyCode::Char('d') | KeyCode::Char('x') if key.modifiers.is_empty() => {
+                    cut_selection(app);
+                    leave_visual_mode(app);
+                }
+                KeyCode::Char('v') if key.modifiers.is_empty() => {
+                    if app.vi_mode == ViMode::Visual {
+                        leave_visual_mode(app);
+                    } else if let Some(selection) = app.selection.as_mut() {
+                        selection.kind = SelectionKind::Inclusive;
+                        app.vi_mode = ViMode::Visual;
+                    }
+                }
+                KeyCode::Char('V') if key.modifiers.is_empty() || key.modifiers == KeyModifiers::SHIFT => {
+                    if app.vi_mode == ViMode::VisualLine {
+                        leave_visual_mode(app);
+                    } else if let Some(selection) = app.selection.as_mut() {
+                        selection.kind = SelectionKind::Lines;
+                        app.vi_mode = ViMode::VisualLine;
+                    }
+                }
+                KeyCode::Esc => leave_visual_mode(app),
+                _ => {}
+            }
+        }
     }
 
     // Clamp and maintain invariants
@@ -1411,6 +2677,53 @@ fn handle_editor_key_vi(app: &mut App, key: KeyEvent) {
     }
 }
 
+// Vi cursor motions shared by normal and visual mode; returns whether the key was one
+fn vi_motion(app: &mut App, key: KeyEvent) -> bool {
+    if !key.modifiers.is_empty() && key.modifiers != KeyModifiers::SHIFT {
+        return false;
+    }
+    match key.code {
+        KeyCode::Char('h') | KeyCode::Left => move_left(app),
+        KeyCode::Char('l') | KeyCode::Right => move_right(app),
+        KeyCode::Char('j') | KeyCode::Down => move_down(app),
+        KeyCode::Char('k') | KeyCode::Up => move_up(app),
+        KeyCode::Char('0') => {
+            app.cursor_col = 0;
+            ensure_cursor_visible(app);
+        }
+        KeyCode::Char('$') => {
+            app.cursor_col = app.buffer[app.cursor_row].chars().count();
+            ensure_cursor_visible(app);
+        }
+        KeyCode::Char('g') => {
+            if matches!(app.vi_pending_op, Some('g')) {
+                // gg: go to top
+                app.cursor_row = 0;
+                app.cursor_col = 0;
+                ensure_cursor_visible(app);
+                app.vi_pending_op = None;
+            } else {
+                // Start g operation
+                app.vi_pending_op = Some('g');
+            }
+        }
+        KeyCode::Char('G') => {
+            // End of file
+            app.cursor_row = app.buffer.len().saturating_sub(1);
+            app.cursor_col = app.buffer[app.cursor_row].chars().count();
+            ensure_cursor_visible(app);
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn leave_visual_mode(app: &mut App) {
+    app.selection = None;
+    app.vi_mode = ViMode::Normal;
+    app.vi_pending_op = None;
+}
+
 fn handle_save_dialog_key(app: &mut App, key: KeyEvent) -> io::Result<()> {
     match key.code {
         KeyCode::Esc => {
@@ -1448,86 +2761,21 @@ fn handle_save_dialog_key(app: &mut App, key: KeyEvent) -> io::Result<()> {
     Ok(())
 }
 
-fn handle_open_dialog_key(app: &mut App, key: KeyEvent) -> io::Result<()> {
-    match key.code {
-        KeyCode::Esc => {
-            app.show_open_dialog = false;
-            app.open_error = None;
-        }
-        KeyCode::Enter => {
-            let name = app.open_name_input.trim().to_string();
-            if name.is_empty() {
-                app.open_error = Some("Path cannot be empty".to_string());
-            } else {
-                // Resolve to absolute path for consistent filename display
-                let mut path = PathBuf::from(&name);
-                if path.is_relative() {
-                    path = std::env::current_dir()?.join(path);
-                }
-
-                // If there are unsaved changes, ask for confirmation first
-                if app.dirty {
-                    app.confirm_message = "You have unsaved changes. Open anyway? Unsaved changes will be lost.".to_string();
-                    app.confirm_pending_open = Some(path);
-                    app.show_open_dialog = false;
-                    app.show_confirm_dialog = true;
-                } else {
-                    match app_open_file(app, &path) {
-                        Ok(_) => {
-                            app.show_open_dialog = false;
-                            app.open_error = None;
-                        }
-                        Err(err) => {
-                            app.open_error = Some(format!("Open failed: {}", err));
-                            set_status(app, "Open failed");
-                        }
-                    }
-                }
-            }
-        }
-        KeyCode::Backspace => {
-            app.open_name_input.pop();
-        }
-        KeyCode::Char(ch) => {
-            if key.modifiers.is_empty() && !ch.is_control() {
-                app.open_name_input.push(ch);
-            }
-        }
-        _ => {}
-    }
-    Ok(())
-}
-
 fn handle_confirm_dialog_key(app: &mut App, key: KeyEvent) -> io::Result<()> {
     match key.code {
         KeyCode::Enter => {
-            if let Some(path) = app.confirm_pending_open.take() {
-                // Attempt to open; on error, return to open dialog with error message
-                match app_open_file(app, &path) {
-                    Ok(_) => {
-                        app.show_confirm_dialog = false;
-                        app.open_error = None;
-                        app.show_confirm_dialog = false;
-                    }
-                    Err(err) => {
-                        app.open_error = Some(format!("Open failed: {}", err));
-                        app.show_confirm_dialog = false;
-                        app.show_open_dialog = true;
-                        set_status(app, "Open failed");
-                    }
-                }
-            } else if app.confirm_pending_quit {
+            if app.confirm_pending_quit {
                 // Quit confirmed
                 app.confirm_pending_quit = false;
                 // Hide confirm dialog
                 app.show_confirm_dialog = false;
                 // Signal to main loop to exit
                 app.should_quit = true;
-            } else if app.confirm_pending_new {
-                // New file confirmed
-                app.confirm_pending_new = false;
+            } else if app.confirm_pending_close {
+                // Close confirmed; unsaved changes are dropped
+                app.confirm_pending_close = false;
                 app.show_confirm_dialog = false;
-                app_new_file(app);
+                close_active_buffer(app);
             } else {
                 // No pending action; just close
                 app.show_confirm_dialog = false;
@@ -1535,16 +2783,9 @@ fn handle_confirm_dialog_key(app: &mut App, key: KeyEvent) -> io::Result<()> {
 
         }
         KeyCode::Esc => {
-            // Cancel; return to the open dialog if we came from there
-            app.show_confirm_dialog = false;
-            if app.confirm_pending_open.is_some() {
-                app.show_open_dialog = true;
-            }
-            // Keep the pending path so the user can adjust; or clear it
-            // Clear to avoid accidental reuse
-            app.confirm_pending_open = None;
+            // Cancel
             app.confirm_pending_quit = false;
-            app.confirm_pending_new = false;
+            app.confirm_pending_close = false;
             app.show_confirm_dialog = false;
         }
         _ => {}
@@ -1552,15 +2793,178 @@ fn handle_confirm_dialog_key(app: &mut App, key: KeyEvent) -> io::Result<()> {
     Ok(())
 }
 
-fn handle_input_dialog_key(app: &mut App, key: KeyEvent) -> io::Result<()> {
+// The dialog's rows: the run settings, then the tape policy
+fn settings_values(settings: &ReplSettings, keep_tape: bool) -> Vec<(&'static str, String)> {
+    let mut values = settings.entries();
+    values.push(("tape", if keep_tape { "keep" } else { "reset" }.to_string()));
+    values
+}
+
+// What Ctrl+S writes to the [ide] table: the applied values of the settings edited this session
+fn settings_to_save(app: &App) -> Vec<(&'static str, String)> {
+    settings_values(&app.settings, app.keep_tape)
+        .into_iter()
+        .filter(|(name, _)| app.edited_settings.contains(name))
+        .collect()
+}
+
+fn open_settings(app: &mut App) {
+    let values = settings_values(&app.settings, app.keep_tape);
+    app.settings_dialog = SettingsDialog { values, selected: 0, error: None };
+    app.show_settings = true;
+}
+
+fn handle_settings_key(app: &mut App, key: KeyEvent) {
+    let dialog = &mut app.settings_dialog;
+    let count = dialog.values.len().max(1);
     match key.code {
-        KeyCode::Esc => {
-            // Send EOF
-            if let Some(h) = app.runner.as_ref() {
-                let _ = h.tx_cmd.send(UiCmd::ProvideInput(None));
+        KeyCode::Esc => app.show_settings = false,
+        KeyCode::Up | KeyCode::BackTab => dialog.selected = (dialog.selected + count - 1) % count,
+        KeyCode::Down | KeyCode::Tab => dialog.selected = (dialog.selected + 1) % count,
+        KeyCode::Left | KeyCode::Right => {
+            if let Some((name, value)) = dialog.values.get_mut(dialog.selected) {
+                let choices: &[&str] = match *name {
+                    "eof" => &["zero", "unchanged", "max"],
+                    "tape" => &["reset", "keep"],
+                    _ => &[],
+                };
+                if !choices.is_empty() {
+                    let at = choices.iter().position(|c| c == value).unwrap_or(0);
+                    let next = if key.code == KeyCode::Right { at + 1 } else { at + choices.len() - 1 };
+                    *value = choices[next % choices.len()].to_string();
+                }
             }
-            app.show_input_dialog = false;
-            app.input_error = None;
+        }
+        KeyCode::Backspace => {
+            if let Some((_, value)) = dialog.values.get_mut(dialog.selected) {
+                value.pop();
+            }
+        }
+        KeyCode::Enter => {
+            // An invalid value keeps the dialog open with the error
+            if !apply_settings(app) {
+                return;
+            }
+            app.show_settings = false;
+            let note = if app.runner.is_some() { " (from the next run)" } else { "" };
+            set_status(app, &format!("Settings applied for this session{note}"));
+        }
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            if !apply_settings(app) {
+                return;
+            }
+            let entries = settings_to_save(app);
+            if entries.is_empty() {
+                app.show_settings = false;
+                set_status(app, "No settings were changed; nothing saved");
+                return;
+            }
+            match crate::config::write_section("ide", &entries) {
+                Ok(path) => {
+                    app.show_settings = false;
+                    set_status(app, &format!("Settings saved to {}", path.display()));
+                }
+                Err(err) => app.settings_dialog.error = Some(format!("Cannot save settings: {err}")),
+            }
+        }
+        KeyCode::Char(ch) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+            if let Some((_, value)) = dialog.values.get_mut(dialog.selected) {
+                value.push(ch);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Validate every value in the settings dialog; nothing changes unless all of them are valid
+fn apply_settings(app: &mut App) -> bool {
+    let mut settings = app.settings.clone();
+    let mut keep_tape = app.keep_tape;
+    for (name, value) in &app.settings_dialog.values {
+        let value = value.trim();
+        let result = match (*name, value) {
+            ("tape", "reset" | "keep") => {
+                keep_tape = value == "keep";
+                Ok(())
+            }
+            ("tape", _) => Err(format!("invalid tape policy '{value}' (reset or keep)")),
+            _ => settings.set(name, value),
+        };
+        if let Err(err) = result {
+            app.settings_dialog.error = Some(err);
+            return false;
+        }
+    }
+    let before = settings_values(&app.settings, app.keep_tape);
+    for ((name, old), (_, new)) in before.iter().zip(settings_values(&settings, keep_tape)) {
+        if *old != new {
+            app.edited_settings.insert(name);
+        }
+    }
+    if settings.memory != app.settings.memory {
+        // Keep the cells that still fit, in every buffer
+        let memory = settings.memory;
+        app.tape.resize(memory, 0);
+        app.tape_ptr = app.tape_ptr.min(memory - 1);
+        app.tape_cursor = app.tape_cursor.min(memory - 1);
+        for buf in &mut app.buffers {
+            buf.tape.resize(memory, 0);
+            buf.tape_ptr = buf.tape_ptr.min(memory - 1);
+        }
+    }
+    app.settings = settings;
+    app.keep_tape = keep_tape;
+    true
+}
+
+fn handle_input_panel_key(app: &mut App, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => app.show_input_panel = false,
+        KeyCode::Char('d') if ctrl => app.show_input_panel = false,
+        KeyCode::Char('o') if ctrl => open_input_file_browser(app),
+        KeyCode::Char('x') if ctrl => {
+            app.prepared_input.file = None;
+            set_status(app, "Input file detached");
+        }
+        KeyCode::Char('k') if ctrl => app.prepared_input.text.clear(),
+        KeyCode::Char('p') if ctrl => app.prompt_when_exhausted = !app.prompt_when_exhausted,
+        KeyCode::Char('e') if ctrl => {
+            app.settings.eof = match app.settings.eof {
+                EofBehavior::Zero => EofBehavior::Unchanged,
+                EofBehavior::Unchanged => EofBehavior::Max,
+                EofBehavior::Max => EofBehavior::Zero,
+            };
+        }
+        KeyCode::Char('v') if ctrl => {
+            if let Some(register) = &app.register {
+                app.prepared_input.text.push_str(&register.text);
+                if register.linewise {
+                    app.prepared_input.text.push('\n');
+                }
+            }
+        }
+        KeyCode::Enter => app.prepared_input.text.push('\n'),
+        KeyCode::Tab => app.prepared_input.text.push('\t'),
+        KeyCode::Backspace => {
+            app.prepared_input.text.pop();
+        }
+        KeyCode::Char(ch) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+            app.prepared_input.text.push(ch);
+        }
+        _ => {}
+    }
+}
+
+fn handle_input_dialog_key(app: &mut App, key: KeyEvent) -> io::Result<()> {
+    match key.code {
+        KeyCode::Esc => {
+            // Send EOF
+            if let Some(h) = app.runner.as_ref() {
+                let _ = h.tx_cmd.send(UiCmd::ProvideInput(None));
+            }
+            app.show_input_dialog = false;
+            app.input_error = None;
             set_status(app, "Sent EOF");
         }
         KeyCode::Enter => {
@@ -1617,7 +3021,13 @@ fn ensure_cursor_visible(app: &mut App) {
 }
 
 // Syntax highlighting for BF tokens + matching bracket highlighting
-fn highlight_bf_line(line: &str, app: &App, row: usize) -> Line<'static> {
+fn highlight_bf_line(
+    line: &str,
+    app: &App,
+    row: usize,
+    hits: &SearchHits,
+    selected: Option<((usize, usize), (usize, usize))>,
+) -> Line<'static> {
     let (match_row_col, cursor_on_bracket) = if app.focused == Focus::Editor
         && row == app.cursor_row
         && app.cursor_col < line.chars().count()
@@ -1657,6 +3067,15 @@ fn highlight_bf_line(line: &str, app: &App, row: usize) -> Line<'static> {
         } else {
             base
         };
+        let styled = if hits.current.contains(&(row, i)) {
+            styled.bg(Color::LightYellow).fg(Color::Black)
+        } else if hits.all.contains(&(row, i)) {
+            styled.bg(Color::DarkGray)
+        } else if selected.is_some_and(|(start, end)| (row, i) >= start && (row, i) < end) {
+            styled.bg(Color::Blue)
+        } else {
+            styled
+        };
 
         spans.push(Span::styled(ch.to_string(), styled));
     }
@@ -1775,15 +3194,217 @@ fn build_bf_mapping(lines: &[String]) -> Mapping {
     }
 }
 
+// Search hits to highlight in the editor: every matched cell, and the match under the cursor
+#[derive(Default)]
+struct SearchHits {
+    all: HashSet<(usize, usize)>,
+    current: Vec<(usize, usize)>,
+}
+
+fn search_hits(app: &App) -> SearchHits {
+    if !(app.show_search || app.search_highlight) || app.search_query.is_empty() {
+        return SearchHits::default();
+    }
+    let matches = find_matches(&app.buffer, &app.search_query, app.search_code_only);
+    let current = matches
+        .iter()
+        .find(|m| m.cells[0] == (app.cursor_row, app.cursor_col))
+        .map(|m| m.cells.clone())
+        .unwrap_or_default();
+    SearchHits { all: matches.into_iter().flat_map(|m| m.cells).collect(), current }
+}
+
+// Non-overlapping matches of `query`, in buffer order. In code-only mode the query and the
+// buffer are reduced to Brainfuck instructions first, so `[-]` also finds `[ - ]` or a loop
+// split across lines
+fn find_matches(lines: &[String], query: &str, code_only: bool) -> Vec<SearchMatch> {
+    if code_only {
+        let pattern = bf_only(query);
+        if pattern.is_empty() {
+            return Vec::new();
+        }
+        let Mapping { bf_seq, bf_idx_to_orig, .. } = build_bf_mapping(lines);
+        // Instructions are ASCII, so byte offsets in bf_seq are instruction indices
+        return bf_seq
+            .match_indices(&pattern)
+            .map(|(i, _)| SearchMatch { cells: (i..i + pattern.len()).map(|idx| bf_idx_to_orig[&idx]).collect() })
+            .collect();
+    }
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let query_len = query.chars().count();
+    let mut matches = Vec::new();
+    for (row, line) in lines.iter().enumerate() {
+        for (byte_idx, _) in line.match_indices(query) {
+            let col = line[..byte_idx].chars().count();
+            matches.push(SearchMatch { cells: (col..col + query_len).map(|c| (row, c)).collect() });
+        }
+    }
+    matches
+}
+
+fn open_search(app: &mut App) {
+    // A selection becomes the replace-all scope; the cursor is free to move while searching
+    app.search_scope = selection_range(app);
+    app.selection = None;
+    if matches!(app.vi_mode, ViMode::Visual | ViMode::VisualLine) {
+        app.vi_mode = ViMode::Normal;
+    }
+    app.show_search = true;
+    app.search_field = SearchField::Find;
+    app.search_origin = (app.cursor_row, app.cursor_col);
+    app.search_query_selected = !app.search_query.is_empty();
+    search_update(app);
+}
+
+// Incremental search: move to the first match at or after where the search started
+fn search_update(app: &mut App) {
+    let matches = find_matches(&app.buffer, &app.search_query, app.search_code_only);
+    let target = matches
+        .iter()
+        .map(|m| m.cells[0])
+        .find(|start| *start >= app.search_origin)
+        .or_else(|| matches.first().map(|m| m.cells[0]));
+    match target {
+        Some(start) => (app.cursor_row, app.cursor_col) = start,
+        None => (app.cursor_row, app.cursor_col) = app.search_origin,
+    }
+    ensure_cursor_visible(app);
+}
+
+// Jump to the next (or previous) match of the last query, wrapping around the buffer
+fn search_step(app: &mut App, forward: bool) {
+    if app.search_query.is_empty() {
+        set_status(app, "No search query (Ctrl+F to search)");
+        return;
+    }
+    let matches = find_matches(&app.buffer, &app.search_query, app.search_code_only);
+    let cursor = (app.cursor_row, app.cursor_col);
+    let starts = matches.iter().map(|m| m.cells[0]);
+    let target = if forward {
+        starts.clone().find(|start| *start > cursor).or_else(|| starts.clone().next())
+    } else {
+        starts.clone().rev().find(|start| *start < cursor).or_else(|| starts.clone().next_back())
+    };
+    let Some(start) = target else {
+        set_status(app, &format!("Not found: {}", app.search_query));
+        return;
+    };
+    (app.cursor_row, app.cursor_col) = start;
+    app.search_highlight = true;
+    ensure_cursor_visible(app);
+    let index = matches.iter().position(|m| m.cells[0] == start).unwrap_or(0);
+    set_status(app, &format!("Match {}/{}", index + 1, matches.len()));
+}
+
+// Replace every match (inside the search scope, if any); a code-only match is replaced from its first to its last instruction,
+// including comments in between. One undo step
+fn replace_all(app: &mut App) -> usize {
+    let mut matches = find_matches(&app.buffer, &app.search_query, app.search_code_only);
+    if let Some((start, end)) = app.search_scope {
+        matches.retain(|m| m.cells[0] >= start && *m.cells.last().expect("matches are non-empty") < end);
+    }
+    if matches.is_empty() {
+        return 0;
+    }
+    let replacement = app.replace_text.clone();
+    edit_buffer(app, EditKind::Other, |app| {
+        // Back to front, so earlier positions stay valid
+        for m in matches.iter().rev() {
+            let (end_row, end_col) = *m.cells.last().expect("matches are non-empty");
+            replace_range(&mut app.buffer, m.cells[0], (end_row, end_col + 1), &replacement);
+        }
+        app.cursor_row = app.cursor_row.min(app.buffer.len() - 1);
+        app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].chars().count());
+    });
+    ensure_cursor_visible(app);
+    matches.len()
+}
+
+fn handle_search_key(app: &mut App, key: KeyEvent) {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let was_selected = std::mem::take(&mut app.search_query_selected);
+    match key.code {
+        KeyCode::Esc => {
+            // Cancel: back to where the search started
+            app.show_search = false;
+            app.search_highlight = false;
+            (app.cursor_row, app.cursor_col) = app.search_origin;
+            ensure_cursor_visible(app);
+        }
+        KeyCode::Enter => match app.search_field {
+            SearchField::Find => {
+                // Accept: stay on the current match and keep highlighting
+                app.show_search = false;
+                app.search_highlight = !app.search_query.is_empty();
+            }
+            SearchField::Replace => {
+                let count = replace_all(app);
+                app.show_search = false;
+                app.search_highlight = false;
+                set_status(app, &format!("Replaced {count} occurrence{}", if count == 1 { "" } else { "s" }));
+            }
+        },
+        KeyCode::Tab | KeyCode::BackTab => {
+            app.search_field = match app.search_field {
+                SearchField::Find => SearchField::Replace,
+                SearchField::Replace => SearchField::Find,
+            };
+        }
+        KeyCode::Down | KeyCode::F(3) if !key.modifiers.contains(KeyModifiers::SHIFT) => search_step(app, true),
+        KeyCode::Up | KeyCode::F(3) | KeyCode::F(15) => search_step(app, false),
+        KeyCode::Char('b') if ctrl => {
+            app.search_code_only = !app.search_code_only;
+            search_update(app);
+        }
+        KeyCode::Backspace => {
+            match app.search_field {
+                SearchField::Find => {
+                    if was_selected {
+                        app.search_query.clear();
+                    } else {
+                        app.search_query.pop();
+                    }
+                    search_update(app);
+                }
+                SearchField::Replace => {
+                    app.replace_text.pop();
+                }
+            }
+        }
+        KeyCode::Char(ch) if !ctrl && !key.modifiers.contains(KeyModifiers::ALT) && !ch.is_control() => {
+            match app.search_field {
+                SearchField::Find => {
+                    if was_selected {
+                        app.search_query.clear();
+                    }
+                    app.search_query.push(ch);
+                    search_update(app);
+                }
+                SearchField::Replace => app.replace_text.push(ch),
+            }
+        }
+        _ => {}
+    }
+}
+
 // Start the Brainfuck runner thread with cooperative cancellation and channels
 fn start_runner(app: &mut App) {
-    // If a runner is already active, ignore
+    // If a runner is already active, ignore (a paused one is resumed)
     if app.runner.is_some() {
+        if app.paused {
+            toggle_pause(app);
+        }
         return;
     }
 
-    // Prepare source (keep only BF tokens)
-    let source = app_current_source(app);
+    // Prepare source (keep only BF tokens): the selection if there is one, else the buffer
+    let selected = selection_range(app);
+    let source = match selected {
+        Some((start, end)) => text_in_range(&app.buffer, start, end),
+        None => app_current_source(app),
+    };
     let filtered = bf_only(&source);
     if filtered.trim().is_empty() {
         // No BF code to run
@@ -1791,6 +3412,30 @@ fn start_runner(app: &mut App) {
         return;
     }
 
+    // Prepared input: the attached file, then the panel text
+    let mut prepared = Vec::new();
+    if let Some(path) = app.prepared_input.file.clone() {
+        match fs::read(&path) {
+            Ok(bytes) => prepared.extend(bytes),
+            Err(err) => {
+                set_status(app, &format!("Cannot read input file {}: {}", path.display(), describe_io_error(&err)));
+                return;
+            }
+        }
+    }
+    prepared.extend_from_slice(app.prepared_input.text.as_bytes());
+    let prepared = Arc::new(Mutex::new(VecDeque::from(prepared)));
+    let prompt_when_exhausted = app.prompt_when_exhausted;
+    let ReplSettings { timeout_ms, max_steps, memory: tape_size, eof } = app.settings.clone();
+    // The buffer's last tape and pointer, or a fresh tape
+    let (memory, pointer) = if app.keep_tape {
+        let mut memory = app.tape.clone();
+        memory.resize(tape_size, 0);
+        (memory, app.tape_ptr.min(tape_size - 1))
+    } else {
+        (vec![0; tape_size], 0)
+    };
+
     // Channels
     let (tx_msg, rx_msg) = mpsc::channel::<RunnerMsg>();
     let (tx_cmd, rx_cmd) = mpsc::channel::<UiCmd>();
@@ -1798,72 +3443,151 @@ fn start_runner(app: &mut App) {
     // Cancel flag and step control
     let cancel = Arc::new(AtomicBool::new(false));
     let cancel_for_timer = cancel.clone();
-
-    // Limits from environment
-    let timeout_ms = std::env::var("BF_TIMEOUT_MS").ok().and_then(|s| s.parse::<usize>().ok()).unwrap_or(2_000);
-    let max_steps = std::env::var("BF_MAX_STEPS").ok().and_then(|s| s.parse::<usize>().ok());
+    let snapshot = Arc::new(AtomicBool::new(false));
+    let view = Arc::new(Mutex::new(app.tape_view.get()));
+    let pause = Arc::new(AtomicBool::new(false));
+    // Set while the program waits for input or is paused; that time does not count toward the timeout
+    let blocked = Arc::new(AtomicBool::new(false));
 
     // Make rx_cmd accessible from callbacks invoked during execution
     let rx_cmd_shared = Arc::new(Mutex::new(rx_cmd));
 
     // Spawn worker thread
     let program = filtered.clone();
+    let (snapshot_for_hook, pause_for_hook, view_for_hook) = (snapshot.clone(), pause.clone(), view.clone());
+    // The pane shows the starting tape until the first snapshot
+    app.tape.clone_from(&memory);
+    app.tape_ptr = pointer;
     thread::spawn(move || {
-        // Timer thread: flip cancel after wall-clock timeout
+        // Timer thread: flip cancel once the program has run for the timeout (if there is one)
         let cancel_for_timer = cancel_for_timer.clone();
         let cancel_clone = cancel_for_timer.clone();
-        thread::spawn(move || {
-            thread::sleep(Duration::from_millis(timeout_ms as u64));
-            cancel_clone.store(true, std::sync::atomic::Ordering::Relaxed);
-        });
+        let blocked_for_timer = blocked.clone();
+        if let Some(timeout_ms) = timeout_ms {
+            thread::spawn(move || {
+                let tick = Duration::from_millis(10);
+                let mut remaining = Duration::from_millis(timeout_ms);
+                while !remaining.is_zero() {
+                    if cancel_clone.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    thread::sleep(tick);
+                    if !blocked_for_timer.load(Ordering::Relaxed) {
+                        remaining = remaining.saturating_sub(tick);
+                    }
+                }
+                cancel_clone.store(true, std::sync::atomic::Ordering::Relaxed);
+            });
+        }
 
         // Build the reader and wire callbacks
         let mut bf = BrainfuckReader::new(program);
+        bf.set_state(memory, pointer);
+        bf.set_eof_behavior(eof);
 
-        // Output: forward produced bytes to UI
-        let tx_out = tx_msg.clone();
+        // Output: collected and sent in batches, along with each tape snapshot, before the
+        // program blocks for input or a pause, and at the end
+        let pending_output = Arc::new(Mutex::new(Vec::new()));
+        let (pending_for_sink, tx_out) = (pending_output.clone(), tx_msg.clone());
         bf.set_output_sink(Box::new(move |bytes: &[u8]| {
-            // Send as a batch; UI appends to its buffer
-            let _ = tx_out.send(RunnerMsg::Output(bytes.to_vec()));
+            let mut pending = pending_for_sink.lock().expect("output mutex poisoned");
+            pending.extend_from_slice(bytes);
+            if pending.len() >= OUTPUT_BATCH {
+                let _ = tx_out.send(RunnerMsg::Output(std::mem::take(&mut *pending)));
+            }
         }));
 
         // Input: ask UI, block until ProvideInput arrives (or channel closes)
         let tx_needs_input = tx_msg.clone();
         let rx_input = rx_cmd_shared.clone();
+        let blocked_for_input = blocked.clone();
+        let pending_for_input = pending_output.clone();
         bf.set_input_provider(Box::new(move || {
+            // Prepared input first; once it is used up, ask the UI or report EOF
+            if let Some(b) = prepared.lock().expect("prepared input mutex poisoned").pop_front() {
+                return Some(b);
+            }
+            if !prompt_when_exhausted {
+                return None;
+            }
+            // The prompt usually follows output the user needs to see first
+            flush_output(&pending_for_input, &tx_needs_input);
             let _ = tx_needs_input.send(RunnerMsg::NeedsInput);
+            blocked_for_input.store(true, Ordering::Relaxed);
             // Wait for a ProvideInput command; None if UI side dropped
-            let recv_res = {
-                let lock = rx_input.lock().expect("rx_cmd mutex poisoned");
-                lock.recv()
+            let lock = rx_input.lock().expect("rx_cmd mutex poisoned");
+            let byte = loop {
+                match lock.recv() {
+                    Ok(UiCmd::ProvideInput(b)) => break b,
+                    Ok(UiCmd::Stop) => break None, // treat Stop as EOF for input
+                    Ok(_) => {} // pause commands only apply between instructions
+                    Err(_) => break None, // channel closed
+                }
             };
-            match recv_res {
-                Ok(UiCmd::ProvideInput(b)) => b,
-                Ok(UiCmd::Stop) => None, // treat Stop as EOF for input
-                Err(_) => None, // channel closed
-            }
+            blocked_for_input.store(false, Ordering::Relaxed);
+            byte
         }));
 
-        // Tape observer: emit 128-cell window snapshots
-        bf.set_tape_observer(
-            128, { // Window size requested from the engine
-                let tx = tx_msg.clone();
-                move |ptr, base, window| {
-                    // copy to fixed array expected by UI
-                    let mut buf = [0u8; 128];
-                    buf[..window.len().min(128)].copy_from_slice(&window[..window.len().min(128)]);
-                    let _ = tx.send(RunnerMsg::Tape { ptr, base, window: buf });
+        // After each instruction: a snapshot of the visible cells when the UI asked for one, and
+        // the pause loop, where the UI may edit cells until it resumes or stops the program
+        let tx_tape = tx_msg.clone();
+        let rx_pause = rx_cmd_shared.clone();
+        let cancel_for_pause = cancel_for_timer.clone();
+        let pending_for_hook = pending_output.clone();
+        let send_view = move |tx: &mpsc::Sender<RunnerMsg>, ptr: usize, memory: &[u8]| {
+            let (start, end) = *view_for_hook.lock().expect("tape view mutex poisoned");
+            let end = end.min(memory.len());
+            let start = start.min(end);
+            let _ = tx.send(RunnerMsg::Tape { ptr, start, cells: memory[start..end].to_vec() });
+        };
+        bf.set_step_hook(move |ptr, memory| {
+            // Plain loads keep the common path cheap; the UI sets these at most once per tick
+            let pausing = pause_for_hook.load(Ordering::Relaxed);
+            let wanted = snapshot_for_hook.load(Ordering::Relaxed) && snapshot_for_hook.swap(false, Ordering::Relaxed);
+            if wanted || pausing {
+                flush_output(&pending_for_hook, &tx_tape);
+                send_view(&tx_tape, ptr, memory);
+            }
+            if !pausing {
+                return;
+            }
+            blocked.store(true, Ordering::Relaxed);
+            let _ = tx_tape.send(RunnerMsg::Paused);
+            let lock = rx_pause.lock().expect("rx_cmd mutex poisoned");
+            loop {
+                match lock.recv() {
+                    Ok(UiCmd::SetCell { addr, value }) => {
+                        if let Some(cell) = memory.get_mut(addr) {
+                            *cell = value;
+                        }
+                        send_view(&tx_tape, ptr, memory);
+                    }
+                    Ok(UiCmd::Snapshot) => send_view(&tx_tape, ptr, memory),
+                    Ok(UiCmd::Resume) => break,
+                    Ok(UiCmd::ProvideInput(_)) => {}
+                    Ok(UiCmd::Stop) | Err(_) => {
+                        cancel_for_pause.store(true, Ordering::Relaxed);
+                        break;
+                    }
                 }
             }
-        );
+            pause_for_hook.store(false, Ordering::Relaxed);
+            blocked.store(false, Ordering::Relaxed);
+            let _ = tx_tape.send(RunnerMsg::Resumed);
+        });
 
         // Run BF with cooperative cancellation
         let ctrl = StepControl::new(max_steps, cancel_for_timer.clone());
         let res = {
             bf.run_with_control(ctrl)
         };
+        // Also lets the timer thread finish
+        cancel_for_timer.store(true, Ordering::Relaxed);
 
-        // Report completion
+        // Report completion, with the remaining output and the final tape
+        flush_output(&pending_output, &tx_msg);
+        let (memory, ptr) = bf.into_state();
+        let _ = tx_msg.send(RunnerMsg::Tape { ptr, start: 0, cells: memory });
         let _ = tx_msg.send(RunnerMsg::Halted(res));
     });
 
@@ -1872,12 +3596,25 @@ fn start_runner(app: &mut App) {
         tx_cmd,
         rx_msg,
         cancel,
+        snapshot,
+        view,
+        pause,
     });
+    app.paused = false;
     app.running = true;
+    app.run_buffer = app.active_buffer;
 
     // Reset previous output buffer for a fresh run
     app.output.clear();
-    set_status(app, "Running...");
+    set_status(app, if selected.is_some() { "Running selection..." } else { "Running..." });
+}
+
+// Send the output collected by the runner so far, if any
+fn flush_output(pending: &Mutex<Vec<u8>>, tx: &mpsc::Sender<RunnerMsg>) {
+    let mut pending = pending.lock().expect("output mutex poisoned");
+    if !pending.is_empty() {
+        let _ = tx.send(RunnerMsg::Output(std::mem::take(&mut *pending)));
+    }
 }
 
 // Helper: get the current editor buffer as a newline-joined string
@@ -1937,31 +3674,44 @@ fn output_display_lines(app: &App) -> u16 {
 // Open a file into the editor buffer
 // Set filename and clear dirty flag on success
 fn app_open_file(app: &mut App, path: &Path) -> io::Result<()> {
-    let content = fs::read_to_string(path)?;
+    // Only the eight instructions matter, so a file that is not valid UTF-8 is still opened;
+    // undecodable bytes become U+FFFD and the status line says so
+    let bytes = fs::read(path)?;
+    let (content, lossy) = match String::from_utf8(bytes) {
+        Ok(text) => (text, false),
+        Err(err) => (String::from_utf8_lossy(err.as_bytes()).into_owned(), true),
+    };
     // Split preserving empty final line if present
     let mut lines: Vec<String> = content.split('\n').map(|s| s.to_string()).collect();
     if lines.is_empty() {
         lines.push(String::new());
     }
-    app.buffer = lines;
+    // Replacing the text is one undo step, so Ctrl+Z brings back what the buffer held before
+    edit_buffer(app, EditKind::Other, |app| app.buffer = lines);
     app.cursor_row = 0;
     app.cursor_col = 0;
     app.scroll_row = 0;
     app.filename = Some(path.to_string_lossy().to_string());
     app.dirty = false;
+    app.history.mark_saved();
 
     // Clear runtime/output state for new file
     app.output.clear();
     app.tape_ptr = 0;
-    app.tape_window_base = 0;
-    app.tape_window = [0u8; 128];
+    app.tape = vec![0; app.settings.memory];
+    app.tape_cursor = 0;
     
     // Position cursor at end of the file and ensure it's visible
     app.cursor_row = app.buffer.len().saturating_sub(1);
     app.cursor_col = app.buffer[app.cursor_row].chars().count();
     ensure_cursor_visible(app);
 
-    set_status(app, &format!("Opened {}", path.display()));
+    remember_recent(app, path);
+    if lossy {
+        set_status(app, &format!("Opened {} (not valid UTF-8; invalid bytes replaced)", path.display()));
+    } else {
+        set_status(app, &format!("Opened {}", path.display()));
+    }
     Ok(())
 }
 
@@ -1984,29 +3734,468 @@ fn app_save_current(app: &mut App) -> io::Result<()> {
     // Ensure parent directory exists or let fs::write return an error
     fs::write(Path::new(filename), content)?;
     app.dirty = false;
-    set_status(app, &format!("Saved {}", filename));
+    app.history.mark_saved();
+    let saved = PathBuf::from(filename);
+    remember_recent(app, &saved);
+    set_status(app, &format!("Saved {}", saved.display()));
     Ok(())
 }
 
 // Create a new untitled file in the editor (resets state)
 fn app_new_file(app: &mut App) {
-    app.buffer.clear();
-    app.buffer.push(String::new());
+    // Undoable like any other edit
+    edit_buffer(app, EditKind::Other, |app| app.buffer = vec![String::new()]);
     app.cursor_row = 0;
     app.cursor_col = 0;
     app.scroll_row = 0;
     app.filename = None;
     app.dirty = false;
+    app.history.mark_saved();
 
     // Reset runtime/output state
     app.output.clear();
     app.tape_ptr = 0;
-    app.tape_window_base = 0;
-    app.tape_window = [0u8; 128];
+    app.tape = vec![0; app.settings.memory];
+    app.tape_cursor = 0;
 
     set_status(app, "New File");
 }
 
+// Exchange the editor state on App with the buffer stored at `index`
+fn swap_buffer_state(app: &mut App, index: usize) {
+    let slot = &mut app.buffers[index];
+    std::mem::swap(&mut app.buffer, &mut slot.buffer);
+    std::mem::swap(&mut app.cursor_row, &mut slot.cursor_row);
+    std::mem::swap(&mut app.cursor_col, &mut slot.cursor_col);
+    std::mem::swap(&mut app.scroll_row, &mut slot.scroll_row);
+    std::mem::swap(&mut app.filename, &mut slot.filename);
+    std::mem::swap(&mut app.dirty, &mut slot.dirty);
+    std::mem::swap(&mut app.history, &mut slot.history);
+    std::mem::swap(&mut app.output, &mut slot.output);
+    std::mem::swap(&mut app.tape_ptr, &mut slot.tape_ptr);
+    std::mem::swap(&mut app.tape, &mut slot.tape);
+    std::mem::swap(&mut app.prepared_input, &mut slot.input);
+}
+
+fn switch_buffer(app: &mut App, index: usize) {
+    if index == app.active_buffer || index >= app.buffers.len() {
+        return;
+    }
+    swap_buffer_state(app, app.active_buffer);
+    swap_buffer_state(app, index);
+    app.active_buffer = index;
+
+    // Selections, pending Vi chords and search highlights refer to the previous buffer
+    app.selection = None;
+    app.vi_pending_op = None;
+    if matches!(app.vi_mode, ViMode::Visual | ViMode::VisualLine) {
+        app.vi_mode = ViMode::Normal;
+    }
+    app.search_highlight = false;
+    app.search_scope = None;
+    app.tape_prompt = None;
+    app.tape_cursor = if app.tape_follow { app.tape_ptr } else { app.tape_cursor.min(app.tape.len().saturating_sub(1)) };
+    let label = buffer_label(app.filename.as_deref());
+    set_status(app, &format!("Buffer {}/{}: {}", index + 1, app.buffers.len(), label));
+}
+
+// Open an empty untitled buffer after the others and switch to it
+fn add_buffer(app: &mut App) {
+    app.buffers.push(BufferState { tape: vec![0; app.settings.memory], ..BufferState::default() });
+    switch_buffer(app, app.buffers.len() - 1);
+}
+
+// Close the active buffer without asking; the last buffer is reset to an empty one instead
+fn close_active_buffer(app: &mut App) {
+    let closing = app.active_buffer;
+    if app.runner.is_some() && app.run_buffer == closing {
+        // Nothing is left to show the run's output; dropping the handle also unblocks input
+        if let Some(h) = app.runner.take() {
+            h.cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+            let _ = h.tx_cmd.send(UiCmd::Stop);
+        }
+        app.running = false;
+        app.paused = false;
+        app.show_input_dialog = false;
+    }
+    if app.buffers.len() == 1 {
+        app_new_file(app);
+        return;
+    }
+    let label = buffer_label(app.filename.as_deref());
+    switch_buffer(app, if closing + 1 < app.buffers.len() { closing + 1 } else { closing - 1 });
+    app.buffers.remove(closing);
+    if app.active_buffer > closing {
+        app.active_buffer -= 1;
+    }
+    if app.run_buffer > closing {
+        app.run_buffer -= 1;
+    }
+    set_status(app, &format!("Closed {label}"));
+}
+
+// Open a file in its own buffer: switch to it if it is already open, reuse the active buffer
+// if it is an untouched untitled one, otherwise add a buffer for it
+fn app_open_in_buffer(app: &mut App, path: &Path) -> io::Result<()> {
+    let name = path.to_string_lossy();
+    let open_at = (0..app.buffers.len()).find(|&i| {
+        let filename = if i == app.active_buffer { &app.filename } else { &app.buffers[i].filename };
+        filename.as_deref() == Some(&*name)
+    });
+    if let Some(index) = open_at {
+        switch_buffer(app, index);
+        return Ok(());
+    }
+    let blank = app.filename.is_none() && !app.dirty && app.buffer.len() == 1 && app.buffer[0].is_empty();
+    if blank {
+        return app_open_file(app, path);
+    }
+
+    let previous = app.active_buffer;
+    add_buffer(app);
+    if let Err(err) = app_open_file(app, path) {
+        close_active_buffer(app);
+        switch_buffer(app, previous);
+        return Err(err);
+    }
+    Ok(())
+}
+
+fn any_buffer_dirty(app: &App) -> bool {
+    app.dirty || app.buffers.iter().enumerate().any(|(i, b)| i != app.active_buffer && b.dirty)
+}
+
+fn unsaved_on_quit_message(app: &App) -> String {
+    let dirty = (0..app.buffers.len())
+        .filter(|&i| if i == app.active_buffer { app.dirty } else { app.buffers[i].dirty })
+        .count();
+    if dirty > 1 {
+        format!("{dirty} buffers have unsaved changes. Quit anyway?")
+    } else {
+        "You have unsaved changes. Quit anyway?".to_string()
+    }
+}
+
+// Ctrl+O: browse the directory of the current file (or the working directory)
+fn open_file_browser(app: &mut App) {
+    let current = app.filename.as_ref().map(PathBuf::from);
+    let dir = current
+        .as_deref()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+    show_browser(app, dir, current.as_deref(), false);
+}
+
+// Pick a file for the prepared input, starting next to the attached one if there is one
+fn open_input_file_browser(app: &mut App) {
+    let attached = app.prepared_input.file.clone();
+    let dir = attached
+        .as_deref()
+        .or(app.filename.as_deref().map(Path::new))
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+    show_browser(app, dir, attached.as_deref(), true);
+}
+
+fn show_browser(app: &mut App, dir: PathBuf, select: Option<&Path>, attach_input: bool) {
+    app.show_file_browser = true;
+    app.browser.attach_input = attach_input;
+    // Input files can be anything; programs are listed by extension
+    app.browser.bf_only = !attach_input;
+    app.browser.show_recent = false;
+    app.browser.error = None;
+    browser_change_dir(app, dir);
+    if let Some(path) = select {
+        browser_select_path(app, path);
+    }
+}
+
+// List `dir` in the browser; on failure the previous listing stays and the error is shown
+fn browser_change_dir(app: &mut App, dir: PathBuf) {
+    match read_browser_dir(&dir) {
+        Ok(entries) => {
+            app.browser.dir = dir;
+            app.browser.entries = entries;
+            app.browser.filter.clear();
+            app.browser.selected = 0;
+            app.browser.error = None;
+        }
+        Err(err) => {
+            app.browser.error = Some(format!("Cannot read {}: {}", dir.display(), describe_io_error(&err)));
+        }
+    }
+}
+
+// Directory entries, directories first, each group sorted by name; ".." leads unless at the root
+fn read_browser_dir(dir: &Path) -> io::Result<Vec<BrowserEntry>> {
+    let mut entries: Vec<BrowserEntry> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let path = entry.path();
+            // Follow symlinks so a link to a directory can be entered
+            let is_dir = fs::metadata(&path).map(|m| m.is_dir()).unwrap_or(false);
+            let name = entry.file_name().to_string_lossy().into_owned();
+            BrowserEntry { label: if is_dir { format!("{name}/") } else { name }, path, is_dir }
+        })
+        .collect();
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.label.to_lowercase().cmp(&b.label.to_lowercase())));
+    if let Some(parent) = dir.parent() {
+        entries.insert(0, BrowserEntry { label: "../".to_string(), path: parent.to_path_buf(), is_dir: true });
+    }
+    Ok(entries)
+}
+
+// Entries that pass the .bf/.b and fuzzy filters, best matches first while filtering
+fn browser_visible(app: &App) -> Vec<BrowserEntry> {
+    let browser = &app.browser;
+    let source: Vec<BrowserEntry> = if browser.show_recent {
+        app.recent_files
+            .iter()
+            .filter(|path| path.is_file())
+            .map(|path| BrowserEntry { label: path.display().to_string(), path: path.clone(), is_dir: false })
+            .collect()
+    } else {
+        browser.entries.clone()
+    };
+    let show_hidden = browser.filter.starts_with('.');
+    let mut scored: Vec<(i32, BrowserEntry)> = source
+        .into_iter()
+        .filter(|entry| entry.is_dir || !browser.bf_only || is_bf_file(&entry.path))
+        .filter(|entry| show_hidden || entry.label == "../" || browser.show_recent || !entry.label.starts_with('.'))
+        .filter_map(|entry| {
+            if browser.filter.is_empty() {
+                return Some((0, entry));
+            }
+            if entry.label == "../" {
+                return None;
+            }
+            fuzzy_score(&entry.label, &browser.filter).map(|score| (score, entry))
+        })
+        .collect();
+    // Stable, so equal scores keep the directory order
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, entry)| entry).collect()
+}
+
+fn is_bf_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("bf") || ext.eq_ignore_ascii_case("b"))
+}
+
+// Case-insensitive subsequence match. Consecutive characters and matches at the start of a
+// word score higher; None if the pattern does not match
+fn fuzzy_score(candidate: &str, pattern: &str) -> Option<i32> {
+    let candidate: Vec<char> = candidate.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut prev_match: Option<usize> = None;
+    for p in pattern.chars().flat_map(char::to_lowercase) {
+        let found = (pos..candidate.len()).find(|&i| candidate[i] == p)?;
+        score += 1;
+        if prev_match.is_some_and(|prev| prev + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || matches!(candidate[found - 1], '/' | '_' | '-' | '.' | ' ') {
+            score += 8;
+        }
+        prev_match = Some(found);
+        pos = found + 1;
+    }
+    // Prefer shorter names among otherwise equal matches
+    Some(score * 100 - candidate.len() as i32)
+}
+
+fn browser_select_path(app: &mut App, path: &Path) {
+    if let Some(i) = browser_visible(app).iter().position(|entry| entry.path == path) {
+        app.browser.selected = i;
+    }
+}
+
+fn browser_parent(app: &mut App) {
+    let from = app.browser.dir.clone();
+    if let Some(parent) = from.parent() {
+        browser_change_dir(app, parent.to_path_buf());
+        browser_select_path(app, &from);
+    }
+}
+
+// Enter: go into a directory or open a file. A filter containing '/' is taken as a path
+// (relative to the listed directory) so a file can still be opened by typing its path
+fn browser_activate(app: &mut App) {
+    let target = if app.browser.filter.contains('/') {
+        let typed = PathBuf::from(app.browser.filter.trim());
+        let path = if typed.is_relative() { app.browser.dir.join(typed) } else { typed };
+        Some(BrowserEntry { label: String::new(), is_dir: path.is_dir(), path })
+    } else {
+        browser_visible(app).get(app.browser.selected).cloned()
+    };
+    let Some(entry) = target else {
+        return;
+    };
+    if entry.is_dir {
+        app.browser.show_recent = false;
+        if entry.label == "../" {
+            browser_parent(app);
+        } else {
+            browser_change_dir(app, entry.path);
+        }
+        return;
+    }
+    if app.browser.attach_input {
+        app.show_file_browser = false;
+        set_status(app, &format!("Input file: {}", entry.path.display()));
+        app.prepared_input.file = Some(entry.path);
+        return;
+    }
+    match app_open_in_buffer(app, &entry.path) {
+        Ok(()) => {
+            app.show_file_browser = false;
+            app.browser.error = None;
+        }
+        Err(err) => {
+            let name = entry.path.file_name().unwrap_or(entry.path.as_os_str()).to_string_lossy().into_owned();
+            app.browser.error = Some(format!("Cannot open {}: {}", name, describe_io_error(&err)));
+            set_status(app, "Open failed");
+        }
+    }
+}
+
+fn handle_file_browser_key(app: &mut App, key: KeyEvent) {
+    let count = browser_visible(app).len();
+    let last = count.saturating_sub(1);
+    match key.code {
+        KeyCode::Esc => {
+            app.show_file_browser = false;
+            app.browser.error = None;
+        }
+        KeyCode::Enter => browser_activate(app),
+        KeyCode::Up => app.browser.selected = app.browser.selected.saturating_sub(1),
+        KeyCode::Down => app.browser.selected = (app.browser.selected + 1).min(last),
+        KeyCode::PageUp => app.browser.selected = app.browser.selected.saturating_sub(10),
+        KeyCode::PageDown => app.browser.selected = (app.browser.selected + 10).min(last),
+        KeyCode::Home => app.browser.selected = 0,
+        KeyCode::End => app.browser.selected = last,
+        KeyCode::Tab => {
+            app.browser.show_recent = !app.browser.show_recent;
+            app.browser.filter.clear();
+            app.browser.selected = 0;
+            app.browser.error = None;
+        }
+        KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.browser.bf_only = !app.browser.bf_only;
+            app.browser.selected = 0;
+        }
+        // With an empty filter, Backspace and Left go up a directory
+        KeyCode::Backspace | KeyCode::Left if app.browser.filter.is_empty() && !app.browser.show_recent => {
+            browser_parent(app);
+        }
+        KeyCode::Backspace if !app.browser.filter.is_empty() => {
+            app.browser.filter.pop();
+            app.browser.selected = 0;
+        }
+        KeyCode::Right if browser_visible(app).get(app.browser.selected).is_some_and(|e| e.is_dir) => {
+            browser_activate(app);
+        }
+        KeyCode::Char(ch) if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
+            app.browser.filter.push(ch);
+            app.browser.selected = 0;
+        }
+        _ => {}
+    }
+}
+
+// Friendlier wording for the errors people actually hit when opening files
+fn describe_io_error(err: &io::Error) -> String {
+    match err.kind() {
+        io::ErrorKind::PermissionDenied => "permission denied".to_string(),
+        io::ErrorKind::NotFound => "no such file or directory".to_string(),
+        io::ErrorKind::IsADirectory => "is a directory".to_string(),
+        io::ErrorKind::NotADirectory => "not a directory".to_string(),
+        _ => err.to_string(),
+    }
+}
+
+fn load_recent(store: &Path) -> Vec<PathBuf> {
+    fs::read_to_string(store)
+        .map(|text| text.lines().filter(|line| !line.is_empty()).map(PathBuf::from).collect())
+        .unwrap_or_default()
+}
+
+// Move `path` to the front of the recent files and persist the list (best effort)
+fn remember_recent(app: &mut App, path: &Path) {
+    app.recent_files.retain(|p| p != path);
+    app.recent_files.insert(0, path.to_path_buf());
+    app.recent_files.truncate(20);
+    if let Some(store) = &app.recent_store {
+        let text: String = app.recent_files.iter().map(|p| format!("{}\n", p.display())).collect();
+        if let Some(dir) = store.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        let _ = fs::write(store, text);
+    }
+}
+
+// Short name for tabs and messages: the file name without its directory
+fn buffer_label(filename: Option<&str>) -> String {
+    match filename {
+        Some(path) => Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string()),
+        None => "<untitled>".to_string(),
+    }
+}
+
+// Re-lay out the buffer with the formatter; the command sequence is unchanged. With a
+// selection, only the selected lines are formatted (on their own, so loops must be balanced)
+fn app_format_buffer(app: &mut App) {
+    if let Some((start, end)) = selection_range(app) {
+        let source = app.buffer[start.0..=end.0].join("\n");
+        match BrainfuckFormatter::new(&source).format() {
+            Ok(formatted) if formatted == source => set_status(app, "Already formatted"),
+            Ok(formatted) => {
+                app.selection = None;
+                if matches!(app.vi_mode, ViMode::Visual | ViMode::VisualLine) {
+                    app.vi_mode = ViMode::Normal;
+                }
+                edit_buffer(app, EditKind::Other, |app| {
+                    app.buffer.splice(start.0..=end.0, formatted.split('\n').map(str::to_string));
+                    (app.cursor_row, app.cursor_col) = (start.0, 0);
+                });
+                ensure_cursor_visible(app);
+                set_status(app, "Formatted selection");
+            }
+            Err(err) => set_status(app, &format!("Format failed: {err}")),
+        }
+        return;
+    }
+    let source = app_current_source(app);
+    let formatted = match BrainfuckFormatter::new(&source).format() {
+        Ok(f) => f,
+        Err(err) => {
+            set_status(app, &format!("Format failed: {err}"));
+            return;
+        }
+    };
+    if formatted == source {
+        set_status(app, "Already formatted");
+        return;
+    }
+
+    edit_buffer(app, EditKind::Other, |app| {
+        app.buffer = formatted.split('\n').map(str::to_string).collect();
+        app.cursor_row = app.cursor_row.min(app.buffer.len() - 1);
+        app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].len());
+    });
+    app.scroll_row = app.scroll_row.min(app.cursor_row);
+    set_status(app, "Formatted buffer");
+}
+
 // Helper: choose a new default filename in the current directory.
 // Tries "untitled.bf", "untitled1.bf", "untitled2.bf", ...
 fn generate_new_filename() -> io::Result<PathBuf> {
@@ -2048,19 +4237,14 @@ fn save_to_filename(app: &mut App, name: &str) -> io::Result<()> {
     fs::write(&path, content)?;
     app.filename = Some(path.to_string_lossy().to_string());
     app.dirty = false;
+    app.history.mark_saved();
+    remember_recent(app, &path);
     Ok(())
 }
 
-// Compute current cell value if the pointer is within the current 128-cell window
+// Value of the cell under the data pointer
 fn current_cell_value(app: &App) -> Option<u8> {
-    let base = app.tape_window_base;
-    let end = base.saturating_add(128);
-    if app.tape_ptr >= base && app.tape_ptr < end {
-        let idx = app.tape_ptr - base;
-        Some(app.tape_window[idx])
-    } else {
-        None
-    }
+    app.tape.get(app.tape_ptr).copied()
 }
 
 // Helper: set a status message
@@ -2122,11 +4306,8 @@ fn move_down(app: &mut App) {
 fn delete_current_line(app: &mut App) {
     if app.buffer.len() == 1 {
         // Keep one empty line
-        if !app.buffer[0].is_empty() {
-            app.buffer[0].clear();
-            app.cursor_col = 0;
-            app.dirty = true;
-        }
+        app.buffer[0].clear();
+        app.cursor_col = 0;
         return;
     }
     app.buffer.remove(app.cursor_row);
@@ -2134,6 +4315,1086 @@ fn delete_current_line(app: &mut App) {
         app.cursor_row = app.buffer.len() - 1;
     }
     app.cursor_col = app.buffer[app.cursor_row].chars().count().min(app.cursor_col);
-    app.dirty = true;
     ensure_cursor_visible(app);
 }
+
+// Selected text as (start, end) positions, end exclusive; None without a selection
+fn selection_range(app: &App) -> Option<((usize, usize), (usize, usize))> {
+    let selection = app.selection?;
+    let cursor = (app.cursor_row, app.cursor_col);
+    let (start, end) = if selection.anchor <= cursor { (selection.anchor, cursor) } else { (cursor, selection.anchor) };
+    let line_len = |row: usize| app.buffer.get(row).map_or(0, |l| l.chars().count());
+    match selection.kind {
+        SelectionKind::Exclusive => (start != end).then_some((start, end)),
+        SelectionKind::Inclusive => Some((start, (end.0, (end.1 + 1).min(line_len(end.0))))),
+        SelectionKind::Lines => Some(((start.0, 0), (end.0, line_len(end.0)))),
+    }
+}
+
+// Text between two positions (end exclusive), lines joined with '\n'
+fn text_in_range(lines: &[String], start: (usize, usize), end: (usize, usize)) -> String {
+    let mut out = String::new();
+    for (row, line) in lines.iter().enumerate().take(end.0 + 1).skip(start.0) {
+        let from = if row == start.0 { start.1 } else { 0 };
+        let to = if row == end.0 { end.1 } else { line.chars().count() };
+        if row > start.0 {
+            out.push('\n');
+        }
+        out.extend(line.chars().skip(from).take(to.saturating_sub(from)));
+    }
+    out
+}
+
+// Replace the text between two positions (end exclusive) and return the position after the new text
+fn replace_range(lines: &mut Vec<String>, start: (usize, usize), end: (usize, usize), text: &str) -> (usize, usize) {
+    let head: String = lines[start.0].chars().take(start.1).collect();
+    let tail: String = lines[end.0].chars().skip(end.1).collect();
+    let mut new_lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+    let last = new_lines.len() - 1;
+    let after = if last == 0 {
+        (start.0, start.1 + new_lines[0].chars().count())
+    } else {
+        (start.0 + last, new_lines[last].chars().count())
+    };
+    new_lines[0].insert_str(0, &head);
+    new_lines[last].push_str(&tail);
+    lines.splice(start.0..=end.0, new_lines);
+    after
+}
+
+fn set_register(app: &mut App, text: String, linewise: bool) {
+    if app.osc52_clipboard {
+        copy_to_terminal_clipboard(&text);
+    }
+    app.register = Some(Register { text, linewise });
+}
+
+// OSC 52 asks the terminal to put text on the system clipboard (works over SSH too)
+fn copy_to_terminal_clipboard(text: &str) {
+    use std::io::Write;
+    let mut out = io::stdout();
+    let _ = write!(out, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+    let _ = out.flush();
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |acc, (i, b)| acc | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn copy_selection(app: &mut App) {
+    let Some((start, end)) = selection_range(app) else {
+        set_status(app, "Nothing selected");
+        return;
+    };
+    let linewise = app.selection.is_some_and(|s| s.kind == SelectionKind::Lines);
+    let text = text_in_range(&app.buffer, start, end);
+    let lines = end.0 - start.0 + 1;
+    set_register(app, text, linewise);
+    set_status(app, &format!("Copied {lines} line{}", if lines == 1 { "" } else { "s" }));
+}
+
+fn cut_selection(app: &mut App) {
+    if app.selection.is_none() {
+        set_status(app, "Nothing selected");
+        return;
+    }
+    copy_selection(app);
+    delete_selection(app);
+}
+
+// Remove the selected text (whole lines in visual line mode) as one undo step
+fn delete_selection(app: &mut App) {
+    let Some((start, end)) = selection_range(app) else {
+        app.selection = None;
+        return;
+    };
+    let linewise = app.selection.is_some_and(|s| s.kind == SelectionKind::Lines);
+    app.selection = None;
+    edit_buffer(app, EditKind::Other, |app| {
+        if linewise {
+            app.buffer.drain(start.0..=end.0);
+            app.cursor_row = start.0.min(app.buffer.len().saturating_sub(1));
+            app.cursor_col = 0;
+        } else {
+            (app.cursor_row, app.cursor_col) = start;
+            replace_range(&mut app.buffer, start, end, "");
+        }
+    });
+    ensure_cursor_visible(app);
+}
+
+// Paste the register at the cursor (`after`: one character later, like vi `p`); linewise
+// text goes above the current line, or below it with `after`
+fn paste_register(app: &mut App, after: bool) {
+    let Some(register) = app.register.clone() else {
+        set_status(app, "Nothing to paste");
+        return;
+    };
+    edit_buffer(app, EditKind::Other, |app| {
+        if register.linewise {
+            let row = if after { app.cursor_row + 1 } else { app.cursor_row };
+            app.buffer.splice(row..row, register.text.split('\n').map(str::to_string));
+            (app.cursor_row, app.cursor_col) = (row, 0);
+        } else {
+            let len = app.buffer[app.cursor_row].chars().count();
+            let col = if after && app.cursor_col < len { app.cursor_col + 1 } else { app.cursor_col };
+            let end = replace_range(&mut app.buffer, (app.cursor_row, col), (app.cursor_row, col), &register.text);
+            (app.cursor_row, app.cursor_col) = end;
+        }
+    });
+    ensure_cursor_visible(app);
+}
+
+impl LineEdit {
+    // The smallest line range that differs between two buffers, or None if they are equal
+    fn diff(before: &[String], after: &[String]) -> Option<Self> {
+        if before == after {
+            return None;
+        }
+        let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+        let max_suffix = before.len().min(after.len()) - prefix;
+        let suffix = before.iter().rev().zip(after.iter().rev()).take(max_suffix).take_while(|(a, b)| a == b).count();
+        Some(Self {
+            row: prefix,
+            old: before[prefix..before.len() - suffix].to_vec(),
+            new: after[prefix..after.len() - suffix].to_vec(),
+        })
+    }
+}
+
+impl EditHistory {
+    // Record an edit made with the cursor moving from `before` to `after`
+    fn record(&mut self, kind: EditKind, edit: LineEdit, before: (usize, usize), after: (usize, usize)) {
+        let joins = kind != EditKind::Other && self.open_group == Some((kind, before));
+        match self.undo.last_mut() {
+            Some(group) if joins => {
+                group.edits.push(edit);
+                group.cursor_after = after;
+            }
+            _ => {
+                if self.saved_at.is_some_and(|depth| depth > self.undo.len()) {
+                    // The saved state was only reachable through redo
+                    self.saved_at = None;
+                }
+                self.undo.push(UndoGroup { edits: vec![edit], cursor_before: before, cursor_after: after });
+            }
+        }
+        self.redo.clear();
+        self.open_group = (kind != EditKind::Other).then_some((kind, after));
+    }
+
+    // The buffer now matches the file on disk; later edits start a new group
+    fn mark_saved(&mut self) {
+        self.saved_at = Some(self.undo.len());
+        self.open_group = None;
+    }
+
+    fn is_saved(&self) -> bool {
+        self.saved_at == Some(self.undo.len())
+    }
+}
+
+// Apply `change` to the buffer and record it for undo; the buffer is only marked dirty if it changed
+fn edit_buffer(app: &mut App, kind: EditKind, change: impl FnOnce(&mut App)) {
+    let before = app.buffer.clone();
+    let cursor_before = (app.cursor_row, app.cursor_col);
+    change(app);
+    if app.buffer.is_empty() {
+        app.buffer.push(String::new());
+    }
+    if let Some(edit) = LineEdit::diff(&before, &app.buffer) {
+        app.history.record(kind, edit, cursor_before, (app.cursor_row, app.cursor_col));
+        app.dirty = true;
+    }
+}
+
+fn app_undo(app: &mut App) {
+    let Some(group) = app.history.undo.pop() else {
+        set_status(app, "Nothing to undo");
+        return;
+    };
+    for edit in group.edits.iter().rev() {
+        app.buffer.splice(edit.row..edit.row + edit.new.len(), edit.old.iter().cloned());
+    }
+    (app.cursor_row, app.cursor_col) = group.cursor_before;
+    app.history.redo.push(group);
+    app_after_history_step(app);
+    set_status(app, "Undo");
+}
+
+fn app_redo(app: &mut App) {
+    let Some(group) = app.history.redo.pop() else {
+        set_status(app, "Nothing to redo");
+        return;
+    };
+    for edit in &group.edits {
+        app.buffer.splice(edit.row..edit.row + edit.old.len(), edit.new.iter().cloned());
+    }
+    (app.cursor_row, app.cursor_col) = group.cursor_after;
+    app.history.undo.push(group);
+    app_after_history_step(app);
+    set_status(app, "Redo");
+}
+
+fn app_after_history_step(app: &mut App) {
+    app.history.open_group = None;
+    app.selection = None;
+    app.dirty = !app.history.is_saved();
+    app.cursor_row = app.cursor_row.min(app.buffer.len() - 1);
+    app.cursor_col = app.cursor_col.min(app.buffer[app.cursor_row].chars().count());
+    ensure_cursor_visible(app);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(app: &mut App, code: KeyCode, modifiers: KeyModifiers) {
+        handle_key(app, KeyEvent::new(code, modifiers)).unwrap();
+    }
+
+    // Process runner messages until `done` holds (or a few seconds pass)
+    fn wait_for(app: &mut App, done: impl Fn(&App) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done(app) && Instant::now() < deadline {
+            process_runner_messages(app);
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(done(app), "timed out waiting for the runner");
+    }
+
+    fn type_str(app: &mut App, text: &str) {
+        for ch in text.chars() {
+            match ch {
+                '\n' => press(app, KeyCode::Enter, KeyModifiers::NONE),
+                _ => press(app, KeyCode::Char(ch), KeyModifiers::NONE),
+            }
+        }
+    }
+
+    #[test]
+    fn typing_is_undone_as_one_group_and_redone() {
+        let mut app = App::default();
+        type_str(&mut app, "+++\n>.");
+        press(&mut app, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(app.buffer, vec!["+++", "."]);
+
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec!["+++", ">."]);
+        assert_eq!((app.cursor_row, app.cursor_col), (1, 1));
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec![""]);
+        assert!(!app.dirty);
+
+        press(&mut app, KeyCode::Char('y'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec!["+++", ">."]);
+        assert!(app.dirty);
+
+        // A new edit drops the redo stack
+        type_str(&mut app, "-");
+        press(&mut app, KeyCode::Char('y'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec!["+++", ">.-"]);
+    }
+
+    #[test]
+    fn vi_normal_mode_undoes_with_u_and_redoes_with_ctrl_r() {
+        let mut app = App { vi_enabled: true, vi_mode: ViMode::Normal, ..App::default() };
+        app.buffer = vec!["+".to_string(), "-".to_string()];
+        press(&mut app, KeyCode::Char('d'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('d'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('x'), KeyModifiers::NONE);
+        assert_eq!(app.buffer, vec![""]);
+
+        press(&mut app, KeyCode::Char('u'), KeyModifiers::NONE);
+        assert_eq!(app.buffer, vec!["-"]);
+        press(&mut app, KeyCode::Char('u'), KeyModifiers::NONE);
+        assert_eq!(app.buffer, vec!["+", "-"]);
+        press(&mut app, KeyCode::Char('r'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec!["-"]);
+        assert!(app.runner.is_none(), "Ctrl+R must not start a run in Vi normal mode");
+    }
+
+    #[test]
+    fn undo_back_to_the_saved_state_clears_dirty() {
+        let mut app = App::default();
+        type_str(&mut app, "++");
+        app.history.mark_saved();
+        app.dirty = false;
+        type_str(&mut app, "--");
+        assert!(app.dirty);
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec!["++"]);
+        assert!(!app.dirty);
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert!(app.dirty);
+    }
+
+    #[test]
+    fn incremental_search_moves_to_matches_and_wraps() {
+        let mut app = App::default();
+        type_str(&mut app, "+[-]\n>>[-]<");
+        (app.cursor_row, app.cursor_col) = (0, 2);
+        press(&mut app, KeyCode::Char('f'), KeyModifiers::CONTROL);
+        type_str(&mut app, "[-");
+        assert_eq!((app.cursor_row, app.cursor_col), (1, 2));
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        assert_eq!((app.cursor_row, app.cursor_col), (0, 1));
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(!app.show_search && app.search_highlight);
+        press(&mut app, KeyCode::F(3), KeyModifiers::SHIFT);
+        assert_eq!((app.cursor_row, app.cursor_col), (1, 2));
+
+        // Esc cancels back to where the search started
+        press(&mut app, KeyCode::Char('f'), KeyModifiers::CONTROL);
+        type_str(&mut app, "<");
+        assert_eq!((app.cursor_row, app.cursor_col), (1, 5));
+        press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!((app.cursor_row, app.cursor_col), (1, 2));
+    }
+
+    #[test]
+    fn code_only_search_skips_comments_and_spans_lines() {
+        let lines: Vec<String> = vec!["a [ clear".into(), " - ] b".into(), "[-]".into()];
+        assert!(find_matches(&lines, "[-]", false).len() == 1);
+        let matches = find_matches(&lines, "[-]", true);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].cells, vec![(0, 2), (1, 1), (1, 3)]);
+        assert!(find_matches(&lines, "clear", true).is_empty());
+    }
+
+    #[test]
+    fn replace_all_is_one_undo_step() {
+        let mut app = App::default();
+        type_str(&mut app, "x[\n-]y [-]");
+        press(&mut app, KeyCode::Char('f'), KeyModifiers::CONTROL);
+        press(&mut app, KeyCode::Char('b'), KeyModifiers::CONTROL);
+        type_str(&mut app, "[-]");
+        press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+        type_str(&mut app, "Z");
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(app.buffer, vec!["xZy Z"]);
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec!["x[", "-]y [-]"]);
+    }
+
+    #[test]
+    fn shift_selection_copies_cuts_and_is_replaced_by_typing() {
+        let mut app = App::default();
+        type_str(&mut app, "+[->+<]\n.");
+        (app.cursor_row, app.cursor_col) = (0, 1);
+        for _ in 0..6 {
+            press(&mut app, KeyCode::Right, KeyModifiers::SHIFT);
+        }
+        assert_eq!(selection_range(&app), Some(((0, 1), (0, 7))));
+        press(&mut app, KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec!["+", "."]);
+        assert_eq!(app.register, Some(Register { text: "[->+<]".to_string(), linewise: false }));
+
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('v'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec!["+", "[->+<]."]);
+
+        press(&mut app, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut app, KeyCode::End, KeyModifiers::SHIFT);
+        type_str(&mut app, "-");
+        assert_eq!(app.buffer, vec!["+", "-"]);
+        assert!(app.selection.is_none());
+    }
+
+    #[test]
+    fn vi_visual_modes_yank_cut_and_paste() {
+        let mut app = App { vi_enabled: true, vi_mode: ViMode::Normal, ..App::default() };
+        app.buffer = vec!["ab".to_string(), "cd".to_string(), "ef".to_string()];
+        press(&mut app, KeyCode::Char('V'), KeyModifiers::SHIFT);
+        press(&mut app, KeyCode::Char('j'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('d'), KeyModifiers::NONE);
+        assert_eq!(app.buffer, vec!["ef"]);
+        assert_eq!(app.vi_mode, ViMode::Normal);
+        press(&mut app, KeyCode::Char('p'), KeyModifiers::NONE);
+        assert_eq!(app.buffer, vec!["ef", "ab", "cd"]);
+
+        // Charwise visual includes the character under the cursor
+        (app.cursor_row, app.cursor_col) = (0, 0);
+        press(&mut app, KeyCode::Char('v'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('l'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('y'), KeyModifiers::NONE);
+        assert_eq!(app.register, Some(Register { text: "ef".to_string(), linewise: false }));
+        press(&mut app, KeyCode::Char('P'), KeyModifiers::SHIFT);
+        assert_eq!(app.buffer[0], "efef");
+
+        press(&mut app, KeyCode::Char('u'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('u'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Char('u'), KeyModifiers::NONE);
+        assert_eq!(app.buffer, vec!["ab", "cd", "ef"]);
+    }
+
+    #[test]
+    fn replace_all_stays_inside_the_selection() {
+        let mut app = App { buffer: vec!["++".to_string(), "++".to_string(), "++".to_string()], ..App::default() };
+        (app.cursor_row, app.cursor_col) = (1, 0);
+        press(&mut app, KeyCode::Down, KeyModifiers::SHIFT);
+        press(&mut app, KeyCode::Char('f'), KeyModifiers::CONTROL);
+        type_str(&mut app, "+");
+        press(&mut app, KeyCode::Tab, KeyModifiers::NONE);
+        type_str(&mut app, "-");
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(app.buffer, vec!["++", "--", "++"]);
+    }
+
+    #[test]
+    fn run_selection_runs_only_the_selected_code() {
+        let mut app = App { buffer: vec!["+.+.".to_string(), "+.".to_string()], ..App::default() };
+        (app.cursor_row, app.cursor_col) = (0, 2);
+        app.selection = Some(Selection { anchor: (0, 0), kind: SelectionKind::Exclusive });
+        start_runner(&mut app);
+        wait_for(&mut app, |app| app.runner.is_none());
+        assert_eq!(app.output, vec![1]);
+
+        // Without a selection the whole buffer runs
+        app.selection = None;
+        start_runner(&mut app);
+        wait_for(&mut app, |app| app.runner.is_none());
+        assert_eq!(app.output, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn format_selection_rewrites_only_the_selected_lines() {
+        let buffer = ["header", "++>++[>+<-]", "+++[->+<]>."].map(str::to_string).to_vec();
+        let mut app = App { buffer: buffer.clone(), ..App::default() };
+        (app.cursor_row, app.cursor_col) = (1, 3);
+        app.selection = Some(Selection { anchor: (1, 0), kind: SelectionKind::Exclusive });
+        app_format_buffer(&mut app);
+        assert_eq!(app.buffer, vec!["header", "++ >++[>+<-]", "+++[->+<]>."]);
+        assert!(app.selection.is_none());
+        assert_eq!(app.status_message.as_ref().unwrap().0, "Formatted selection");
+
+        app_undo(&mut app);
+        assert_eq!(app.buffer, buffer);
+    }
+
+    #[test]
+    fn base64_matches_the_standard_alphabet_and_padding() {
+        assert_eq!(base64_encode(b""), "");
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64_encode(b"[-]\n"), "Wy1dCg==");
+    }
+
+    #[test]
+    fn buffers_keep_their_own_text_cursor_dirty_flag_and_history() {
+        let mut app = App::default();
+        type_str(&mut app, "+++");
+        press(&mut app, KeyCode::Char('n'), KeyModifiers::CONTROL);
+        assert_eq!((app.buffers.len(), app.active_buffer), (2, 1));
+        assert_eq!(app.buffer, vec![""]);
+        assert!(!app.dirty);
+        type_str(&mut app, "--\n-");
+
+        press(&mut app, KeyCode::PageUp, KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec!["+++"]);
+        assert_eq!((app.cursor_row, app.cursor_col), (0, 3));
+        assert!(app.dirty);
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec![""]);
+
+        press(&mut app, KeyCode::Char('2'), KeyModifiers::ALT);
+        assert_eq!(app.buffer, vec!["--", "-"]);
+        assert_eq!((app.cursor_row, app.cursor_col), (1, 1));
+        assert!(any_buffer_dirty(&app));
+    }
+
+    #[test]
+    fn opening_files_reuses_blank_buffers_and_switches_to_open_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.bf"), dir.path().join("b.bf"));
+        fs::write(&a, "+").unwrap();
+        fs::write(&b, "-").unwrap();
+
+        let mut app = App::default();
+        app_open_in_buffer(&mut app, &a).unwrap();
+        assert_eq!(app.buffers.len(), 1);
+        app_open_in_buffer(&mut app, &b).unwrap();
+        assert_eq!((app.buffers.len(), app.active_buffer), (2, 1));
+        app_open_in_buffer(&mut app, &a).unwrap();
+        assert_eq!((app.buffers.len(), app.active_buffer), (2, 0));
+        assert_eq!(app.buffer, vec!["+"]);
+
+        // A failed open leaves the buffer list as it was
+        assert!(app_open_in_buffer(&mut app, &dir.path().join("missing.bf")).is_err());
+        assert_eq!((app.buffers.len(), app.active_buffer), (2, 0));
+        assert_eq!(app.buffer, vec!["+"]);
+    }
+
+    #[test]
+    fn closing_a_dirty_buffer_asks_first() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('n'), KeyModifiers::CONTROL);
+        type_str(&mut app, "+");
+        press(&mut app, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert!(app.show_confirm_dialog);
+        press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(app.buffers.len(), 2);
+
+        press(&mut app, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!((app.buffers.len(), app.active_buffer), (1, 0));
+        assert!(!app.dirty);
+
+        // The last buffer is emptied rather than removed
+        app.buffer = vec!["-".to_string()];
+        app.filename = Some("saved.bf".to_string());
+        press(&mut app, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffers.len(), 1);
+        assert_eq!((app.buffer.clone(), app.filename.clone()), (vec![String::new()], None));
+    }
+
+    #[test]
+    fn run_output_goes_to_the_buffer_that_started_the_run() {
+        let mut app = App::default();
+        type_str(&mut app, "++++++++[>++++++++<-]>+.");
+        start_runner(&mut app);
+        press(&mut app, KeyCode::Char('n'), KeyModifiers::CONTROL);
+        wait_for(&mut app, |app| app.runner.is_none());
+        assert!(app.output.is_empty());
+        assert_eq!(app.buffers[0].output, b"A");
+
+        press(&mut app, KeyCode::PageDown, KeyModifiers::CONTROL);
+        assert_eq!(app.output, b"A");
+        assert_eq!(app.tape_ptr, 1);
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts_and_runs() {
+        assert!(fuzzy_score("hello.bf", "hlo").is_some());
+        assert!(fuzzy_score("hello.bf", "olh").is_none());
+        assert!(fuzzy_score("Loop.bf", "lo").unwrap() > fuzzy_score("hello.bf", "lo").unwrap());
+        assert!(fuzzy_score("golf.bf", "golf").unwrap() > fuzzy_score("golfed_version.bf", "golf").unwrap());
+    }
+
+    #[test]
+    fn file_browser_lists_filters_and_opens_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("lib")).unwrap();
+        fs::write(dir.path().join("lib").join("util.b"), "-").unwrap();
+        fs::write(dir.path().join("main.bf"), "+").unwrap();
+        fs::write(dir.path().join("notes.txt"), "").unwrap();
+        fs::write(dir.path().join(".hidden.bf"), "").unwrap();
+
+        let filename = Some(dir.path().join("main.bf").to_string_lossy().into_owned());
+        let mut app = App { filename, ..App::default() };
+        open_file_browser(&mut app);
+        let labels = |app: &App| browser_visible(app).into_iter().map(|e| e.label).collect::<Vec<_>>();
+        assert_eq!(labels(&app), vec!["../", "lib/", "main.bf"]);
+        assert_eq!(app.browser.selected, 2);
+
+        press(&mut app, KeyCode::Char('b'), KeyModifiers::CONTROL);
+        assert_eq!(labels(&app), vec!["../", "lib/", "main.bf", "notes.txt"]);
+        press(&mut app, KeyCode::Char('b'), KeyModifiers::CONTROL);
+
+        // Into lib/ and back up; the directory we came from stays selected
+        type_str(&mut app, "lb");
+        assert_eq!(labels(&app), vec!["lib/"]);
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(labels(&app), vec!["../", "util.b"]);
+        press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(browser_visible(&app)[app.browser.selected].label, "lib/");
+
+        press(&mut app, KeyCode::Right, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(!app.show_file_browser);
+        assert_eq!(app.buffer, vec!["-"]);
+        assert_eq!(app.recent_files, vec![dir.path().join("lib").join("util.b")]);
+    }
+
+    #[test]
+    fn file_browser_reports_errors_and_keeps_its_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.bf"), "+").unwrap();
+        let filename = Some(dir.path().join("a.bf").to_string_lossy().into_owned());
+        let mut app = App { filename, ..App::default() };
+        open_file_browser(&mut app);
+
+        browser_change_dir(&mut app, dir.path().join("gone"));
+        assert!(app.browser.error.as_deref().unwrap().ends_with("no such file or directory"));
+        assert_eq!(app.browser.dir, dir.path());
+
+        type_str(&mut app, "./missing.bf");
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(app.browser.error.as_deref(), Some("Cannot open missing.bf: no such file or directory"));
+        assert!(app.show_file_browser);
+    }
+
+    #[test]
+    fn non_utf8_files_open_with_replacement_characters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("latin1.bf");
+        fs::write(&path, b"+\xe9+.").unwrap();
+        let mut app = App::default();
+        app_open_file(&mut app, &path).unwrap();
+        assert_eq!(app.buffer, vec!["+\u{FFFD}+."]);
+        assert!(app.status_message.as_ref().unwrap().0.contains("not valid UTF-8"));
+    }
+
+    #[test]
+    fn opening_and_new_file_can_be_undone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prog.bf");
+        fs::write(&path, "++>.").unwrap();
+        let mut app = App::default();
+        type_str(&mut app, "+++");
+        app_open_file(&mut app, &path).unwrap();
+        assert_eq!((app.buffer.clone(), app.dirty), (vec!["++>.".to_string()], false));
+
+        app_undo(&mut app);
+        assert_eq!((app.buffer.clone(), app.dirty), (vec!["+++".to_string()], true));
+        app_redo(&mut app);
+        assert_eq!((app.buffer.clone(), app.dirty), (vec!["++>.".to_string()], false));
+
+        app_new_file(&mut app);
+        assert_eq!((app.buffer.clone(), app.dirty), (vec![String::new()], false));
+        app_undo(&mut app);
+        assert_eq!((app.buffer.clone(), app.dirty), (vec!["++>.".to_string()], true));
+        app_undo(&mut app);
+        assert_eq!(app.buffer, vec!["+++"]);
+    }
+
+    #[test]
+    fn recent_files_persist_newest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("state").join("ide_recent");
+        let mut app = App { recent_store: Some(store.clone()), ..App::default() };
+        remember_recent(&mut app, Path::new("/a.bf"));
+        remember_recent(&mut app, Path::new("/b.bf"));
+        remember_recent(&mut app, Path::new("/a.bf"));
+        assert_eq!(load_recent(&store), vec![PathBuf::from("/a.bf"), PathBuf::from("/b.bf")]);
+    }
+
+    #[test]
+    fn prepared_input_is_read_before_the_input_dialog() {
+        let mut app = App::default();
+        type_str(&mut app, ",.,.,.");
+        press(&mut app, KeyCode::Char('d'), KeyModifiers::CONTROL);
+        type_str(&mut app, "h\n");
+        press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(app.prepared_input.text, "h\n");
+
+        start_runner(&mut app);
+        wait_for(&mut app, |app| app.show_input_dialog);
+        assert_eq!(app.output, b"h\n");
+        type_str(&mut app, "!\n");
+        wait_for(&mut app, |app| app.runner.is_none());
+        assert_eq!(app.output, b"h\n!");
+
+        // Each buffer has its own prepared input
+        press(&mut app, KeyCode::Char('n'), KeyModifiers::CONTROL);
+        assert_eq!(app.prepared_input, PreparedInput::default());
+        press(&mut app, KeyCode::PageUp, KeyModifiers::CONTROL);
+        assert_eq!(app.prepared_input.text, "h\n");
+    }
+
+    #[test]
+    fn used_up_input_can_report_eof_instead_of_asking() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("input.txt");
+        fs::write(&file, "ab").unwrap();
+        // Written like the README's [ide] example, trailing comments included
+        let config = "[ide]\neof = \"max\"                # zero, unchanged or max\ninput-exhausted = \"eof\"    # prompt or eof\n";
+        let mut app = App::default();
+        apply_ide_config(&mut app, &crate::config::parse_section(config, "ide"));
+        assert_eq!((app.settings.eof, app.prompt_when_exhausted), (EofBehavior::Max, false));
+        assert!(app.status_message.is_none());
+        app.prepared_input = PreparedInput { file: Some(file), text: "c".to_string() };
+        type_str(&mut app, ",.,.,.,.");
+        start_runner(&mut app);
+        wait_for(&mut app, |app| app.runner.is_none());
+        assert!(!app.show_input_dialog);
+        assert_eq!(app.output, b"abc\xff");
+    }
+
+    #[test]
+    fn runner_output_arrives_in_batches() {
+        let mut app = App::default();
+        // 8^5 * 4 = 131072 zero bytes
+        type_str(&mut app, "++++++++[>++++++++[>++++++++[>++++++++[>++++++++[>++++[>.<-]<-]<-]<-]<-]<-]");
+        start_runner(&mut app);
+        let handle = app.runner.take().unwrap();
+        let (mut batches, mut bytes, mut snapshots) = (0, 0, 0);
+        loop {
+            match handle.rx_msg.recv_timeout(Duration::from_secs(10)).unwrap() {
+                RunnerMsg::Output(out) => {
+                    batches += 1;
+                    bytes += out.len();
+                }
+                RunnerMsg::Tape { .. } => snapshots += 1,
+                RunnerMsg::Halted(res) => {
+                    res.unwrap();
+                    break;
+                }
+                other => panic!("unexpected message {other:?}"),
+            }
+        }
+        assert_eq!(bytes, 131_072);
+        assert_eq!(batches, 2);
+        // Nobody asked for snapshots, so only the final one is sent
+        assert_eq!(snapshots, 1);
+    }
+
+    #[test]
+    fn command_palette_filters_and_runs_commands() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::Char('P'), KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+        assert!(app.show_palette);
+        assert_eq!(palette_entries(&app).len(), COMMANDS.len() - 1);
+        type_str(&mut app, "line num");
+        assert_eq!(palette_entries(&app)[0].command, Command::ToggleLineNumbers);
+        assert_eq!(command_keys(&app, Command::ToggleLineNumbers), "Ctrl+L");
+        let shown = app.show_line_numbers;
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(!app.show_palette);
+        assert_ne!(app.show_line_numbers, shown);
+
+        // F4 too, and ':' in Vi normal mode; Esc closes without running anything
+        press(&mut app, KeyCode::F(4), KeyModifiers::NONE);
+        assert!(app.show_palette && app.palette.filter.is_empty());
+        press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        assert!(!app.show_palette);
+        let mut app = App { vi_enabled: true, vi_mode: ViMode::Normal, ..App::default() };
+        press(&mut app, KeyCode::Char(':'), KeyModifiers::NONE);
+        assert!(app.show_palette);
+        type_str(&mut app, "quit");
+        assert!(handle_key(&mut app, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)).unwrap());
+    }
+
+    #[test]
+    fn remapped_keys_replace_the_defaults() {
+        let mut keymap = Keymap::default();
+        keymap.set("undo", "ctrl+u, alt+backspace").unwrap();
+        keymap.set("select-all", "").unwrap();
+        assert!(keymap.set("launch", "ctrl+l").is_err());
+        assert!(keymap.set("run", "hyper+r").is_err());
+        let mut app = App { keymap, ..App::default() };
+        assert_eq!(command_keys(&app, Command::Undo), "Ctrl+U, Alt+Backspace");
+
+        type_str(&mut app, "+-");
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::CONTROL);
+        press(&mut app, KeyCode::Char('a'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec!["+-".to_string()]);
+        assert!(app.selection.is_none());
+        press(&mut app, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(app.buffer, vec![String::new()]);
+    }
+
+    #[test]
+    fn remapped_keys_take_precedence_over_the_defaults() {
+        let ctrl_s = parse_key_list("ctrl+s").unwrap()[0];
+        let mut keymap = Keymap::default();
+        keymap.set("find", "ctrl+s").unwrap();
+        assert!(keymap.keys(Command::Save).is_empty());
+        assert_eq!(keymap.lookup(ctrl_s, |_| true), Some(Command::Find));
+
+        // Two remapped commands cannot share a key; the first one keeps it
+        let err = keymap.set("save", "ctrl+s, ctrl+shift+s").unwrap_err();
+        assert_eq!(err, "Ctrl+S is already bound to 'find'");
+        assert!(keymap.keys(Command::Save).is_empty());
+        keymap.set("save", "ctrl+shift+s").unwrap();
+
+        let mut app = App { keymap, ..App::default() };
+        press(&mut app, KeyCode::Char('s'), KeyModifiers::CONTROL);
+        assert!(app.show_search);
+        assert!(!app.show_save_dialog);
+    }
+
+    #[test]
+    fn key_events_match_the_config_spelling() {
+        let spec = |code, modifiers| key_spec(&KeyEvent::new(code, modifiers)).map(|key| key.to_string());
+        assert_eq!(spec(KeyCode::Char('P'), KeyModifiers::CONTROL).as_deref(), Some("Ctrl+Shift+P"));
+        assert_eq!(spec(KeyCode::BackTab, KeyModifiers::SHIFT).as_deref(), Some("Shift+Tab"));
+        assert_eq!(spec(KeyCode::F(15), KeyModifiers::NONE).as_deref(), Some("Shift+F3"));
+        assert_eq!(spec(KeyCode::Char('+'), KeyModifiers::SHIFT).as_deref(), Some("+"));
+        assert_eq!(spec(KeyCode::Null, KeyModifiers::NONE), None);
+    }
+
+    #[test]
+    fn settings_dialog_validates_and_applies_every_value() {
+        let mut app = App::default();
+        press(&mut app, KeyCode::F(2), KeyModifiers::NONE);
+        assert!(app.show_settings);
+        // Timeout: "2000" -> "none"
+        for _ in 0..4 {
+            press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        }
+        type_str(&mut app, "none");
+        // Memory: an invalid value keeps the dialog open and changes nothing
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        type_str(&mut app, "x");
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(app.show_settings && app.settings_dialog.error.is_some());
+        assert_eq!(app.settings.timeout_ms, Some(2_000));
+
+        press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        }
+        type_str(&mut app, "16");
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Right, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(!app.show_settings);
+        assert_eq!(app.settings, ReplSettings { timeout_ms: None, max_steps: None, memory: 16, eof: EofBehavior::Unchanged });
+        assert!(app.keep_tape);
+        assert_eq!(app.tape.len(), 16);
+    }
+
+    #[test]
+    fn saving_settings_writes_only_values_edited_in_the_dialog() {
+        // The timeout came from BF_TIMEOUT_MS; it stays out of bf.toml unless edited
+        let settings = ReplSettings { timeout_ms: Some(5), ..ReplSettings::default() };
+        let mut app = App { settings, ..App::default() };
+        press(&mut app, KeyCode::F(2), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Down, KeyModifiers::NONE);
+        for _ in 0..5 {
+            press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        }
+        type_str(&mut app, "1e3");
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(settings_to_save(&app), vec![("memory", "1000".to_string())]);
+
+        // Edits from earlier in the session are kept; retyping an unchanged value is not an edit
+        press(&mut app, KeyCode::F(2), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        type_str(&mut app, "5");
+        press(&mut app, KeyCode::Up, KeyModifiers::NONE);
+        press(&mut app, KeyCode::Left, KeyModifiers::NONE);
+        assert!(apply_settings(&mut app));
+        assert_eq!(settings_to_save(&app), vec![("memory", "1000".to_string()), ("tape", "keep".to_string())]);
+
+        // The README's example table, comments included, loads every value
+        let config = "[ide]\ntimeout = \"none\"   # milliseconds or none (default 2000)\nmax-steps = 1e9    # count or none (default none)\n\
+                      memory = 65536     # tape cells (default 30000)\ntape = \"keep\"      # reset or keep (default reset)\n";
+        let mut loaded = App::default();
+        apply_ide_config(&mut loaded, &crate::config::parse_section(config, "ide"));
+        assert_eq!((loaded.settings.max_steps, loaded.settings.memory, loaded.keep_tape), (Some(1_000_000_000), 65_536, true));
+        assert!(loaded.status_message.is_none());
+    }
+
+    #[test]
+    fn kept_tapes_carry_over_to_the_next_run() {
+        let settings = ReplSettings { memory: 4, ..ReplSettings::default() };
+        let mut app = App { settings, keep_tape: true, ..App::default() };
+        type_str(&mut app, "+>++");
+        start_runner(&mut app);
+        wait_for(&mut app, |app| app.runner.is_none());
+        start_runner(&mut app);
+        wait_for(&mut app, |app| app.runner.is_none());
+        assert_eq!(app.tape, vec![1, 3, 2, 0]);
+        assert_eq!(app.tape_ptr, 2);
+
+        // The next run ends on the last cell, the one after steps past the end of the small tape
+        start_runner(&mut app);
+        wait_for(&mut app, |app| app.runner.is_none());
+        assert_eq!(app.tape, vec![1, 3, 3, 2]);
+        start_runner(&mut app);
+        wait_for(&mut app, |app| app.runner.is_none());
+        assert!(app.status_message.as_ref().is_some_and(|(text, _)| text.starts_with("Error")));
+    }
+
+    #[test]
+    fn paused_programs_can_have_their_cells_edited() {
+        let mut app = App::default();
+        // As drawn: the first rows of the tape are visible
+        app.tape_view.set((0, 64));
+        type_str(&mut app, "+[]");
+        start_runner(&mut app);
+        press(&mut app, KeyCode::F(6), KeyModifiers::NONE);
+        wait_for(&mut app, |app| app.paused);
+        assert_eq!(app.tape[0], 1);
+
+        app.focused = Focus::Tape;
+        press(&mut app, KeyCode::Char('e'), KeyModifiers::NONE);
+        press(&mut app, KeyCode::Backspace, KeyModifiers::NONE);
+        type_str(&mut app, "0");
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert_eq!(app.tape[0], 0);
+        assert!(app.tape_prompt.is_none() && app.tape_error.is_none());
+
+        press(&mut app, KeyCode::F(6), KeyModifiers::NONE);
+        wait_for(&mut app, |app| app.runner.is_none());
+        assert!(!app.paused);
+        assert_eq!(app.status_message.as_ref().map(|(text, _)| text.as_str()), Some("Program finished"));
+    }
+
+    #[test]
+    fn run_snapshots_carry_the_visible_cells_and_the_final_one_the_whole_tape() {
+        let mut app = App::default();
+        type_str(&mut app, ">>>>>+[]");
+        start_runner(&mut app);
+        let handle = app.runner.take().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            request_tape_view(&handle, (4, 8), false);
+            if let RunnerMsg::Tape { ptr, start, cells } = handle.rx_msg.recv_timeout(Duration::from_secs(5)).unwrap() {
+                assert_eq!((start, cells.len()), (4, 4));
+                if (ptr, cells.as_slice()) == (5, [0, 1, 0, 0].as_slice()) {
+                    break;
+                }
+            }
+            assert!(Instant::now() < deadline, "the program never reached its loop");
+        }
+
+        handle.cancel.store(true, Ordering::Relaxed);
+        let mut last = None;
+        loop {
+            match handle.rx_msg.recv_timeout(Duration::from_secs(5)).unwrap() {
+                RunnerMsg::Tape { start, cells, .. } => last = Some((start, cells)),
+                RunnerMsg::Halted(_) => break,
+                _ => {}
+            }
+        }
+        let (start, cells) = last.unwrap();
+        assert_eq!((start, cells.len(), cells[5]), (0, 30_000, 1));
+
+        // A window lands in place; a whole tape replaces it
+        let mut tape = vec![0; 8];
+        apply_tape_snapshot(&mut tape, 4, vec![7, 8]);
+        assert_eq!(tape, [0, 0, 0, 0, 7, 8, 0, 0]);
+        apply_tape_snapshot(&mut tape, 0, vec![1; 16]);
+        assert_eq!(tape, vec![1; 16]);
+    }
+
+    #[test]
+    fn tape_cells_can_only_be_edited_while_paused() {
+        let mut app = App { focused: Focus::Tape, ..App::default() };
+        press(&mut app, KeyCode::Char('+'), KeyModifiers::NONE);
+        assert_eq!(app.tape[0], 0);
+        assert_eq!(app.tape_error.as_deref(), Some("Pause the program (F6) to edit cells"));
+    }
+
+    #[test]
+    fn tape_pane_finds_addresses_values_and_regions() {
+        let mut tape = vec![0; TAPE_CELLS];
+        tape[3] = 65;
+        tape[4] = 1;
+        tape[100] = 65;
+        let mut app = App { focused: Focus::Tape, tape, ..App::default() };
+        assert_eq!(describe_regions(&nonzero_regions(&app.tape), 3), "3-4 100");
+
+        press(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+        type_str(&mut app, "0x10\n");
+        assert_eq!(app.tape_cursor, 16);
+        assert!(!app.tape_follow);
+
+        press(&mut app, KeyCode::Char('/'), KeyModifiers::NONE);
+        type_str(&mut app, "'A'\n");
+        assert_eq!(app.tape_cursor, 100);
+        press(&mut app, KeyCode::Char('n'), KeyModifiers::NONE);
+        assert_eq!(app.tape_cursor, 3);
+        press(&mut app, KeyCode::Char('N'), KeyModifiers::NONE);
+        assert_eq!(app.tape_cursor, 100);
+
+        press(&mut app, KeyCode::Char('Z'), KeyModifiers::NONE);
+        assert_eq!(app.tape_cursor, 3);
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::NONE);
+        assert_eq!(app.tape_cursor, 100);
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::NONE);
+        assert_eq!(app.tape_cursor, 100);
+        assert!(app.tape_error.is_some());
+
+        press(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+        type_str(&mut app, "30000\n");
+        assert_eq!(app.tape_cursor, 100);
+        assert!(app.tape_error.is_some());
+
+        // Esc closes the prompt without quitting
+        press(&mut app, KeyCode::Char('g'), KeyModifiers::NONE);
+        assert!(!handle_key(&mut app, KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)).unwrap());
+        assert!(app.tape_prompt.is_none());
+
+        press(&mut app, KeyCode::Char('c'), KeyModifiers::NONE);
+        assert_eq!(app.tape_cursor, 0);
+        assert!(app.tape_follow);
+
+        // The same actions run from the palette (focusing the pane) and follow remapped keys
+        app.focused = Focus::Editor;
+        assert!(!run_command(&mut app, Command::TapeNextNonzero));
+        assert_eq!(app.focused, Focus::Tape);
+        assert_eq!(app.tape_cursor, 3);
+        app.keymap.set("tape-next-nonzero", "j").unwrap();
+        press(&mut app, KeyCode::Char('z'), KeyModifiers::NONE);
+        assert_eq!(app.tape_cursor, 3);
+        press(&mut app, KeyCode::Char('j'), KeyModifiers::NONE);
+        assert_eq!(app.tape_cursor, 100);
+    }
+
+    #[test]
+    fn cell_values_parse_and_render_in_every_format() {
+        assert_eq!(parse_cell_value("255"), Some(255));
+        assert_eq!(parse_cell_value("0x41"), Some(65));
+        assert_eq!(parse_cell_value("'A'"), Some(65));
+        assert_eq!(parse_cell_value("256"), None);
+        assert_eq!(parse_cell_value("'AB'"), None);
+        assert_eq!(cell_text(65, CellFormat::Hex), "[41]");
+        assert_eq!(cell_text(65, CellFormat::Dec), " 65 ");
+        assert_eq!(cell_text(65, CellFormat::Char), "'A' ");
+        assert_eq!(cell_text(10, CellFormat::Char), " \\n ");
+        assert_eq!(cell_text(200, CellFormat::Char), "200 ");
+    }
+
+    #[test]
+    fn input_panel_attaches_files_and_cycles_eof() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("data.txt"), "1 2 3").unwrap();
+        let filename = Some(dir.path().join("prog.bf").to_string_lossy().into_owned());
+        let mut app = App { filename, ..App::default() };
+        press(&mut app, KeyCode::Char('d'), KeyModifiers::CONTROL);
+        press(&mut app, KeyCode::Char('o'), KeyModifiers::CONTROL);
+        // Input files are listed whatever their extension
+        type_str(&mut app, "data");
+        press(&mut app, KeyCode::Enter, KeyModifiers::NONE);
+        assert!(!app.show_file_browser && app.show_input_panel);
+        assert_eq!(app.prepared_input.file, Some(dir.path().join("data.txt")));
+        assert_eq!(app.buffers.len(), 1);
+
+        press(&mut app, KeyCode::Char('e'), KeyModifiers::CONTROL);
+        assert_eq!(app.settings.eof, EofBehavior::Unchanged);
+        press(&mut app, KeyCode::Char('x'), KeyModifiers::CONTROL);
+        assert_eq!(app.prepared_input.file, None);
+
+        // A missing input file stops the run before it starts
+        app.prepared_input.file = Some(dir.path().join("gone.txt"));
+        press(&mut app, KeyCode::Esc, KeyModifiers::NONE);
+        type_str(&mut app, ",.");
+        start_runner(&mut app);
+        assert!(app.runner.is_none());
+        assert!(app.status_message.as_ref().unwrap().0.starts_with("Cannot read input file"));
+    }
+
+    #[test]
+    fn line_edit_diff_covers_only_changed_lines() {
+        let lines = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let edit = LineEdit::diff(&lines(&["a", "b", "c"]), &lines(&["a", "x", "y", "c"])).unwrap();
+        assert_eq!(edit, LineEdit { row: 1, old: lines(&["b"]), new: lines(&["x", "y"]) });
+        assert_eq!(LineEdit::diff(&lines(&["a"]), &lines(&["a"])), None);
+        let edit = LineEdit::diff(&lines(&["a", "a"]), &lines(&["a"])).unwrap();
+        assert_eq!(edit, LineEdit { row: 1, old: lines(&["a"]), new: vec![] });
+    }
+}
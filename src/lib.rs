@@ -5,6 +5,8 @@ pub mod repl;
 pub mod ide;
 pub mod config;
 mod writer;
+mod golf;
+mod formatter;
 
 /// Keep only Brainfuck instruction characters.
 pub fn bf_only(s: &str) -> String {
@@ -13,6 +15,8 @@ pub fn bf_only(s: &str) -> String {
         .collect()
 }
 
-pub use reader::{BrainfuckReader, BrainfuckReaderError, UnmatchedBracketKind};
-pub use writer::{BrainfuckWriter, WriterOptions};
+pub use reader::{BrainfuckReader, BrainfuckReaderError, EofBehavior, UnmatchedBracketKind};
+pub use writer::{BrainfuckWriter, CellWidth, WriterOptions, WriterTarget, WriterTemplate};
+pub use formatter::{BrainfuckFormatter, FormatOptions};
+pub use golf::{BrainfuckGolfer, GolfVerifyError, GolfVerifyReport, default_verify_samples};
 pub use repl::ModeFlagOverride;
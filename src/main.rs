@@ -10,6 +10,9 @@ fn print_top_usage_and_exit(program: &str, code: i32) -> ! {
   {0} read  [--debug|-d] --file <PATH> # Run Brainfuck code loaded from file
   {0} write [--bytes] [TEXT...]        # Generate Brainfuck to print TEXT/STDIN/file
   {0} write [--bytes] --file <PATH>    # Generate Brainfuck to print file contents
+  {0} write --template <NAME>          # Generate a Brainfuck program that transforms its input
+  {0} golf  [--verify] "<code>"        # Print a shorter equivalent program (also --file <PATH>)
+  {0} fmt   [--check] "<code>"         # Indent and wrap Brainfuck source (also --file <PATH>)
   {0} repl                             # Start a Brainfuck REPL (read-eval-print loop)
   {0} ide   [--file <PATH>]            # Start a terminal-based Brainfuck IDE, optionally loading a file
 
@@ -36,6 +39,8 @@ struct Cli {
 enum Command {
     Read(rust_bf::commands::read::ReadArgs),
     Write(rust_bf::commands::write::WriteArgs),
+    Golf(rust_bf::commands::golf::GolfArgs),
+    Fmt(rust_bf::commands::fmt::FmtArgs),
     Repl(rust_bf::commands::repl::ReplArgs),
     Ide(rust_bf::commands::ide::IdeArgs),
 }
@@ -53,6 +58,8 @@ fn main() {
     let code = match cli.command {
         Some(Command::Read(args)) => rust_bf::commands::read::run(&program, args),
         Some(Command::Write(args)) => rust_bf::commands::write::run(&program, args),
+        Some(Command::Golf(args)) => rust_bf::commands::golf::run(&program, args),
+        Some(Command::Fmt(args)) => rust_bf::commands::fmt::run(&program, args),
         Some(Command::Repl(args)) => {
             let program = "repl";
             let mode_flag = if args.bare {
@@ -63,7 +70,15 @@ fn main() {
                 rust_bf::repl::ModeFlagOverride::None
             };
             
-            let code = rust_bf::commands::repl::run(&program, false, mode_flag);
+            let options = rust_bf::commands::repl::options_from_args(program, &args);
+            let code = match &args.script {
+                Some(_) if args.help => {
+                    eprintln!("Error: --help cannot be used with --script");
+                    1
+                }
+                Some(script) => rust_bf::commands::repl::run_script(program, script, options),
+                None => rust_bf::commands::repl::run(&program, args.help, mode_flag, options),
+            };
             std::process::exit(code);
         },
         Some(Command::Ide(args)) => {
@@ -81,7 +96,7 @@ fn main() {
         }
         None => {
             // Default to REPL when no subcommand is provided
-            rust_bf::commands::repl::run(&program, false, rust_bf::repl::ModeFlagOverride::None)
+            rust_bf::commands::repl::run(&program, false, rust_bf::repl::ModeFlagOverride::None, rust_bf::repl::ReplOptions::load())
         }
     };
 
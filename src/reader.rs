@@ -7,7 +7,8 @@
 //! - Memory tape initialized to 0.
 //! - Strict pointer bounds: moving left from cell 0 or right past the end
 //!   returns an error.
-//! - Input `,` reads a single byte from stdin; on EOF the current cell is set to 0.
+//! - Input `,` reads a single byte from stdin; on EOF the current cell is set to 0
+//!   (see [`EofBehavior`] for the alternatives).
 //! - Output `.` prints the byte at the current cell as a character (no newline).
 //! - Properly handles nested loops `[]`; unmatched brackets are reported as errors.
 //! - Any non-Brainfuck character causes an error.
@@ -74,6 +75,52 @@ impl fmt::Display for UnmatchedBracketKind {
     }
 }
 
+/// What `,` stores in the current cell when input is exhausted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EofBehavior {
+    /// Set the cell to 0 (the default)
+    #[default]
+    Zero,
+    /// Leave the cell as it was
+    Unchanged,
+    /// Set the cell to 255 (i.e. -1)
+    Max,
+}
+
+impl EofBehavior {
+    fn apply(self, cell: &mut u8) {
+        match self {
+            EofBehavior::Zero => *cell = 0,
+            EofBehavior::Unchanged => {}
+            EofBehavior::Max => *cell = u8::MAX,
+        }
+    }
+}
+
+/// Parses `zero` (or `0`), `unchanged` (or `keep`) and `max` (or `255`, `-1`).
+impl std::str::FromStr for EofBehavior {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zero" | "0" => Ok(EofBehavior::Zero),
+            "unchanged" | "keep" => Ok(EofBehavior::Unchanged),
+            "max" | "255" | "-1" => Ok(EofBehavior::Max),
+            _ => Err(format!("invalid eof behavior '{s}' (zero, unchanged or max)")),
+        }
+    }
+}
+
+impl fmt::Display for EofBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EofBehavior::Zero => "zero",
+            EofBehavior::Unchanged => "unchanged",
+            EofBehavior::Max => "max",
+        })
+    }
+}
+
 /// Controls for cooperative cancellation and step limiting.
 #[derive(Clone)]
 pub struct StepControl {
@@ -97,13 +144,20 @@ pub struct BrainfuckReader {
     code: String,
     memory: Vec<u8>,
     pointer: usize,
+    // Instructions executed by the most recent run
+    steps: usize,
+    eof_behavior: EofBehavior,
     // Optional hooks:
     output_sink: Option<Box<dyn Fn(&[u8]) + Send + Sync>>,
     input_provider: Option<Box<dyn Fn() -> Option<u8> + Send + Sync>>,
     // (window_size, observer (ptr, base, window_slice))
     tape_observer: Option<(usize, Box<dyn Fn(usize, usize, &[u8]) + Send + Sync>)>,
+    // (ptr, whole tape), may modify cells
+    step_hook: Option<StepHook>,
 }
 
+type StepHook = Box<dyn Fn(usize, &mut [u8]) + Send + Sync>;
+
 impl BrainfuckReader {
     /// Create a new interpreter from Brainfuck `code`.
     ///
@@ -113,9 +167,12 @@ impl BrainfuckReader {
             code,
             memory: vec![0; 30000],
             pointer: 0,
+            steps: 0,
+            eof_behavior: EofBehavior::Zero,
             output_sink: None,
             input_provider: None,
             tape_observer: None,
+            step_hook: None,
         }
     }
 
@@ -125,12 +182,41 @@ impl BrainfuckReader {
             code,
             memory: vec![0; memory_size],
             pointer: 0,
+            steps: 0,
+            eof_behavior: EofBehavior::Zero,
             output_sink: None,
             input_provider: None,
             tape_observer: None,
+            step_hook: None,
         }
     }
 
+    /// Replace the memory tape and data pointer, e.g. to resume a previous session.
+    /// The pointer is clamped to the last cell of `memory`.
+    ///
+    /// Panics if `memory` is empty: the pointer must always be on a cell.
+    pub fn set_state(&mut self, memory: Vec<u8>, pointer: usize) {
+        assert!(!memory.is_empty(), "the tape needs at least one cell");
+        self.pointer = pointer.min(memory.len() - 1);
+        self.memory = memory;
+    }
+
+    /// Number of instructions executed by the most recent run (including a run that
+    /// ended with an error).
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Consume the interpreter, returning its memory tape and data pointer.
+    pub fn into_state(self) -> (Vec<u8>, usize) {
+        (self.memory, self.pointer)
+    }
+
+    /// Choose what `,` does at EOF (default: set the cell to 0).
+    pub fn set_eof_behavior(&mut self, eof: EofBehavior) {
+        self.eof_behavior = eof;
+    }
+
     /// Provide an output sink. When set, '.' sends bytes to this sink instead of stdout.
     /// The sink receives a slice of bytes; for Brainfuck, it will be a single-byte slice per '.'.
     pub fn set_output_sink<F>(&mut self, sink: F)
@@ -141,7 +227,7 @@ impl BrainfuckReader {
     }
 
     /// Provide an input provider. When set, ',' reads from this provider instead of stdin.
-    /// Returning None indicates EOF (cell is set to 0).
+    /// Returning None indicates EOF (the cell is updated per [`EofBehavior`]).
     pub fn set_input_provider<F>(&mut self, provider: F)
     where
         F: Fn() -> Option<u8> + Send + Sync + 'static,
@@ -160,6 +246,16 @@ impl BrainfuckReader {
         self.tape_observer = Some((window_size.max(1), Box::new(observer)));
     }
 
+    /// Provide a hook that runs after every instruction with the data pointer and the whole
+    /// tape. Unlike the tape observer it may change cells, e.g. a debugger editing memory
+    /// while the program is paused. It runs on every step, so keep the common path cheap.
+    pub fn set_step_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(usize, &mut [u8]) + Send + Sync + 'static,
+    {
+        self.step_hook = Some(Box::new(hook));
+    }
+
     /// Internal executor shared by run and run_debug.
     fn execute(&mut self, debug: bool, step_control: Option<&StepControl>) -> Result<(), BrainfuckReaderError> {
         let mut code_ptr = 0;
@@ -196,6 +292,7 @@ impl BrainfuckReader {
         }
 
         let mut step: usize = 0;
+        self.steps = 0;
         if debug {
             println!("STEP | IP  | PTR | CELL | INSTR | ACTION");
             println!("-----+-----+-----+------+-------+------------------------------------------------");
@@ -270,25 +367,25 @@ impl BrainfuckReader {
                 }
                 ',' => {
                     if debug {
-                        self.memory[self.pointer] = 0; // simulate EOF
-                        if let Some(a) = action.as_mut() { *a = "Read byte from stdin -> simulated EOF (set cell to 0)".to_string(); }
+                        self.eof_behavior.apply(&mut self.memory[self.pointer]); // simulate EOF
+                        if let Some(a) = action.as_mut() { *a = format!("Read byte from stdin -> simulated EOF (cell is {})", self.memory[self.pointer]); }
                     } else {
                         // Prefer input provider when set; fall back to stdin.
                         if let Some(provider) = self.input_provider.as_ref() {
                             match (provider)() {
                                 Some(b) => { self.memory[self.pointer] = b; }
-                                None => { self.memory[self.pointer] = 0; } // EOF
+                                None => { self.eof_behavior.apply(&mut self.memory[self.pointer]); } // EOF
                             }
                             if let Some(a) = action.as_mut() { *a = format!("Read byte from input provider -> {}", self.memory[self.pointer]); }
                         } else {
                             // Read exactly one byte from stdin into the current cell.
-                            // On EOF, set the current cell to 0.
+                            // On EOF, apply the configured EOF behavior.
                             use std::io::Read;
                             let mut buf = [0u8; 1];
                             match std::io::stdin().read(&mut buf) {
                                 Ok(0) => {
                                     // EOF: common BF behavior is to set cell to 0
-                                    self.memory[self.pointer] = 0;
+                                    self.eof_behavior.apply(&mut self.memory[self.pointer]);
                                 }
                                 Ok(_) => {
                                     self.memory[self.pointer] = buf[0];
@@ -330,6 +427,9 @@ impl BrainfuckReader {
                 let end = (base + *win_size).min(self.memory.len());
                 (observer)(self.pointer, base, &self.memory[base..end]);
             }
+            if let Some(hook) = self.step_hook.as_ref() {
+                (hook)(self.pointer, &mut self.memory);
+            }
 
             if debug {
                 println!(
@@ -346,6 +446,7 @@ impl BrainfuckReader {
 
             // Advance step counter
             step += 1;
+            self.steps += 1;
             // Move to the next instruction
             code_ptr += 1;
         }
@@ -439,6 +540,52 @@ mod tests {
         assert_eq!(bf.memory[0], 255);
     }
 
+    #[test]
+    fn state_carries_between_readers() {
+        let mut first = BrainfuckReader::new_with_memory("+++>++".to_string(), 4);
+        first.run().unwrap();
+        let (memory, pointer) = first.into_state();
+
+        let mut second = BrainfuckReader::new_with_memory("+<+".to_string(), 1);
+        second.set_state(memory, pointer);
+        second.run().unwrap();
+        assert_eq!(second.steps(), 3);
+        assert_eq!(second.into_state(), (vec![4, 3, 0, 0], 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one cell")]
+    fn state_needs_a_cell() {
+        BrainfuckReader::new("+".to_string()).set_state(Vec::new(), 0);
+    }
+
+    #[test]
+    fn eof_behavior_applies_to_input_provider() {
+        for (eof, expected) in [(EofBehavior::Zero, 0), (EofBehavior::Unchanged, 5), (EofBehavior::Max, 255)] {
+            let mut bf = BrainfuckReader::new_with_memory("+++++,".to_string(), 1);
+            bf.set_input_provider(|| None);
+            bf.set_eof_behavior(eof);
+            bf.run().unwrap();
+            assert_eq!(bf.into_state().0, vec![expected]);
+        }
+    }
+
+    #[test]
+    fn step_hook_can_change_cells() {
+        // The hook turns the 1 left by '+' into 'A' before '.' prints it
+        let out = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = out.clone();
+        let mut bf = BrainfuckReader::new_with_memory("+.".to_string(), 4);
+        bf.set_output_sink(move |bytes| sink.lock().unwrap().extend_from_slice(bytes));
+        bf.set_step_hook(|ptr, memory| {
+            if memory[ptr] == 1 {
+                memory[ptr] = b'A';
+            }
+        });
+        bf.run().unwrap();
+        assert_eq!(*out.lock().unwrap(), b"A");
+    }
+
     #[test]
     fn wrapping_addition() {
         let code = "+".repeat(256); // 256 increments should wrap around
@@ -1,21 +1,456 @@
 use std::{env, thread};
+use std::collections::{HashMap, VecDeque};
 use std::io::{self, IsTerminal, Write};
-use std::sync::{mpsc, Arc};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
-use reedline::{Signal, DefaultPrompt, DefaultPromptSegment, HistoryItem, Highlighter, StyledText};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use reedline::{Completer, Hinter, History, KeyCode, KeyModifiers, SearchDirection, SearchQuery, Signal, ValidationResult, Validator, DefaultPrompt, DefaultPromptSegment, HistoryItem, Highlighter, Span, StyledText, Suggestion};
 use nu_ansi_term::Style;
-use crate::{cli_util, BrainfuckReader, BrainfuckReaderError, bf_only};
-use crate::cli_util::rat_to_nu;
+use crate::{cli_util, config, BrainfuckReader, BrainfuckReaderError, EofBehavior, bf_only};
+use crate::cli_util::{rat_to_nu, KeyName, KeySpec};
 use crate::reader::StepControl;
 
-pub fn repl_loop() -> io::Result<()> {
+/// Options for an interactive REPL session, set from `bf repl` flags.
+#[derive(Debug, Clone, Default)]
+pub struct ReplOptions {
+    /// Keep tape, pointer and pending input across submissions (`--persist`)
+    pub persist: bool,
+    /// Execution limits and tape settings
+    pub settings: ReplSettings,
+    /// Where and how much editor history to keep
+    pub history: HistoryOptions,
+    /// Use reedline's Vi edit mode instead of Emacs (`--vi`, or `edit-mode = "vi"`)
+    pub vi_mode: bool,
+    /// Keys for submitting, newlines and history navigation
+    pub keys: ReplKeys,
+}
+
+impl ReplOptions {
+    /// Options from the config file and environment, before any flags are applied.
+    pub fn load() -> Self {
+        let vi_mode = match config::read_section("repl").get("edit-mode").map(String::as_str) {
+            None | Some("emacs") => false,
+            Some("vi") => true,
+            Some(other) => {
+                eprintln!("repl: ignoring edit-mode = {other}: expected \"emacs\" or \"vi\"");
+                false
+            }
+        };
+        Self {
+            persist: false,
+            settings: ReplSettings::load(),
+            history: HistoryOptions::load(),
+            vi_mode,
+            keys: ReplKeys::load(),
+        }
+    }
+}
+
+/// Remappable editor actions and their default keys.
+const KEY_ACTIONS: [(&str, &str); 4] = [
+    ("submit", "ctrl+d, ctrl+z"),
+    ("newline", "enter"),
+    ("history-previous", "alt+up, ctrl+up"),
+    ("history-next", "alt+down, ctrl+down"),
+];
+
+/// Key bindings for the remappable editor actions (see [`KEY_ACTIONS`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplKeys {
+    bindings: Vec<(&'static str, Vec<(KeyModifiers, KeyCode)>)>,
+}
+
+impl Default for ReplKeys {
+    fn default() -> Self {
+        let bindings = KEY_ACTIONS
+            .iter()
+            .map(|(action, keys)| (*action, parse_key_list(keys).expect("default keys are valid")))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl ReplKeys {
+    /// Defaults, with actions listed in the `[repl.keys]` table of `bf.toml` replaced,
+    /// e.g. `history-previous = "alt+up, ctrl+p"`.
+    pub fn load() -> Self {
+        let mut keys = Self::default();
+        for (action, value) in config::read_section("repl.keys") {
+            if let Err(e) = keys.set(&action, &value) {
+                eprintln!("repl: ignoring [repl.keys] {action} = {value}: {e}");
+            }
+        }
+        keys
+    }
+
+    /// Replace the keys for `action` with a comma-separated list; an empty list unbinds it.
+    pub fn set(&mut self, action: &str, keys: &str) -> Result<(), String> {
+        let parsed = parse_key_list(keys)?;
+        match self.bindings.iter_mut().find(|(name, _)| *name == action) {
+            Some((_, bound)) => *bound = parsed,
+            None => {
+                let names: Vec<&str> = KEY_ACTIONS.iter().map(|(name, _)| *name).collect();
+                return Err(format!("unknown action '{action}' (expected one of: {})", names.join(", ")));
+            }
+        }
+        Ok(())
+    }
+
+    fn add_to(&self, keybindings: &mut reedline::Keybindings) {
+        use reedline::{EditCommand, ReedlineEvent};
+        for (action, keys) in &self.bindings {
+            let event = match *action {
+                // Enter (unlike Submit) runs the validator, so unbalanced code gets a newline instead
+                "submit" => ReedlineEvent::Enter,
+                "newline" => ReedlineEvent::Edit(vec![EditCommand::InsertNewline]),
+                "history-previous" => ReedlineEvent::PreviousHistory,
+                _ => ReedlineEvent::NextHistory,
+            };
+            for (modifiers, code) in keys {
+                keybindings.add_binding(*modifiers, *code, event.clone());
+            }
+        }
+    }
+}
+
+fn parse_key_list(keys: &str) -> Result<Vec<(KeyModifiers, KeyCode)>, String> {
+    Ok(cli_util::parse_key_list(keys)?.into_iter().map(to_reedline_key).collect())
+}
+
+fn to_reedline_key(spec: KeySpec) -> (KeyModifiers, KeyCode) {
+    let mut modifiers = KeyModifiers::NONE;
+    for (held, modifier) in [(spec.ctrl, KeyModifiers::CONTROL), (spec.alt, KeyModifiers::ALT), (spec.shift, KeyModifiers::SHIFT)] {
+        if held {
+            modifiers |= modifier;
+        }
+    }
+    let code = match spec.key {
+        KeyName::Char(c) => KeyCode::Char(c),
+        KeyName::F(n) => KeyCode::F(n),
+        KeyName::Enter => KeyCode::Enter,
+        KeyName::Tab => KeyCode::Tab,
+        KeyName::Esc => KeyCode::Esc,
+        KeyName::Backspace => KeyCode::Backspace,
+        KeyName::Delete => KeyCode::Delete,
+        KeyName::Insert => KeyCode::Insert,
+        KeyName::Up => KeyCode::Up,
+        KeyName::Down => KeyCode::Down,
+        KeyName::Left => KeyCode::Left,
+        KeyName::Right => KeyCode::Right,
+        KeyName::Home => KeyCode::Home,
+        KeyName::End => KeyCode::End,
+        KeyName::PageUp => KeyCode::PageUp,
+        KeyName::PageDown => KeyCode::PageDown,
+    };
+    (modifiers, code)
+}
+
+/// History file settings for the interactive editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryOptions {
+    /// History file; `None` keeps history in memory for the session only
+    pub file: Option<PathBuf>,
+    /// Maximum number of entries kept
+    pub size: usize,
+}
+
+impl Default for HistoryOptions {
+    fn default() -> Self {
+        Self { file: config::data_dir().map(|dir| dir.join("repl_history")), size: 1_000 }
+    }
+}
+
+impl HistoryOptions {
+    /// Defaults, overridden by `history-file` and `history-size` in the `[repl]` table of `bf.toml`.
+    pub fn load() -> Self {
+        Self::from_table(&config::read_section("repl"))
+    }
+
+    /// Defaults, overridden by the history keys of a `[repl]` table; invalid values are reported and ignored.
+    fn from_table(table: &HashMap<String, String>) -> Self {
+        let mut history = Self::default();
+        for name in ["history-file", "history-size"] {
+            if let Some(value) = table.get(name)
+                && let Err(e) = history.set(name, value)
+            {
+                eprintln!("repl: ignoring {name} = {value}: {e}");
+            }
+        }
+        history
+    }
+
+    /// Change `history-file` (a path, or `none` for in-memory history) or `history-size`.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "history-file" => {
+                self.file = match value {
+                    "none" | "off" => None,
+                    path => Some(PathBuf::from(expand_home(path))),
+                };
+            }
+            "history-size" => {
+                self.size = parse_count(value)
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| format!("invalid history size '{value}' (at least 1)"))?;
+            }
+            _ => return Err(format!("unknown history setting '{name}'")),
+        }
+        Ok(())
+    }
+}
+
+/// Execution settings for REPL runs, changeable at runtime with `:set <name> <value>`.
+/// The IDE uses the same settings, loaded from its own `[ide]` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplSettings {
+    /// Wall-clock limit per run; `None` waits indefinitely
+    pub timeout_ms: Option<u64>,
+    /// Instruction limit per run; `None` is unlimited
+    pub max_steps: Option<usize>,
+    /// Tape size in cells
+    pub memory: usize,
+    /// What `,` stores at EOF
+    pub eof: EofBehavior,
+}
+
+impl Default for ReplSettings {
+    fn default() -> Self {
+        Self { timeout_ms: Some(2_000), max_steps: None, memory: 30_000, eof: EofBehavior::Zero }
+    }
+}
+
+/// Upper bound for `:set memory`, to keep a typo from allocating gigabytes.
+const MAX_MEMORY: usize = 1 << 26;
+
+impl ReplSettings {
+    /// Setting names accepted by [`ReplSettings::set`], in display order.
+    pub const NAMES: [&'static str; 4] = ["timeout", "max-steps", "memory", "eof"];
+
+    /// Defaults, overridden by the `[repl]` table of `bf.toml`, then by `BF_TIMEOUT_MS`
+    /// and `BF_MAX_STEPS`. Invalid values are reported and skipped.
+    pub fn load() -> Self {
+        let (settings, problems) = Self::load_section("repl");
+        for problem in problems {
+            eprintln!("repl: {problem}");
+        }
+        settings
+    }
+
+    /// Like [`ReplSettings::load`], but from the `[section]` table; invalid values are
+    /// skipped and described in the returned messages instead of printed.
+    pub fn load_section(section: &str) -> (Self, Vec<String>) {
+        Self::from_table(&config::read_section(section))
+    }
+
+    /// Defaults, overridden by the settings in a config table, then by the environment.
+    pub fn from_table(table: &HashMap<String, String>) -> (Self, Vec<String>) {
+        let mut settings = Self::default();
+        let mut problems = Vec::new();
+        let from_env = [("timeout", env::var("BF_TIMEOUT_MS").ok()), ("max-steps", env::var("BF_MAX_STEPS").ok())];
+        let from_config = Self::NAMES.iter().map(|name| (*name, table.get(*name).cloned()));
+        for (name, value) in from_config.chain(from_env) {
+            if let Some(value) = value
+                && let Err(e) = settings.set(name, &value)
+            {
+                problems.push(format!("ignoring {name} = {value}: {e}"));
+            }
+        }
+        (settings, problems)
+    }
+
+    /// Change one setting by name, e.g. `set("max-steps", "1e6")`.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let unlimited = matches!(value, "none" | "off" | "unlimited");
+        match name {
+            "timeout" => {
+                self.timeout_ms = if unlimited {
+                    None
+                } else {
+                    let ms = parse_count(value).ok_or_else(|| format!("invalid timeout '{value}' (milliseconds or 'none')"))?;
+                    Some(u64::try_from(ms).unwrap_or(u64::MAX))
+                };
+            }
+            "max-steps" => {
+                self.max_steps = if unlimited {
+                    None
+                } else {
+                    Some(parse_count(value).ok_or_else(|| format!("invalid step limit '{value}' (a count like 1000000 or 1e6, or 'none')"))?)
+                };
+            }
+            "memory" => {
+                self.memory = parse_count(value)
+                    .filter(|cells| (1..=MAX_MEMORY).contains(cells))
+                    .ok_or_else(|| format!("invalid memory size '{value}' (1..={MAX_MEMORY} cells)"))?;
+            }
+            "eof" => self.eof = value.parse()?,
+            _ => return Err(format!("unknown setting '{name}' (expected one of: {})", Self::NAMES.join(", "))),
+        }
+        Ok(())
+    }
+
+    /// Current values by name, in the form accepted by [`ReplSettings::set`].
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("timeout", self.timeout_ms.map_or("none".to_string(), |ms| ms.to_string())),
+            ("max-steps", self.max_steps.map_or("none".to_string(), |n| n.to_string())),
+            ("memory", self.memory.to_string()),
+            ("eof", self.eof.to_string()),
+        ]
+    }
+}
+
+/// Parse a non-negative count: `5000`, `1_000_000` or `1e6`.
+fn parse_count(s: &str) -> Option<usize> {
+    let digits = s.replace('_', "");
+    if digits.contains(['e', 'E']) {
+        let n = digits.parse::<f64>().ok()?;
+        if n.is_finite() && n >= 0.0 && n.fract() == 0.0 && n <= usize::MAX as f64 {
+            return Some(n as usize);
+        }
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Machine state kept between submissions. The last run's final tape is always kept for
+/// inspection; it is only used as the starting state of the next run while persistence is on.
+struct ReplSession {
+    persist: bool,
+    settings: ReplSettings,
+    memory: Vec<u8>,
+    pointer: usize,
+    // Bytes queued for `,` (by `:input`, `:input-file` or an earlier prompt) but not consumed yet
+    pending_input: Arc<Mutex<VecDeque<u8>>>,
+    // Lines typed at the inline input prompt since the last recorded entry, replayed as `:input`
+    prompted_input: Arc<Mutex<Vec<u8>>>,
+    // Prompt for a line on stdin when the queue runs dry; when off, `,` sees EOF instead
+    stdin_input: bool,
+    last_run: Option<RunStats>,
+    transcript: Option<Transcript>,
+}
+
+/// An open `:record` transcript, written in the format `bf repl --script` replays: code as
+/// submitted, meta-commands as `:` lines, and `:#` between consecutive code submissions so
+/// each one still runs as its own program.
+struct Transcript {
+    path: String,
+    file: std::fs::File,
+    after_code: bool,
+}
+
+impl ReplSession {
+    fn new(persist: bool, settings: ReplSettings) -> Self {
+        Self {
+            persist,
+            memory: vec![0; settings.memory],
+            settings,
+            pointer: 0,
+            pending_input: Arc::new(Mutex::new(VecDeque::new())),
+            prompted_input: Arc::new(Mutex::new(Vec::new())),
+            stdin_input: true,
+            last_run: None,
+            transcript: None,
+        }
+    }
+
+    /// Clear tape, pointer and pending input; the persistence setting is kept.
+    fn reset(&mut self) {
+        self.memory = vec![0; self.settings.memory];
+        self.pointer = 0;
+        if let Ok(mut pending) = self.pending_input.lock() {
+            pending.clear();
+        }
+    }
+
+    /// Change a setting; a new tape size resizes the tape, keeping cells that still fit.
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.settings.set(name, value)?;
+        if self.memory.len() != self.settings.memory {
+            self.memory.resize(self.settings.memory, 0);
+            self.pointer = self.pointer.min(self.settings.memory - 1);
+        }
+        Ok(())
+    }
+
+    /// Start a transcript at `path`, replacing any open one. The header restores persistence
+    /// and non-default settings so a replay starts from the same configuration.
+    fn start_recording(&mut self, path: &str) -> io::Result<()> {
+        let mut header = String::from(":# bf repl transcript\n");
+        if self.persist {
+            header.push_str(":persist on\n");
+        }
+        let defaults = ReplSettings::default().entries();
+        for ((name, value), (_, default)) in self.settings.entries().into_iter().zip(defaults) {
+            if value != default {
+                header.push_str(&format!(":set {name} {value}\n"));
+            }
+        }
+        let mut file = std::fs::File::create(path)?;
+        file.write_all(header.as_bytes())?;
+        self.transcript = Some(Transcript { path: path.to_string(), file, after_code: false });
+        // Input typed before recording started belongs to no recorded entry
+        if let Ok(mut prompted) = self.prompted_input.lock() {
+            prompted.clear();
+        }
+        Ok(())
+    }
+
+    /// Append a handled submission to the transcript, if one is open. Input typed at the
+    /// prompt while it ran is written first as `:input`, so the replay does not wait for stdin.
+    fn record(&mut self, entry: &str, is_code: bool) {
+        let typed = self.prompted_input.lock().map(|mut prompted| std::mem::take(&mut *prompted)).unwrap_or_default();
+        let Some(transcript) = self.transcript.as_mut() else {
+            return;
+        };
+        let mut text = String::new();
+        if !typed.is_empty() {
+            text.push_str(&format!(":input {}\n", quote_input(&typed)));
+        } else if is_code && transcript.after_code {
+            text.push_str(":#\n");
+        }
+        if is_code {
+            // A code line that looks like a meta-command would be replayed as one; keep only its instructions
+            for line in entry.lines() {
+                if line.trim_start().starts_with(':') {
+                    text.push_str(&bf_only(line));
+                } else {
+                    text.push_str(line);
+                }
+                text.push('\n');
+            }
+        } else {
+            text.push_str(entry.trim());
+            text.push('\n');
+        }
+        transcript.after_code = is_code;
+        if let Err(e) = transcript.file.write_all(text.as_bytes()) {
+            eprintln!("repl: failed to write {}: {e}; recording stopped", transcript.path);
+            let _ = io::stderr().flush();
+            self.transcript = None;
+        }
+    }
+
+    /// Record a meta-command line after it was handled. `:record`/`:stop-record` are left out,
+    /// and `:load` becomes a comment: the edited buffer is recorded when it is submitted.
+    fn record_meta(&mut self, line: &str, cmd: &MetaCommand) {
+        match cmd {
+            MetaCommand::Record(_) | MetaCommand::StopRecord => {}
+            MetaCommand::Load(_) => self.record(&format!(":# {}", line.trim().trim_start_matches(':')), false),
+            _ => self.record(line, false),
+        }
+    }
+}
+
+pub fn repl_loop(options: ReplOptions) -> io::Result<()> {
     // Initialize interactive line editor
-    let mut editor = init_line_editor()?;
+    let mut editor = init_line_editor(&options)?;
 
     // Track the "current editing buffer" across prompts for `:dump`
     let mut current_buffer: String = String::new();
 
+    let mut session = ReplSession::new(options.persist, options.settings);
+
     loop {
         // Prompt and read a multi-line submission via editor
         let submission = read_submission_interactive(&mut editor)?;
@@ -30,13 +465,17 @@ pub fn repl_loop() -> io::Result<()> {
 
         // Meta-command recognition: line starting with `:`
         if let Some(meta) = parse_meta_command(&submission) {
-            match handle_meta_command(&mut editor, &meta, &current_buffer)? {
+            let action = handle_meta_command(Some(&mut editor), &meta, &current_buffer, &mut session)?;
+            session.record_meta(&submission, &meta);
+            match action {
                 MetaAction::Exit => return Ok(()),
                 MetaAction::Continue => {},
                 MetaAction::ResetState => {
                     // Clear any pending state we keep in the loop; editor buffer will be fresh next prompt
                     current_buffer = String::new();
+                    session.reset();
                 }
+                MetaAction::Loaded(code) => current_buffer = code,
             }
             continue; // Do not execute or add to history
         }
@@ -55,7 +494,8 @@ pub fn repl_loop() -> io::Result<()> {
         }
 
         // Execute the Brainfuck code buffer
-        execute_bf_buffer(filtered);
+        execute_bf_buffer(filtered, &mut session);
+        session.record(&submission, true);
 
         // Test hook: if BF_REPL_ONCE=1, exit after one execution
         if env::var("BF_REPL_ONCE").ok().as_deref() == Some("1") {
@@ -64,50 +504,68 @@ pub fn repl_loop() -> io::Result<()> {
     }
 }
 
-fn init_line_editor() -> io::Result<reedline::Reedline> {
+fn init_line_editor(options: &ReplOptions) -> io::Result<reedline::Reedline> {
     use reedline::{
-        default_emacs_keybindings, EditCommand, Emacs, KeyCode, KeyModifiers, Reedline, ReedlineEvent,
+        default_emacs_keybindings, default_vi_insert_keybindings, default_vi_normal_keybindings, ColumnarMenu,
+        EditMode, Emacs, FileBackedHistory, MenuBuilder, Reedline, ReedlineMenu, Vi,
     };
 
-    // Start from default emacs-like bindings and adjust:
-    // - Enter -> InsertNewLine (do not submit)
-    // - Ctrl+D -> AcceptLine (submit)
-    // - Ctrl+Z -> AcceptLine (submit, for Windows)
-    let mut keybindings = default_emacs_keybindings();
-    keybindings.add_binding(KeyModifiers::NONE, KeyCode::Enter, ReedlineEvent::Edit(vec![EditCommand::InsertNewline]));
-    keybindings.add_binding(KeyModifiers::CONTROL, KeyCode::Char('d'), ReedlineEvent::Submit);
-    keybindings.add_binding(KeyModifiers::CONTROL, KeyCode::Char('z'), ReedlineEvent::Submit);
-    
-    // Default edit-mode navigation.
-    // Up/down move within the current multiline buffer, not history.
-    keybindings.add_binding(
-        KeyModifiers::NONE,
-        KeyCode::Up,
-        ReedlineEvent::Up
-    );
-    keybindings.add_binding(
-        KeyModifiers::NONE,
-        KeyCode::Down,
-        ReedlineEvent::Down
-    );
-    
-    // Explicit history-mode convenience bindings
-    // Alt+Up/Alt+Down or Ctrl+Up/Ctrl+Down to navigate history items.
-    keybindings.add_binding(KeyModifiers::ALT, KeyCode::Up, ReedlineEvent::PreviousHistory);
-    keybindings.add_binding(KeyModifiers::CONTROL, KeyCode::Up, ReedlineEvent::PreviousHistory);
-    keybindings.add_binding(KeyModifiers::ALT, KeyCode::Down, ReedlineEvent::NextHistory);
-    keybindings.add_binding(KeyModifiers::CONTROL, KeyCode::Down, ReedlineEvent::NextHistory);
+    let edit_mode: Box<dyn EditMode> = if options.vi_mode {
+        let mut insert = default_vi_insert_keybindings();
+        let mut normal = default_vi_normal_keybindings();
+        add_repl_bindings(&mut insert, &options.keys);
+        add_repl_bindings(&mut normal, &options.keys);
+        Box::new(Vi::new(insert, normal))
+    } else {
+        let mut keybindings = default_emacs_keybindings();
+        add_repl_bindings(&mut keybindings, &options.keys);
+        Box::new(Emacs::new(keybindings))
+    };
+    let completion_menu = ColumnarMenu::default().with_name("completion_menu");
 
-    let history = reedline::FileBackedHistory::new(1_000).unwrap();
+    let history_options = &options.history;
+    // Persist history to a file when configured; fall back to in-memory history if it cannot be opened
+    let size = history_options.size;
+    let history = match &history_options.file {
+        Some(path) => FileBackedHistory::with_file(size, path.clone()).unwrap_or_else(|e| {
+            eprintln!("repl: history file {} unavailable ({e}); history will not be saved", path.display());
+            FileBackedHistory::new(size).expect("history capacity is valid")
+        }),
+        None => FileBackedHistory::new(size).expect("history capacity is valid"),
+    };
 
     let editor = Reedline::create()
         .with_highlighter(Box::new(BrainfuckHighlighter::new_from_config()))
         .with_history(Box::new(history))
-        .with_edit_mode(Box::new(Emacs::new(keybindings)));
+        // Programs are recorded (newlines intact); meta commands are not
+        .with_history_exclusion_prefix(Some(":".to_string()))
+        .with_completer(Box::new(ReplCompleter))
+        .with_hinter(Box::new(ReplHinter::default()))
+        .with_validator(Box::new(BracketValidator))
+        .with_menu(ReedlineMenu::EngineCompleter(Box::new(completion_menu)))
+        .with_edit_mode(edit_mode);
 
     Ok(editor)
 }
 
+/// Bindings shared by Emacs mode and both Vi modes, on top of reedline's defaults:
+/// - Up/Down move within the current multiline buffer, not history
+/// - Tab opens the completion menu (meta-commands, their arguments and file paths) or cycles through it
+/// - Remappable actions from [repl.keys]; by default Enter inserts a newline, Ctrl+D/Ctrl+Z
+///   (Windows) submit, and Alt+Up/Down or Ctrl+Up/Down navigate history. Added last so
+///   remapped keys win over the bindings above.
+fn add_repl_bindings(keybindings: &mut reedline::Keybindings, keys: &ReplKeys) {
+    use reedline::ReedlineEvent;
+    keybindings.add_binding(KeyModifiers::NONE, KeyCode::Up, ReedlineEvent::Up);
+    keybindings.add_binding(KeyModifiers::NONE, KeyCode::Down, ReedlineEvent::Down);
+    keybindings.add_binding(
+        KeyModifiers::NONE,
+        KeyCode::Tab,
+        ReedlineEvent::UntilFound(vec![ReedlineEvent::Menu("completion_menu".to_string()), ReedlineEvent::MenuNext]),
+    );
+    keys.add_to(keybindings);
+}
+
 pub fn read_submission<R: io::BufRead>(stdin: &mut R) -> Option<String> {
     // Collect all lines until EOF
     let mut buffer = String::new();
@@ -150,6 +608,10 @@ fn read_submission_interactive(editor: &mut reedline::Reedline) -> io::Result<Op
             if !buffer.trim().is_empty() && !buffer.trim_start().starts_with(':') {
                 let _ = editor.history_mut().save(HistoryItem::from_command_line(buffer.clone()));
             }
+            // Write through right away: Ctrl+C exits the process without unwinding
+            if let Err(e) = editor.sync_history() {
+                eprintln!("repl: failed to save history: {e}");
+            }
             Ok(Some(buffer))
         }
         Ok(Signal::CtrlC) => Ok(None), // Global SIGINT, exit immediately
@@ -170,53 +632,241 @@ fn read_submission_interactive(editor: &mut reedline::Reedline) -> io::Result<Op
 /// - Errors are printed concisely to stderr.
 /// - A newline is always written to stdout after execution (success or error)
 ///   so that the prompt begins at column 0 on the next iteration.
-fn execute_bf_buffer(buffer: String) {
-    // Limits from environment variables
-    let timeout_ms = env::var("BF_TIMEOUT_MS").ok().and_then(|s| s.parse::<usize>().ok()).unwrap_or(2_000);
-    let max_steps = env::var("BF_MAX_STEPS").ok().and_then(|s| s.parse::<usize>().ok());
+/// - With a persistent session, the run starts from the session's tape and pointer.
+///   Either way, the final state (even after an error or timeout) and run statistics
+///   are stored in the session for `:tape`, `:ptr` and `:stats`.
+fn execute_bf_buffer(buffer: String, session: &mut ReplSession) {
+    let ReplSettings { timeout_ms, max_steps, memory: tape_size, eof } = session.settings.clone();
 
     // Cooperative cancellation flag
     let cancel_flag = Arc::new(AtomicBool::new(false));
-    let (tx, rx) = mpsc::channel::<Result<(), BrainfuckReaderError>>();
+    let (tx, rx) = mpsc::channel::<(Result<(), BrainfuckReaderError>, RunState)>();
     let program = buffer.clone();
     let cancel_flag_clone = cancel_flag.clone();
+    let persist = session.persist;
+    let memory = if persist { std::mem::take(&mut session.memory) } else { vec![0; tape_size] };
+    let pointer = if persist { session.pointer } else { 0 };
+    let pending_input = session.pending_input.clone();
+    let prompted_input = session.prompted_input.clone();
+    let stdin_input = session.stdin_input;
+    // Set while the worker waits at the inline input prompt; that time does not count toward the timeout
+    let waiting_for_input = Arc::new(AtomicBool::new(false));
+    let waiting_clone = waiting_for_input.clone();
+    let output_len = Arc::new(AtomicUsize::new(0));
+    let output_len_clone = output_len.clone();
+    let started = Instant::now();
 
     thread::spawn(move || {
         let mut bf = BrainfuckReader::new(program);
+        bf.set_state(memory, pointer);
+        bf.set_eof_behavior(eof);
+        bf.set_input_provider(move || {
+            if stdin_input {
+                next_input_byte(&pending_input, &prompted_input, &waiting_clone)
+            } else {
+                pending_input.lock().ok()?.pop_front()
+            }
+        });
+        // Same bytes as the reader's default stdout output, but counted for `:stats`
+        bf.set_output_sink(move |bytes| {
+            for b in bytes {
+                print!("{}", *b as char);
+            }
+            output_len_clone.fetch_add(bytes.len(), Ordering::Relaxed);
+        });
         let ctrl = StepControl::new(max_steps, cancel_flag_clone);
         // Run with cooperative cancellation
         let res = bf.run_with_control(ctrl);
-        let _ = tx.send(res);
+        let steps = bf.steps();
+        let (memory, pointer) = bf.into_state();
+        let _ = tx.send((res, RunState { memory, pointer, steps }));
     });
 
-    let timeout = Duration::from_millis(timeout_ms as u64);
-    match rx.recv_timeout(timeout) {
-        Ok(Ok(())) => { } // Success
-        Ok(Err(BrainfuckReaderError::StepLimitExceeded { limit })) => {
-            eprintln!("Execution aborted: step limit exceeded ({limit})");
-            let _ = io::stderr().flush();
-        }
-        Ok(Err(BrainfuckReaderError::Canceled)) => {
-            eprintln!("Execution aborted: wall-clock timeout ({timeout_ms} ms)");
-            let _ = io::stderr().flush();
-        }
-        Ok(Err(other)) => {
-            cli_util::print_reader_error(None, &buffer, &other);
-            let _ = io::stderr().flush();
+    let received = match timeout_ms {
+        Some(ms) => wait_for_worker(&rx, Duration::from_millis(ms), &waiting_for_input),
+        None => rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected),
+    };
+    let (final_state, outcome) = match received {
+        Ok((res, state)) => {
+            let outcome = match res {
+                Ok(()) => "ok".to_string(), // Success
+                Err(BrainfuckReaderError::StepLimitExceeded { limit }) => {
+                    eprintln!("Execution aborted: step limit exceeded ({limit})");
+                    let _ = io::stderr().flush();
+                    "step limit exceeded".to_string()
+                }
+                Err(BrainfuckReaderError::Canceled) => {
+                    eprintln!("Execution aborted: wall-clock timeout ({} ms)", timeout_ms.unwrap_or_default());
+                    let _ = io::stderr().flush();
+                    "timeout".to_string()
+                }
+                Err(other) => {
+                    cli_util::print_reader_error(None, &buffer, &other);
+                    let _ = io::stderr().flush();
+                    "error".to_string()
+                }
+            };
+            (Some(state), outcome)
         }
         Err(mpsc::RecvTimeoutError::Timeout) => {
             // Signal cancel and inform the user
             cancel_flag.store(true, Ordering::Relaxed);
-            eprintln!("Execution aborted: wall-clock timeout ({} ms)", timeout_ms);
+            eprintln!("Execution aborted: wall-clock timeout ({} ms)", timeout_ms.unwrap_or_default());
             let _ = io::stderr().flush();
+            // The worker stops at its next step; give it a moment to hand back the tape
+            let state = rx.recv_timeout(Duration::from_millis(250)).ok().map(|(_, state)| state);
+            (state, "timeout".to_string())
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => (None, "error".to_string()), // Worker ended unexpectedly; nothing to add
+    };
+
+    if !persist && let Ok(mut pending) = session.pending_input.lock() {
+        // Unread input only carries over to the next run in a persistent session
+        pending.clear();
+    }
+
+    let elapsed = started.elapsed();
+    match final_state {
+        Some(RunState { memory, pointer, steps }) => {
+            session.memory = memory;
+            session.pointer = pointer;
+            session.last_run = Some(RunStats { steps: Some(steps), elapsed, output_len: output_len.load(Ordering::Relaxed), outcome });
+        }
+        None => {
+            if persist {
+                // e.g. the worker is still blocked reading stdin
+                eprintln!("Tape state could not be recovered; session tape reset");
+                let _ = io::stderr().flush();
+            }
+            session.reset();
+            session.last_run = Some(RunStats { steps: None, elapsed, output_len: output_len.load(Ordering::Relaxed), outcome });
         }
-        Err(mpsc::RecvTimeoutError::Disconnected) => {} // Worker ended unexpectedly; nothing to add
     }
 
     println!();
     let _ = io::stdout().flush(); // Ensure output is flushed
 }
 
+/// Tape, pointer and step count handed back by the execution thread.
+struct RunState {
+    memory: Vec<u8>,
+    pointer: usize,
+    steps: usize,
+}
+
+/// Statistics about the most recent execution, shown by `:stats`.
+#[derive(Debug, Clone)]
+struct RunStats {
+    /// `None` when the worker could not report back (e.g. blocked on input after a timeout)
+    steps: Option<usize>,
+    elapsed: Duration,
+    output_len: usize,
+    outcome: String,
+}
+
+/// Wait for the worker's result, counting only time it spends running (not time blocked
+/// at the inline input prompt) toward `timeout`.
+fn wait_for_worker<T>(rx: &mpsc::Receiver<T>, timeout: Duration, waiting_for_input: &AtomicBool) -> Result<T, mpsc::RecvTimeoutError> {
+    const TICK: Duration = Duration::from_millis(20);
+    let mut running = Duration::ZERO;
+    loop {
+        let tick_started = Instant::now();
+        match rx.recv_timeout(TICK.min(timeout.saturating_sub(running)).max(Duration::from_millis(1))) {
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if !waiting_for_input.load(Ordering::Relaxed) {
+                    running += tick_started.elapsed();
+                }
+                if running >= timeout {
+                    return Err(mpsc::RecvTimeoutError::Timeout);
+                }
+            }
+            other => return other,
+        }
+    }
+}
+
+/// Input provider for `,`: serve bytes queued by `:input`/`:input-file` (or left over
+/// from an earlier prompt) first. When the queue is empty, read another line from stdin,
+/// with an `input> ` prompt on a terminal; the line is also kept in `prompted_input` for
+/// `:record`. Returns `None` at EOF.
+fn next_input_byte(pending_input: &Mutex<VecDeque<u8>>, prompted_input: &Mutex<Vec<u8>>, waiting_for_input: &AtomicBool) -> Option<u8> {
+    let mut pending = pending_input.lock().ok()?;
+    if pending.is_empty() {
+        // Show program output so far before prompting
+        let _ = io::stdout().flush();
+        if io::stdin().is_terminal() && io::stderr().is_terminal() {
+            eprint!("input> ");
+            let _ = io::stderr().flush();
+        }
+        waiting_for_input.store(true, Ordering::Relaxed);
+        let mut line = String::new();
+        let read = io::stdin().read_line(&mut line);
+        waiting_for_input.store(false, Ordering::Relaxed);
+        match read {
+            Ok(0) | Err(_) => return None, // EOF
+            Ok(_) => {
+                if let Ok(mut prompted) = prompted_input.lock() {
+                    prompted.extend(line.bytes());
+                }
+                pending.extend(line.bytes());
+            }
+        }
+    }
+    pending.pop_front()
+}
+
+/// Parse the argument of `:input`: a double-quoted string with escapes (`\n`, `\t`, `\r`,
+/// `\0`, `\\`, `\"`, `\xNN`), or the rest of the line taken literally.
+fn parse_input_text(arg: &str) -> Result<Vec<u8>, String> {
+    let Some(inner) = arg.strip_prefix('"') else {
+        return Ok(arg.as_bytes().to_vec());
+    };
+    let inner = inner.strip_suffix('"').ok_or("missing closing quote")?;
+    let mut bytes = Vec::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => bytes.push(b'\n'),
+            Some('t') => bytes.push(b'\t'),
+            Some('r') => bytes.push(b'\r'),
+            Some('0') => bytes.push(0),
+            Some('\\') => bytes.push(b'\\'),
+            Some('"') => bytes.push(b'"'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16).map_err(|_| format!("invalid escape \\x{hex}"))?;
+                bytes.push(byte);
+            }
+            Some(other) => return Err(format!("invalid escape \\{other}")),
+            None => return Err("trailing backslash".to_string()),
+        }
+    }
+    Ok(bytes)
+}
+
+/// Quote bytes as an `:input` argument that [`parse_input_text`] reads back unchanged.
+fn quote_input(bytes: &[u8]) -> String {
+    let mut quoted = String::from("\"");
+    for &b in bytes {
+        match b {
+            b'\n' => quoted.push_str("\\n"),
+            b'\t' => quoted.push_str("\\t"),
+            b'\r' => quoted.push_str("\\r"),
+            b'\\' => quoted.push_str("\\\\"),
+            b'"' => quoted.push_str("\\\""),
+            0x20..=0x7e => quoted.push(b as char),
+            _ => quoted.push_str(&format!("\\x{b:02x}")),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ReplMode {
     Bare,
@@ -266,21 +916,58 @@ pub fn select_mode(flag: ModeFlagOverride) -> Result<ReplMode, String> {
     }
 }
 
-pub fn execute_bare_once() -> io::Result<()> {
-    let mut locked = io::BufReader::new(io::stdin().lock());
-    let submission = read_submission(&mut locked);
+pub fn execute_bare_once(options: ReplOptions) -> io::Result<()> {
+    // Release the stdin lock before running: `,` reads stdin from the worker thread
+    let submission = read_submission(&mut io::BufReader::new(io::stdin().lock()));
     if let Some(s) = submission {
-        let trimmed = s.trim();
-        if !trimmed.is_empty() {
-            let filtered = bf_only(trimmed);
-            if !filtered.is_empty() {
-                execute_bf_buffer(filtered);
-            }
+        run_bare_script(&s, &mut ReplSession::new(options.persist, options.settings))?;
+    }
+    Ok(())
+}
+
+/// Replay a transcript written by `:record` (or any script in the same format) without
+/// reading it from stdin, so `,` can still read stdin once queued input runs out.
+pub fn execute_script(path: &Path, options: ReplOptions) -> io::Result<()> {
+    let script = std::fs::read_to_string(path)?;
+    run_bare_script(&script, &mut ReplSession::new(options.persist, options.settings))
+}
+
+/// Run a bare-mode submission. Lines starting with `:` are meta commands; the code
+/// between them is executed as one program before the next meta command runs.
+fn run_bare_script(script: &str, session: &mut ReplSession) -> io::Result<()> {
+    let mut pending = String::new();
+    for line in script.split_inclusive('\n') {
+        let Some(meta) = parse_meta_command(line) else {
+            pending.push_str(line);
+            continue;
+        };
+        run_bare_code(&pending, session);
+        let action = handle_meta_command(None, &meta, &pending, session)?;
+        session.record_meta(line, &meta);
+        pending.clear();
+        match action {
+            MetaAction::Exit => return Ok(()),
+            MetaAction::Continue => {}
+            MetaAction::ResetState => session.reset(),
+            // Loaded code runs with whatever follows it, before the next meta command
+            MetaAction::Loaded(code) => pending = code,
         }
     }
+    run_bare_code(&pending, session);
     Ok(())
 }
 
+fn run_bare_code(code: &str, session: &mut ReplSession) {
+    let trimmed = code.trim();
+    if !trimmed.is_empty() {
+        let filtered = bf_only(trimmed);
+        if !filtered.is_empty() {
+            execute_bf_buffer(filtered, session);
+            session.record(code, true);
+        }
+    }
+}
+
 #[derive(Default)]
 struct BrainfuckHighlighter {
     // Per-char styles for BF commands, and a fallback for non-commands
@@ -293,6 +980,8 @@ struct BrainfuckHighlighter {
     map_lbracket: Style,
     map_rbracket: Style,
     map_other: Style,
+    map_unmatched: Style,
+    map_match: Style,
 }
 
 impl BrainfuckHighlighter {
@@ -311,6 +1000,8 @@ impl BrainfuckHighlighter {
         s.map_lbracket = Style::new().fg(rat_to_nu(cfg.editor_op_bracket)).bold();
         s.map_rbracket = Style::new().fg(rat_to_nu(cfg.editor_op_bracket)).bold();
         s.map_other = Style::new().fg(rat_to_nu(cfg.editor_non_bf)).bold();
+        s.map_unmatched = Style::new().fg(rat_to_nu(cfg.editor_bracket_unmatched)).bold().underline();
+        s.map_match = s.map_lbracket.on(rat_to_nu(cfg.editor_bracket_match));
         s
     }
 
@@ -331,13 +1022,23 @@ impl BrainfuckHighlighter {
 }
 
 impl Highlighter for BrainfuckHighlighter {
-    fn highlight(&self, line: &str, _cursor: usize) -> StyledText {
+    fn highlight(&self, line: &str, cursor: usize) -> StyledText {
         let mut out: StyledText = StyledText::new();
         let mut current_style: Option<Style> = None;
         let mut buffer = String::new();
 
-        for ch in line.chars() {
-            let style = self.style_for(ch);
+        // Meta-commands are not code, so their brackets are not checked
+        let brackets = if line.trim_start().starts_with(':') { BracketMap::default() } else { BracketMap::new(line) };
+        let pair = brackets.pair_at_cursor(line, cursor);
+
+        for (i, ch) in line.char_indices() {
+            let style = if brackets.unmatched.contains(&i) {
+                self.map_unmatched
+            } else if pair.is_some_and(|(a, b)| i == a || i == b) {
+                self.map_match
+            } else {
+                self.style_for(ch)
+            };
 
             match current_style {
                 None => {
@@ -364,26 +1065,437 @@ impl Highlighter for BrainfuckHighlighter {
     }
 }
 
+/// Loop structure of a buffer, by byte offset.
+#[derive(Debug, Default)]
+struct BracketMap {
+    /// Each bracket's partner, in both directions
+    partners: HashMap<usize, usize>,
+    /// Brackets without a partner, in buffer order
+    unmatched: Vec<usize>,
+}
+
+impl BracketMap {
+    fn new(text: &str) -> Self {
+        let mut map = Self::default();
+        let mut open: Vec<usize> = Vec::new();
+        for (i, ch) in text.char_indices() {
+            match ch {
+                '[' => open.push(i),
+                ']' => match open.pop() {
+                    Some(j) => {
+                        map.partners.insert(i, j);
+                        map.partners.insert(j, i);
+                    }
+                    None => map.unmatched.push(i),
+                },
+                _ => {}
+            }
+        }
+        map.unmatched.extend(open);
+        map.unmatched.sort_unstable();
+        map
+    }
+
+    /// The bracket at the cursor (or just before it) and its partner, if both exist.
+    fn pair_at_cursor(&self, text: &str, cursor: usize) -> Option<(usize, usize)> {
+        let bytes = text.as_bytes();
+        let is_bracket = |i: usize| matches!(bytes.get(i), Some(b'[' | b']'));
+        let at = if is_bracket(cursor) {
+            cursor
+        } else if cursor > 0 && is_bracket(cursor - 1) {
+            cursor - 1
+        } else {
+            return None;
+        };
+        self.partners.get(&at).map(|partner| (at, *partner))
+    }
+
+    /// Describe the first unmatched bracket with its 1-based line and column.
+    fn first_problem(&self, text: &str) -> Option<String> {
+        let at = *self.unmatched.first()?;
+        let before = &text[..at];
+        let line = before.matches('\n').count() + 1;
+        let column = before[before.rfind('\n').map_or(0, |i| i + 1)..].chars().count() + 1;
+        Some(format!("unmatched '{}' at line {line}, column {column}", &text[at..at + 1]))
+    }
+}
+
+/// Keeps Ctrl+D from submitting code with unbalanced brackets; reedline inserts a
+/// newline instead, and [`ReplHinter`] shows the problem.
+struct BracketValidator;
+
+impl Validator for BracketValidator {
+    fn validate(&self, line: &str) -> ValidationResult {
+        if line.trim_start().starts_with(':') || BracketMap::new(line).unmatched.is_empty() {
+            ValidationResult::Complete
+        } else {
+            ValidationResult::Incomplete
+        }
+    }
+}
+
+/// Completes meta-command names, their arguments and flags, and file paths after
+/// commands that take one (`:load`, `:run`, ...).
+struct ReplCompleter;
+
+impl Completer for ReplCompleter {
+    fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
+        let before = &line[..pos];
+        let current_line = &before[before.rfind('\n').map_or(0, |i| i + 1)..];
+        let trimmed = current_line.trim_start();
+        if !trimmed.starts_with(':') {
+            return Vec::new();
+        }
+
+        // Still typing the command name
+        let Some((head, args)) = trimmed.split_once(char::is_whitespace) else {
+            return META_COMMANDS
+                .iter()
+                .filter(|spec| spec.name.starts_with(trimmed))
+                .map(|spec| Suggestion {
+                    value: spec.name.to_string(),
+                    description: Some(spec.summary.to_string()),
+                    span: Span::new(pos - trimmed.len(), pos),
+                    append_whitespace: !spec.usage.is_empty(),
+                    ..Suggestion::default()
+                })
+                .collect();
+        };
+
+        let Some(spec) = find_meta(head) else {
+            return Vec::new();
+        };
+        let args = args.trim_start();
+        let words: &[&str] = match spec.args {
+            ArgCompletion::None => return Vec::new(),
+            ArgCompletion::Path => return complete_path(args, pos - args.len(), pos),
+            // Only the first argument has fixed choices
+            ArgCompletion::First(_) if args.contains(char::is_whitespace) => return Vec::new(),
+            ArgCompletion::First(words) | ArgCompletion::Flags(words) => words,
+        };
+        let fragment = &args[args.rfind(char::is_whitespace).map_or(0, |i| i + 1)..];
+        let used: Vec<&str> = args.split_whitespace().collect();
+        words
+            .iter()
+            .filter(|word| word.starts_with(fragment) && !used.contains(word))
+            .map(|word| Suggestion {
+                value: word.to_string(),
+                span: Span::new(pos - fragment.len(), pos),
+                append_whitespace: true,
+                ..Suggestion::default()
+            })
+            .collect()
+    }
+}
+
+/// Inline hints at the end of the buffer:
+/// - for a partly typed meta-command, the rest of its name, then its arguments (only the
+///   name part is inserted when the hint is accepted);
+/// - for code with unbalanced brackets, the first unmatched bracket.
+#[derive(Default)]
+struct ReplHinter {
+    completion: String,
+}
+
+impl Hinter for ReplHinter {
+    fn handle(&mut self, line: &str, pos: usize, _history: &dyn History, use_ansi_coloring: bool, _cwd: &str) -> String {
+        self.completion.clear();
+        let typed = line.trim_start();
+        if pos != line.len() {
+            return String::new();
+        }
+        if !typed.starts_with(':') {
+            let Some(problem) = BracketMap::new(line).first_problem(line) else {
+                return String::new();
+            };
+            let hint = format!("  ({problem})");
+            return if use_ansi_coloring {
+                Style::new().fg(rat_to_nu(crate::config::colors().editor_bracket_unmatched)).paint(hint).to_string()
+            } else {
+                hint
+            };
+        }
+        if typed.contains(char::is_whitespace) {
+            return String::new();
+        }
+        let mut matches = META_COMMANDS.iter().filter(|spec| spec.name.starts_with(typed));
+        let (Some(spec), None) = (matches.next(), matches.next()) else {
+            return String::new();
+        };
+
+        self.completion = spec.name[typed.len()..].to_string();
+        let hint = if spec.usage.is_empty() {
+            self.completion.clone()
+        } else {
+            format!("{} {}", self.completion, spec.usage)
+        };
+        if use_ansi_coloring {
+            Style::new().fg(nu_ansi_term::Color::DarkGray).paint(hint).to_string()
+        } else {
+            hint
+        }
+    }
+
+    fn complete_hint(&self) -> String {
+        self.completion.clone()
+    }
+
+    fn next_hint_token(&self) -> String {
+        self.completion.clone()
+    }
+}
+
+/// Suggest directory entries matching the last component of `fragment`, replacing
+/// `start..end` in the buffer. Directories get a trailing `/` so completion can continue.
+fn complete_path(fragment: &str, start: usize, end: usize) -> Vec<Suggestion> {
+    let (dir_part, name_part) = match fragment.rfind('/') {
+        Some(i) => (&fragment[..=i], &fragment[i + 1..]),
+        None => ("", fragment),
+    };
+    let dir = if dir_part.is_empty() { ".".to_string() } else { expand_home(dir_part) };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut suggestions: Vec<Suggestion> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            // Hidden entries only when asked for explicitly
+            if !name.starts_with(name_part) || (name.starts_with('.') && !name_part.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            Some(Suggestion {
+                value: format!("{dir_part}{name}{}", if is_dir { "/" } else { "" }),
+                span: Span::new(start, end),
+                append_whitespace: false,
+                ..Suggestion::default()
+            })
+        })
+        .collect();
+    suggestions.sort_by(|a, b| a.value.cmp(&b.value));
+    suggestions
+}
+
+/// How the arguments of a meta-command are completed.
+#[derive(Debug, Clone, Copy)]
+enum ArgCompletion {
+    None,
+    /// The rest of the line is a file path
+    Path,
+    /// Fixed choices for the first argument
+    First(&'static [&'static str]),
+    /// Flags, each offered until it has been used
+    Flags(&'static [&'static str]),
+}
+
+/// One entry in the meta-command registry, shared by the parser, the completer,
+/// the hinter and `:help`.
+struct MetaSpec {
+    name: &'static str,
+    aliases: &'static [&'static str],
+    /// Argument synopsis for help and hints, e.g. `[-n|--stderr]`
+    usage: &'static str,
+    summary: &'static str,
+    args: ArgCompletion,
+    /// Builds the command from the rest of the line (trimmed)
+    parse: fn(&str) -> MetaCommand,
+}
+
+/// All meta-commands, in `:help` order.
+const META_COMMANDS: &[MetaSpec] = &[
+    MetaSpec { name: ":help", aliases: &[], usage: "", summary: "Show this help", args: ArgCompletion::None, parse: |_| MetaCommand::Help },
+    MetaSpec { name: ":exit", aliases: &[":quit"], usage: "", summary: "Exit immediately (code 0)", args: ArgCompletion::None, parse: |_| MetaCommand::Exit },
+    MetaSpec { name: ":reset", aliases: &[], usage: "", summary: "Clear the current buffer and the tape (history is kept)", args: ArgCompletion::None, parse: |_| MetaCommand::Reset },
+    MetaSpec {
+        name: ":persist",
+        aliases: &[],
+        usage: "[on|off]",
+        summary: "Keep tape, pointer and pending input between runs",
+        args: ArgCompletion::First(&["on", "off"]),
+        parse: parse_persist,
+    },
+    MetaSpec {
+        name: ":tape",
+        aliases: &[],
+        usage: "[N..M]",
+        summary: "Show tape cells (default: the 16 cells around the pointer)",
+        args: ArgCompletion::None,
+        parse: parse_tape,
+    },
+    MetaSpec { name: ":ptr", aliases: &[], usage: "[N]", summary: "Show the data pointer, or move it to cell N", args: ArgCompletion::None, parse: parse_ptr },
+    MetaSpec {
+        name: ":set",
+        aliases: &[],
+        usage: "<cell|setting> <value>",
+        summary: "Write a cell (0-255, 0xNN or 'c'), or change timeout, max-steps, memory or eof",
+        args: ArgCompletion::First(&ReplSettings::NAMES),
+        parse: parse_set,
+    },
+    MetaSpec {
+        name: ":show",
+        aliases: &[],
+        usage: "settings",
+        summary: "Show the current settings",
+        args: ArgCompletion::First(&["settings"]),
+        parse: parse_show,
+    },
+    MetaSpec {
+        name: ":save-settings",
+        aliases: &[],
+        usage: "",
+        summary: "Save the current settings to the config file",
+        args: ArgCompletion::None,
+        parse: |_| MetaCommand::SaveSettings,
+    },
+    MetaSpec {
+        name: ":input",
+        aliases: &[],
+        usage: "[\"text\"|clear]",
+        summary: "Queue input for ',' (escapes: \\n \\t \\xNN), show the queue, or drop it",
+        args: ArgCompletion::First(&["clear"]),
+        parse: parse_input,
+    },
+    MetaSpec {
+        name: ":input-file",
+        aliases: &[],
+        usage: "<path>",
+        summary: "Queue a file's bytes as input for ','",
+        args: ArgCompletion::Path,
+        parse: |path| path_command(path, ":input-file", MetaCommand::InputFile),
+    },
+    MetaSpec {
+        name: ":history",
+        aliases: &[],
+        usage: "[clear]",
+        summary: "List past submissions, or clear them (and the history file)",
+        args: ArgCompletion::First(&["clear"]),
+        parse: parse_history,
+    },
+    MetaSpec {
+        name: ":record",
+        aliases: &[],
+        usage: "<path>",
+        summary: "Record submissions and commands to a transcript (replay: bf repl --script)",
+        args: ArgCompletion::Path,
+        parse: |path| path_command(path, ":record", MetaCommand::Record),
+    },
+    MetaSpec { name: ":stop-record", aliases: &[], usage: "", summary: "Stop recording and close the transcript", args: ArgCompletion::None, parse: |_| MetaCommand::StopRecord },
+    MetaSpec { name: ":#", aliases: &[], usage: "[text]", summary: "Comment; in a script it also ends the code before it", args: ArgCompletion::None, parse: |_| MetaCommand::Comment },
+    MetaSpec { name: ":stats", aliases: &[], usage: "", summary: "Steps, time and output length of the last run", args: ArgCompletion::None, parse: |_| MetaCommand::Stats },
+    MetaSpec { name: ":load", aliases: &[], usage: "<path>", summary: "Load a file into the buffer for editing (bare mode: run it)", args: ArgCompletion::Path, parse: |path| path_command(path, ":load", MetaCommand::Load) },
+    MetaSpec { name: ":run", aliases: &[], usage: "<path>", summary: "Execute a file", args: ArgCompletion::Path, parse: |path| path_command(path, ":run", MetaCommand::Run) },
+    MetaSpec {
+        name: ":save",
+        aliases: &[],
+        usage: "<path>",
+        summary: "Write the current buffer (bare mode: the last code) to a file",
+        args: ArgCompletion::Path,
+        parse: |path| path_command(path, ":save", MetaCommand::Save),
+    },
+    MetaSpec {
+        name: ":dump",
+        aliases: &[],
+        usage: "[-n|--stderr]",
+        summary: "Print the current buffer (content on stdout; with --stderr, all on stderr)",
+        args: ArgCompletion::Flags(&["-n", "--stderr"]),
+        parse: parse_dump,
+    },
+];
+
+fn find_meta(name: &str) -> Option<&'static MetaSpec> {
+    META_COMMANDS.iter().find(|spec| spec.name == name || spec.aliases.contains(&name))
+}
+
+/// Error for an unknown meta-command, suggesting the closest known name.
+fn unknown_meta_message(name: &str) -> String {
+    let closest = META_COMMANDS
+        .iter()
+        .flat_map(|spec| std::iter::once(spec.name).chain(spec.aliases.iter().copied()))
+        .map(|known| (edit_distance(name, known), known))
+        .min();
+    match closest {
+        Some((distance, known)) if distance <= 2 => format!("unknown command '{name}'; did you mean '{known}'?"),
+        _ => format!("unknown command '{name}' (:help lists commands)"),
+    }
+}
+
+/// Levenshtein distance between two strings, by chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            row.push(substitute.min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum MetaCommand {
     /// Exit the REPL immediately with code 0
     Exit,
     /// Show help text
     Help,
-    /// Clear the current editing buffer
+    /// Clear the current editing buffer (and the session tape)
     Reset,
+    /// Show or change whether tape state persists across submissions
+    Persist(Option<bool>),
     /// Print the current editing buffer to stdout or stderr
     Dump {
         with_line_numbers: bool,
         all_to_stderr: bool,
     },
+    /// Print tape cells `start..end` (defaults to the 16-cell row around the pointer)
+    Tape { start: Option<usize>, end: Option<usize> },
+    /// Show the data pointer, or move it to a cell
+    Ptr(Option<usize>),
+    /// Write a value into a tape cell
+    SetCell { cell: usize, value: u8 },
+    /// Change an execution setting (`:set timeout 5000`)
+    SetSetting { name: String, value: String },
+    /// Print the current execution settings
+    ShowSettings,
+    /// Write the current execution settings to the `[repl]` table of `bf.toml`
+    SaveSettings,
+    /// List history entries, or clear them (including the history file)
+    History { clear: bool },
+    /// Queue bytes for `,`; `None` shows the queue
+    Input(Option<Vec<u8>>),
+    /// Queue a file's bytes for `,`
+    InputFile(String),
+    /// Drop queued input
+    InputClear,
+    /// Start recording a transcript to a file
+    Record(String),
+    /// Close the transcript
+    StopRecord,
+    /// A `:#` comment line
+    Comment,
+    /// Show steps, time and output length of the last run
+    Stats,
+    /// Load a file into the editing buffer
+    Load(String),
+    /// Load a file and execute it
+    Run(String),
+    /// Write the current buffer to a file
+    Save(String),
+    /// A known command with bad arguments; the message explains the expected form
+    Invalid(String),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum MetaAction {
     Continue,
     Exit,
     ResetState,
+    /// A file was loaded; it becomes the current buffer
+    Loaded(String),
 }
 
 fn parse_meta_command(input: &str) -> Option<MetaCommand> {
@@ -391,29 +1503,147 @@ fn parse_meta_command(input: &str) -> Option<MetaCommand> {
     if !line.starts_with(':') {
         return None;
     }
-    let mut parts = line.split_whitespace();
-    let head = parts.next().unwrap_or("");
-    match head {
-        ":exit" | ":quit" => Some(MetaCommand::Exit),
-        ":help" => Some(MetaCommand::Help),
-        ":reset" => Some(MetaCommand::Reset),
-        ":dump" => {
-            let mut with_line_numbers = false;
-            let mut all_to_stderr = false;
-            for arg in parts {
-                match arg {
-                    "--line-numbers" | "-n" => with_line_numbers = true,
-                    "--stderr" | "-e" => all_to_stderr = true,
-                    _ => {}
-                }
-            }
-            Some(MetaCommand::Dump { with_line_numbers, all_to_stderr })
+    // `:#` needs no space before the comment text
+    let head = if line.starts_with(":#") { ":#" } else { line.split_whitespace().next().unwrap_or("") };
+    match find_meta(head) {
+        Some(spec) => Some((spec.parse)(line[head.len()..].trim())),
+        None => Some(MetaCommand::Invalid(unknown_meta_message(head))),
+    }
+}
+
+fn parse_persist(arg: &str) -> MetaCommand {
+    match arg {
+        "" => MetaCommand::Persist(None),
+        "on" => MetaCommand::Persist(Some(true)),
+        "off" => MetaCommand::Persist(Some(false)),
+        _ => MetaCommand::Invalid("usage: :persist [on|off]".to_string()),
+    }
+}
+
+fn parse_tape(range: &str) -> MetaCommand {
+    if range.is_empty() {
+        return MetaCommand::Tape { start: None, end: None };
+    }
+    match parse_cell_range(range) {
+        Some((start, end)) => MetaCommand::Tape { start, end },
+        None => MetaCommand::Invalid(format!("invalid range '{range}' (expected N, N..M, N.. or ..M)")),
+    }
+}
+
+fn parse_ptr(arg: &str) -> MetaCommand {
+    if arg.is_empty() {
+        return MetaCommand::Ptr(None);
+    }
+    match arg.parse::<usize>() {
+        Ok(n) => MetaCommand::Ptr(Some(n)),
+        Err(_) => MetaCommand::Invalid(format!("invalid cell index '{arg}'")),
+    }
+}
+
+fn parse_set(args: &str) -> MetaCommand {
+    let usage = "usage: :set <cell> <value> (value: 0-255, 0xNN or 'c') or :set <setting> <value>";
+    let mut parts = args.split_whitespace();
+    let (Some(cell), Some(value)) = (parts.next(), parts.next()) else {
+        return MetaCommand::Invalid(usage.to_string());
+    };
+    // A leading digit means a cell poke; anything else names a setting
+    if !cell.starts_with(|c: char| c.is_ascii_digit()) {
+        return MetaCommand::SetSetting { name: cell.to_string(), value: value.to_string() };
+    }
+    match (cell.parse::<usize>(), parse_cell_value(value)) {
+        (Ok(cell), Some(value)) => MetaCommand::SetCell { cell, value },
+        _ => MetaCommand::Invalid(usage.to_string()),
+    }
+}
+
+fn parse_show(arg: &str) -> MetaCommand {
+    match arg {
+        "settings" => MetaCommand::ShowSettings,
+        _ => MetaCommand::Invalid("usage: :show settings".to_string()),
+    }
+}
+
+fn parse_input(arg: &str) -> MetaCommand {
+    match arg {
+        "" => MetaCommand::Input(None),
+        "clear" => MetaCommand::InputClear,
+        _ => match parse_input_text(arg) {
+            Ok(bytes) => MetaCommand::Input(Some(bytes)),
+            Err(e) => MetaCommand::Invalid(format!("{e} (usage: :input \"text\" | :input clear)")),
+        },
+    }
+}
+
+fn parse_history(arg: &str) -> MetaCommand {
+    match arg {
+        "" => MetaCommand::History { clear: false },
+        "clear" => MetaCommand::History { clear: true },
+        _ => MetaCommand::Invalid("usage: :history [clear]".to_string()),
+    }
+}
+
+/// Commands taking a path: the rest of the line, so paths may contain spaces.
+fn path_command(path: &str, name: &str, command: fn(String) -> MetaCommand) -> MetaCommand {
+    if path.is_empty() {
+        return MetaCommand::Invalid(format!("usage: {name} <path>"));
+    }
+    command(expand_home(path))
+}
+
+fn parse_dump(args: &str) -> MetaCommand {
+    let mut with_line_numbers = false;
+    let mut all_to_stderr = false;
+    for arg in args.split_whitespace() {
+        match arg {
+            "--line-numbers" | "-n" => with_line_numbers = true,
+            "--stderr" | "-e" => all_to_stderr = true,
+            _ => {}
         }
-        _ => Some(MetaCommand::Help),
     }
+    MetaCommand::Dump { with_line_numbers, all_to_stderr }
 }
 
-fn handle_meta_command(editor: &mut reedline::Reedline, cmd: &MetaCommand, current_buffer_snapshot: &str) -> io::Result<MetaAction> {
+/// Parse `N`, `N..M`, `N..` or `..M` into optional bounds (end exclusive).
+fn parse_cell_range(s: &str) -> Option<(Option<usize>, Option<usize>)> {
+    match s.split_once("..") {
+        None => Some((Some(s.parse().ok()?), None)),
+        Some((a, b)) => {
+            let start = if a.is_empty() { None } else { Some(a.parse().ok()?) };
+            let end = if b.is_empty() { None } else { Some(b.parse().ok()?) };
+            Some((start, end))
+        }
+    }
+}
+
+/// Parse a cell value: decimal `0..=255`, hex `0xNN`, or a quoted ASCII char `'c'`.
+fn parse_cell_value(s: &str) -> Option<u8> {
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        return u8::from_str_radix(hex, 16).ok();
+    }
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() == 3 && chars[0] == '\'' && chars[2] == '\'' && chars[1].is_ascii() {
+        return Some(chars[1] as u8);
+    }
+    s.parse().ok()
+}
+
+/// Expand a leading `~/` to the home directory.
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{rest}", home.trim_end_matches('/')),
+        _ => path.to_string(),
+    }
+}
+
+/// Cells shown by `:tape` when no end is given.
+const TAPE_ROW: usize = 16;
+
+fn handle_meta_command(
+    editor: Option<&mut reedline::Reedline>,
+    cmd: &MetaCommand,
+    current_buffer_snapshot: &str,
+    session: &mut ReplSession,
+) -> io::Result<MetaAction> {
     use reedline::EditCommand;
 
     match cmd {
@@ -423,25 +1653,289 @@ fn handle_meta_command(editor: &mut reedline::Reedline, cmd: &MetaCommand, curre
             Ok(MetaAction::Continue)
         }
         MetaCommand::Reset => {
-            let _ = editor.run_edit_commands(&[EditCommand::Clear]);
+            if let Some(editor) = editor {
+                let _ = editor.run_edit_commands(&[EditCommand::Clear]);
+            }
             eprintln!("buffer reset");
             let _ = io::stderr().flush();
             Ok(MetaAction::ResetState)
         }
+        MetaCommand::Persist(setting) => {
+            if let Some(on) = setting {
+                session.persist = *on;
+            }
+            eprintln!("persist: {}", if session.persist { "on" } else { "off" });
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
         MetaCommand::Dump { with_line_numbers, all_to_stderr } => {
             dump_buffer(current_buffer_snapshot, *with_line_numbers, *all_to_stderr)?;
             Ok(MetaAction::Continue)
         }
+        MetaCommand::Tape { start, end } => {
+            let len = session.memory.len();
+            let start = start.unwrap_or(session.pointer - session.pointer % TAPE_ROW).min(len);
+            let end = end.unwrap_or(start + TAPE_ROW).clamp(start, len);
+            write_tape_table(io::stderr(), &session.memory, session.pointer, start, end)?;
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::Ptr(target) => {
+            if let Some(n) = target {
+                if *n >= session.memory.len() {
+                    eprintln!("repl: cell {n} is outside the tape (0..{})", session.memory.len());
+                    let _ = io::stderr().flush();
+                    return Ok(MetaAction::Continue);
+                }
+                session.pointer = *n;
+                warn_if_not_persistent(session);
+            }
+            eprintln!("ptr = {} (cell value {})", session.pointer, session.memory[session.pointer]);
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::SetCell { cell, value } => {
+            if *cell >= session.memory.len() {
+                eprintln!("repl: cell {cell} is outside the tape (0..{})", session.memory.len());
+            } else {
+                session.memory[*cell] = *value;
+                eprintln!("cell {cell} = {value}");
+                warn_if_not_persistent(session);
+            }
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::SetSetting { name, value } => {
+            match session.set(name, value) {
+                Ok(()) => write_settings(io::stderr(), &session.settings)?,
+                Err(e) => eprintln!("repl: {e}"),
+            }
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::ShowSettings => {
+            write_settings(io::stderr(), &session.settings)?;
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::SaveSettings => {
+            match config::write_section("repl", &session.settings.entries()) {
+                Ok(path) => eprintln!("saved settings to {}", path.display()),
+                Err(e) => eprintln!("repl: failed to save settings: {e}"),
+            }
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::Input(bytes) => {
+            if let Ok(mut pending) = session.pending_input.lock() {
+                if let Some(bytes) = bytes {
+                    pending.extend(bytes);
+                }
+                eprintln!("input queue: {} bytes", pending.len());
+            }
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::InputFile(path) => {
+            match std::fs::read(path) {
+                Ok(bytes) => {
+                    if let Ok(mut pending) = session.pending_input.lock() {
+                        pending.extend(&bytes);
+                        eprintln!("queued {path} ({} bytes); input queue: {} bytes", bytes.len(), pending.len());
+                    }
+                }
+                Err(e) => eprintln!("repl: failed to read {path}: {e}"),
+            }
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::InputClear => {
+            if let Ok(mut pending) = session.pending_input.lock() {
+                pending.clear();
+            }
+            eprintln!("input queue cleared");
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::History { clear } => {
+            let Some(editor) = editor else {
+                eprintln!("repl: history is only kept in the interactive editor");
+                let _ = io::stderr().flush();
+                return Ok(MetaAction::Continue);
+            };
+            if *clear {
+                match editor.history_mut().clear() {
+                    Ok(()) => eprintln!("history cleared"),
+                    Err(e) => eprintln!("repl: failed to clear history: {e}"),
+                }
+            } else {
+                let query = SearchQuery::everything(SearchDirection::Forward, None);
+                match editor.history().search(query) {
+                    Ok(items) => {
+                        let entries: Vec<String> = items.into_iter().map(|item| item.command_line).collect();
+                        write_history(io::stderr(), &entries)?;
+                    }
+                    Err(e) => eprintln!("repl: failed to read history: {e}"),
+                }
+            }
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::Record(path) => {
+            if let Some(previous) = &session.transcript {
+                eprintln!("stopped recording to {}", previous.path);
+            }
+            match session.start_recording(path) {
+                Ok(()) => eprintln!("recording to {path} (:stop-record to finish)"),
+                Err(e) => {
+                    session.transcript = None;
+                    eprintln!("repl: failed to create {path}: {e}");
+                }
+            }
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::StopRecord => {
+            match session.transcript.take() {
+                Some(transcript) => eprintln!("stopped recording to {}", transcript.path),
+                None => eprintln!("repl: not recording"),
+            }
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::Comment => Ok(MetaAction::Continue),
+        MetaCommand::Stats => {
+            match &session.last_run {
+                None => eprintln!("no runs yet"),
+                Some(stats) => {
+                    let steps = stats.steps.map_or("unknown".to_string(), |n| n.to_string());
+                    eprintln!(
+                        "last run: {} | steps: {} | time: {:.3} ms | output: {} bytes",
+                        stats.outcome,
+                        steps,
+                        stats.elapsed.as_secs_f64() * 1000.0,
+                        stats.output_len
+                    );
+                }
+            }
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::Load(path) => {
+            let Some(code) = read_code_file(path) else {
+                return Ok(MetaAction::Continue);
+            };
+            if let Some(editor) = editor {
+                // Pre-fill the next prompt with the file so it can be edited before submitting
+                editor.run_edit_commands(&[EditCommand::Clear, EditCommand::InsertString(code.clone())]);
+            }
+            eprintln!("loaded {path} ({} bytes)", code.len());
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Loaded(code))
+        }
+        MetaCommand::Run(path) => {
+            let Some(code) = read_code_file(path) else {
+                return Ok(MetaAction::Continue);
+            };
+            let filtered = bf_only(&code);
+            if !filtered.is_empty() {
+                execute_bf_buffer(filtered, session);
+            }
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::Save(path) => {
+            if current_buffer_snapshot.trim().is_empty() {
+                eprintln!("repl: nothing to save (the buffer is empty)");
+            } else {
+                match std::fs::write(path, current_buffer_snapshot) {
+                    Ok(()) => eprintln!("saved {path} ({} bytes)", current_buffer_snapshot.len()),
+                    Err(e) => eprintln!("repl: failed to write {path}: {e}"),
+                }
+            }
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+        MetaCommand::Invalid(message) => {
+            eprintln!("repl: {message}");
+            let _ = io::stderr().flush();
+            Ok(MetaAction::Continue)
+        }
+    }
+}
+
+/// Read a program file for `:load`/`:run`, reporting failures to stderr.
+fn read_code_file(path: &str) -> Option<String> {
+    match std::fs::read_to_string(path) {
+        Ok(code) => Some(code),
+        Err(e) => {
+            eprintln!("repl: failed to read {path}: {e}");
+            let _ = io::stderr().flush();
+            None
+        }
+    }
+}
+
+fn warn_if_not_persistent(session: &ReplSession) {
+    if !session.persist {
+        eprintln!("note: persist is off, so the next run starts from a fresh tape (:persist on)");
+    }
+}
+
+/// Print cells `start..end` as rows of index, decimal, hex and ASCII; the pointer cell is marked `>`.
+fn write_tape_table<W: Write>(mut w: W, memory: &[u8], pointer: usize, start: usize, end: usize) -> io::Result<()> {
+    writeln!(w, "   cell  dec  hex  ascii")?;
+    for (offset, value) in memory[start..end].iter().enumerate() {
+        let cell = start + offset;
+        let marker = if cell == pointer { '>' } else { ' ' };
+        let ascii = if value.is_ascii_graphic() || *value == b' ' { *value as char } else { '.' };
+        writeln!(w, "{marker} {cell:>6}  {value:>3}  {value:02X}   {ascii}")?;
+    }
+    w.flush()
+}
+
+/// Print numbered history entries; continuation lines of multi-line entries are indented.
+fn write_history<W: Write>(mut w: W, entries: &[String]) -> io::Result<()> {
+    if entries.is_empty() {
+        writeln!(w, "history is empty")?;
+    }
+    for (n, entry) in entries.iter().enumerate() {
+        for (i, line) in entry.trim_end_matches('\n').lines().enumerate() {
+            if i == 0 {
+                writeln!(w, "{:>5}  {line}", n + 1)?;
+            } else {
+                writeln!(w, "       {line}")?;
+            }
+        }
+    }
+    w.flush()
+}
+
+/// Print settings as aligned `name  value` lines.
+fn write_settings<W: Write>(mut w: W, settings: &ReplSettings) -> io::Result<()> {
+    for (name, value) in settings.entries() {
+        let unit = match name {
+            "timeout" if settings.timeout_ms.is_some() => " ms",
+            "memory" => " cells",
+            _ => "",
+        };
+        writeln!(w, "{name:<10} {value}{unit}")?;
+    }
+    w.flush()
+}
+
+/// One line per meta-command (`  :name usage  summary`), for `:help` and `bf repl --help`.
+pub fn meta_help_lines() -> String {
+    let width = META_COMMANDS.iter().map(|spec| spec.name.len() + 1 + spec.usage.len()).max().unwrap_or(0);
+    let mut lines = String::new();
+    for spec in META_COMMANDS {
+        let usage = format!("{} {}", spec.name, spec.usage);
+        lines.push_str(&format!("  {usage:<width$}  {}\n", spec.summary));
     }
+    lines
 }
 
 fn print_meta_help_text() -> io::Result<()> {
     let mut err = io::stderr();
     writeln!(err, "Meta commands:")?;
-    writeln!(err, "  :help                Show this help")?;
-    writeln!(err, "  :exit                Exit immediately (code 0)")?;
-    writeln!(err, "  :reset               Clear the current buffer")?;
-    writeln!(err, "  :dump [-n|--stderr]  Print the current buffer (approx: last executed)")?;
+    write!(err, "{}", meta_help_lines())?;
     writeln!(err)?;
     writeln!(err, "Editing: Enter inserts newline; Ctrl+D (or Ctrl+Z on Windows) submits the buffer")?;
     writeln!(err, "Streams: program output -> stdout; prompts/meta/errors -> stderr")?;
@@ -517,6 +2011,367 @@ mod tests {
         assert_eq!(got.as_deref(), Some("+++\n>+.\n"));
     }
 
+    #[test]
+    fn persistent_session_keeps_tape_between_runs() {
+        let mut session = ReplSession::new(true, ReplSettings::default());
+        execute_bf_buffer("+++>++".to_string(), &mut session);
+        execute_bf_buffer("+<+".to_string(), &mut session);
+        assert_eq!((session.memory[0], session.memory[1], session.pointer), (4, 3, 0));
+
+        session.reset();
+        assert_eq!((session.memory[0], session.pointer), (0, 0));
+
+        // Without persistence each run starts fresh; the last run's tape is kept for inspection
+        let mut fresh = ReplSession::new(false, ReplSettings::default());
+        execute_bf_buffer("+++".to_string(), &mut fresh);
+        execute_bf_buffer("+++".to_string(), &mut fresh);
+        assert_eq!(fresh.memory[0], 3);
+    }
+
+    #[test]
+    fn parse_persist_meta_command() {
+        assert_eq!(parse_meta_command(":persist"), Some(MetaCommand::Persist(None)));
+        assert_eq!(parse_meta_command(":persist on"), Some(MetaCommand::Persist(Some(true))));
+        assert_eq!(parse_meta_command(":persist off"), Some(MetaCommand::Persist(Some(false))));
+    }
+
+    #[test]
+    fn parse_state_meta_commands() {
+        assert_eq!(parse_meta_command(":tape"), Some(MetaCommand::Tape { start: None, end: None }));
+        assert_eq!(parse_meta_command(":tape 4..8"), Some(MetaCommand::Tape { start: Some(4), end: Some(8) }));
+        assert_eq!(parse_meta_command(":tape ..3"), Some(MetaCommand::Tape { start: None, end: Some(3) }));
+        assert_eq!(parse_meta_command(":ptr 7"), Some(MetaCommand::Ptr(Some(7))));
+        assert_eq!(parse_meta_command(":set 2 0x41"), Some(MetaCommand::SetCell { cell: 2, value: 65 }));
+        assert_eq!(parse_meta_command(":set 2 'A'"), Some(MetaCommand::SetCell { cell: 2, value: 65 }));
+        assert_eq!(parse_meta_command(":stats"), Some(MetaCommand::Stats));
+        assert!(matches!(parse_meta_command(":set 2 300"), Some(MetaCommand::Invalid(_))));
+        assert!(matches!(parse_meta_command(":tape x..y"), Some(MetaCommand::Invalid(_))));
+    }
+
+    #[test]
+    fn tape_table_marks_pointer() {
+        let mut out = Vec::new();
+        write_tape_table(&mut out, &[0, 72, 10], 1, 0, 3).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains(">      1   72  48   H"), "{text}");
+        assert!(text.contains("       2   10  0A   ."), "{text}");
+    }
+
+    #[test]
+    fn bare_script_pokes_and_runs_with_persist() {
+        let mut session = ReplSession::new(true, ReplSettings::default());
+        run_bare_script("+++\n:set 1 7\n:ptr 1\n+\n", &mut session).unwrap();
+        assert_eq!((session.memory[0], session.memory[1], session.pointer), (3, 8, 1));
+        assert_eq!(session.last_run.as_ref().and_then(|s| s.steps), Some(1));
+    }
+
+    #[test]
+    fn parse_settings_meta_commands() {
+        assert_eq!(
+            parse_meta_command(":set max-steps 1e6"),
+            Some(MetaCommand::SetSetting { name: "max-steps".to_string(), value: "1e6".to_string() })
+        );
+        assert_eq!(parse_meta_command(":set 3 7"), Some(MetaCommand::SetCell { cell: 3, value: 7 }));
+        assert_eq!(parse_meta_command(":show settings"), Some(MetaCommand::ShowSettings));
+        assert!(matches!(parse_meta_command(":show"), Some(MetaCommand::Invalid(_))));
+    }
+
+    #[test]
+    fn settings_parse_values_and_resize_tape() {
+        let mut settings = ReplSettings::default();
+        settings.set("timeout", "5000").unwrap();
+        settings.set("max-steps", "1e6").unwrap();
+        settings.set("eof", "unchanged").unwrap();
+        assert_eq!(settings.timeout_ms, Some(5000));
+        assert_eq!(settings.max_steps, Some(1_000_000));
+        assert_eq!(settings.eof, EofBehavior::Unchanged);
+        settings.set("timeout", "none").unwrap();
+        assert_eq!(settings.timeout_ms, None);
+        assert!(settings.set("max-steps", "2.5e0").is_err());
+        assert!(settings.set("memory", "0").is_err());
+        assert!(settings.set("colour", "red").is_err());
+
+        let mut session = ReplSession::new(true, ReplSettings::default());
+        session.memory[5] = 9;
+        session.pointer = 20;
+        session.set("memory", "8").unwrap();
+        assert_eq!((session.memory.len(), session.memory[5], session.pointer), (8, 9, 7));
+
+        // The run starts from the resized tape, at the clamped pointer
+        session.pending_input.lock().unwrap().push_back(b'A');
+        execute_bf_buffer(",<+".to_string(), &mut session);
+        assert_eq!((session.memory[7], session.memory[6], session.pointer), (b'A', 1, 6));
+
+        // Once the queue is empty `,` hits EOF and stores the configured value
+        session.set("eof", "max").unwrap();
+        session.stdin_input = false;
+        session.pending_input.lock().unwrap().push_back(b'B');
+        execute_bf_buffer(",>,".to_string(), &mut session);
+        assert_eq!((session.memory[6], session.memory[7]), (b'B', 255));
+    }
+
+    #[test]
+    fn history_options_and_listing() {
+        assert_eq!(parse_meta_command(":history"), Some(MetaCommand::History { clear: false }));
+        assert_eq!(parse_meta_command(":history clear"), Some(MetaCommand::History { clear: true }));
+
+        let mut options = HistoryOptions::default();
+        options.set("history-size", "50").unwrap();
+        options.set("history-file", "none").unwrap();
+        assert_eq!(options, HistoryOptions { file: None, size: 50 });
+        assert!(options.set("history-size", "0").is_err());
+
+        let mut out = Vec::new();
+        write_history(&mut out, &["+++\n>+.\n".to_string(), ",.".to_string()]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "    1  +++\n       >+.\n    2  ,.\n");
+    }
+
+    #[test]
+    fn configured_history_file_is_loaded_listed_and_cleared() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("repl_history");
+        let table = HashMap::from([
+            ("history-file".to_string(), path.display().to_string()),
+            ("history-size".to_string(), "5".to_string()),
+        ]);
+        let history = HistoryOptions::from_table(&table);
+        assert_eq!(history, HistoryOptions { file: Some(path.clone()), size: 5 });
+        // Invalid values keep the default
+        let invalid = HistoryOptions::from_table(&HashMap::from([("history-size".to_string(), "0".to_string())]));
+        assert_eq!(invalid.size, HistoryOptions::default().size);
+
+        // An earlier session's multiline entry comes back in the editor
+        let mut previous = reedline::FileBackedHistory::with_file(5, path.clone()).unwrap();
+        previous.save(HistoryItem::from_command_line("+++\n>+.")).unwrap();
+        previous.sync().unwrap();
+        let mut editor = init_line_editor(&ReplOptions { history, ..ReplOptions::default() }).unwrap();
+        let everything = || SearchQuery::everything(SearchDirection::Forward, None);
+        let items = editor.history().search(everything()).unwrap();
+        assert_eq!(items.iter().map(|item| item.command_line.as_str()).collect::<Vec<_>>(), ["+++\n>+."]);
+
+        let mut session = ReplSession::new(false, ReplSettings::default());
+        let list = MetaCommand::History { clear: false };
+        let clear = MetaCommand::History { clear: true };
+        assert_eq!(handle_meta_command(Some(&mut editor), &list, "", &mut session).unwrap(), MetaAction::Continue);
+        assert_eq!(editor.history().search(everything()).unwrap().len(), 1);
+        assert_eq!(handle_meta_command(Some(&mut editor), &clear, "", &mut session).unwrap(), MetaAction::Continue);
+        assert!(editor.history().search(everything()).unwrap().is_empty());
+        assert!(!path.exists());
+        // Scripts have no editor history; the command is a no-op there
+        assert_eq!(handle_meta_command(None, &clear, "", &mut session).unwrap(), MetaAction::Continue);
+    }
+
+    #[test]
+    fn input_text_escapes() {
+        assert_eq!(parse_input_text(r#""a\tb\n\x41\"""#), Ok(b"a\tb\nA\"".to_vec()));
+        assert_eq!(parse_input_text("plain text"), Ok(b"plain text".to_vec()));
+        assert!(parse_input_text(r#""open"#).is_err());
+        assert!(parse_input_text(r#""\q""#).is_err());
+        assert_eq!(parse_meta_command(":input clear"), Some(MetaCommand::InputClear));
+        assert_eq!(parse_meta_command(":input"), Some(MetaCommand::Input(None)));
+    }
+
+    #[test]
+    fn quoted_input_parses_back() {
+        let bytes = b"a \"q\" \\ \n\t\r\x00\xff".to_vec();
+        assert_eq!(parse_input_text(&quote_input(&bytes)), Ok(bytes));
+        assert_eq!(quote_input(b"hi\n"), r#""hi\n""#);
+    }
+
+    #[test]
+    fn record_writes_a_replayable_transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.bft");
+        let script = format!(":record {}\n+.\n:#\n++.\n:load x.bf\n:stop-record\n-.\n", path.display());
+        let mut session = ReplSession::new(false, ReplSettings { memory: 64, ..ReplSettings::default() });
+        run_bare_script(&script, &mut session).unwrap();
+        let transcript = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(transcript, ":# bf repl transcript\n:set memory 64\n+.\n:#\n++.\n:# load x.bf\n");
+        assert!(session.transcript.is_none());
+        assert_eq!(parse_meta_command(":# note"), Some(MetaCommand::Comment));
+    }
+
+    #[test]
+    fn queued_input_feeds_comma_and_is_dropped_without_persist() {
+        let mut session = ReplSession::new(false, ReplSettings::default());
+        run_bare_script(":input \"AB\"\n,>,\n", &mut session).unwrap();
+        assert_eq!((session.memory[0], session.memory[1]), (b'A', b'B'));
+
+        run_bare_script(":input \"xyz\"\n,\n", &mut session).unwrap();
+        assert_eq!(session.memory[0], b'x');
+        assert!(session.pending_input.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn every_registered_command_parses() {
+        for spec in META_COMMANDS {
+            // Commands that need arguments report usage instead of panicking
+            assert!(parse_meta_command(spec.name).is_some(), "{}", spec.name);
+            for alias in spec.aliases {
+                assert!(parse_meta_command(alias).is_some(), "{alias}");
+            }
+        }
+        assert_eq!(parse_meta_command(":quit"), Some(MetaCommand::Exit));
+    }
+
+    #[test]
+    fn unknown_commands_suggest_the_closest_name() {
+        assert_eq!(
+            parse_meta_command(":dmup -n"),
+            Some(MetaCommand::Invalid("unknown command ':dmup'; did you mean ':dump'?".to_string()))
+        );
+        assert_eq!(
+            parse_meta_command(":frobnicate"),
+            Some(MetaCommand::Invalid("unknown command ':frobnicate' (:help lists commands)".to_string()))
+        );
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn completer_suggests_commands_and_flags() {
+        let values = |line: &str| -> Vec<String> {
+            ReplCompleter.complete(line, line.len()).into_iter().map(|s| s.value).collect()
+        };
+        assert_eq!(values(":sa"), vec![":save-settings", ":save"]);
+        assert_eq!(values(":dump "), vec!["-n", "--stderr"]);
+        assert_eq!(values(":dump -n --"), vec!["--stderr"]);
+        assert_eq!(values(":set m"), vec!["max-steps", "memory"]);
+        assert!(values(":set memory ").is_empty());
+        assert!(values(":exit ").is_empty());
+
+        let suggestion = &ReplCompleter.complete("+\n:per", 6)[0];
+        assert_eq!((suggestion.value.as_str(), suggestion.span), (":persist", Span::new(2, 6)));
+        assert!(suggestion.append_whitespace);
+    }
+
+    #[test]
+    fn hinter_completes_unique_command_names() {
+        let history = reedline::FileBackedHistory::new(1).unwrap();
+        let mut hinter = ReplHinter::default();
+        assert_eq!(hinter.handle(":du", 3, &history, false, ""), "mp [-n|--stderr]");
+        assert_eq!(hinter.complete_hint(), "mp");
+        // Ambiguous prefixes and code get no hint
+        assert_eq!(hinter.handle(":s", 2, &history, false, ""), "");
+        assert_eq!(hinter.handle("+++", 3, &history, false, ""), "");
+        assert_eq!(hinter.complete_hint(), "");
+    }
+
+    #[test]
+    fn bracket_map_finds_pairs_and_unmatched() {
+        let map = BracketMap::new("+[>[-]<]]\n[");
+        assert_eq!(map.unmatched, vec![8, 10]);
+        assert_eq!(map.pair_at_cursor("+[>[-]<]]\n[", 1), Some((1, 7)));
+        // Cursor just after a bracket also selects it
+        assert_eq!(map.pair_at_cursor("+[>[-]<]]\n[", 6), Some((5, 3)));
+        assert_eq!(map.pair_at_cursor("+[>[-]<]]\n[", 9), None);
+        assert_eq!(map.first_problem("+[>[-]<]]\n["), Some("unmatched ']' at line 1, column 9".to_string()));
+    }
+
+    #[test]
+    fn submit_keys_go_through_the_validator() {
+        use reedline::{default_emacs_keybindings, default_vi_insert_keybindings, EditCommand, ReedlineEvent};
+        // The editor's own bindings: submit keys must send Enter, which reedline checks with the
+        // validator before submitting (Submit would skip it)
+        for mut keybindings in [default_emacs_keybindings(), default_vi_insert_keybindings()] {
+            add_repl_bindings(&mut keybindings, &ReplKeys::default());
+            assert_eq!(keybindings.find_binding(KeyModifiers::CONTROL, KeyCode::Char('d')), Some(ReedlineEvent::Enter));
+            assert_eq!(
+                keybindings.find_binding(KeyModifiers::NONE, KeyCode::Enter),
+                Some(ReedlineEvent::Edit(vec![EditCommand::InsertNewline]))
+            );
+        }
+        assert!(matches!(BracketValidator.validate("+[->+<]"), ValidationResult::Complete));
+        assert!(matches!(BracketValidator.validate("+[->\n+<"), ValidationResult::Incomplete));
+        assert!(matches!(BracketValidator.validate(":load [draft].bf"), ValidationResult::Complete));
+    }
+
+    #[test]
+    fn hinter_reports_unmatched_brackets() {
+        let history = reedline::FileBackedHistory::new(1).unwrap();
+        let mut hinter = ReplHinter::default();
+        assert_eq!(hinter.handle("+[\n-", 4, &history, false, ""), "  (unmatched '[' at line 1, column 2)");
+        assert_eq!(hinter.complete_hint(), "");
+    }
+
+    #[test]
+    fn highlighter_marks_unmatched_and_cursor_pair() {
+        let hl = BrainfuckHighlighter::new_from_config();
+        let styles = |line: &str, cursor: usize| -> Vec<(Style, String)> { hl.highlight(line, cursor).buffer };
+        let out = styles("[+]]", 0);
+        assert_eq!(out[0], (hl.map_match, "[".to_string()));
+        assert_eq!(out[1], (hl.map_plus, "+".to_string()));
+        assert_eq!(out[2], (hl.map_match, "]".to_string()));
+        assert_eq!(out[3], (hl.map_unmatched, "]".to_string()));
+    }
+
+    #[test]
+    fn parse_key_specs() {
+        let parse_key = |spec| cli_util::parse_key_spec(spec).map(to_reedline_key);
+        assert_eq!(parse_key("ctrl+d"), Ok((KeyModifiers::CONTROL, KeyCode::Char('d'))));
+        assert_eq!(parse_key("Alt+Up"), Ok((KeyModifiers::ALT, KeyCode::Up)));
+        assert_eq!(parse_key("ctrl+shift+enter"), Ok((KeyModifiers::CONTROL | KeyModifiers::SHIFT, KeyCode::Enter)));
+        assert_eq!(parse_key("ctrl++"), Ok((KeyModifiers::CONTROL, KeyCode::Char('+'))));
+        assert_eq!(parse_key("f5"), Ok((KeyModifiers::NONE, KeyCode::F(5))));
+        assert!(parse_key("hyper+x").is_err());
+        assert!(parse_key("ctrl+upp").is_err());
+        assert_eq!(cli_util::parse_key_spec("ctrl+shift+p").unwrap().to_string(), "Ctrl+Shift+P");
+        assert_eq!(cli_util::parse_key_spec("ctrl+pagedown").unwrap().to_string(), "Ctrl+PageDown");
+    }
+
+    #[test]
+    fn remapped_keys_replace_defaults() {
+        use reedline::{default_emacs_keybindings, ReedlineEvent};
+        let mut keys = ReplKeys::default();
+        keys.set("history-previous", "alt+up, ctrl+p").unwrap();
+        keys.set("submit", "ctrl+s").unwrap();
+        assert!(keys.set("launch", "ctrl+l").is_err());
+
+        let mut keybindings = default_emacs_keybindings();
+        keys.add_to(&mut keybindings);
+        assert_eq!(keybindings.find_binding(KeyModifiers::CONTROL, KeyCode::Char('p')), Some(ReedlineEvent::PreviousHistory));
+        assert_eq!(keybindings.find_binding(KeyModifiers::CONTROL, KeyCode::Char('s')), Some(ReedlineEvent::Enter));
+        assert_ne!(keybindings.find_binding(KeyModifiers::CONTROL, KeyCode::Up), Some(ReedlineEvent::PreviousHistory));
+    }
+
+    #[test]
+    fn parse_file_meta_commands() {
+        assert_eq!(parse_meta_command(":load my prog.bf"), Some(MetaCommand::Load("my prog.bf".to_string())));
+        assert_eq!(parse_meta_command(":run a.bf"), Some(MetaCommand::Run("a.bf".to_string())));
+        assert_eq!(parse_meta_command(":save out.bf"), Some(MetaCommand::Save("out.bf".to_string())));
+        assert!(matches!(parse_meta_command(":save"), Some(MetaCommand::Invalid(_))));
+    }
+
+    #[test]
+    fn completer_suggests_paths_after_file_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("hello.bf"), "+").unwrap();
+        std::fs::create_dir(dir.path().join("help")).unwrap();
+        std::fs::write(dir.path().join(".hidden"), "").unwrap();
+
+        let line = format!(":load {}/hel", dir.path().display());
+        let suggestions = ReplCompleter.complete(&line, line.len());
+        let values: Vec<&str> = suggestions.iter().map(|s| s.value.as_str()).collect();
+        let base = dir.path().display().to_string();
+        assert_eq!(values, vec![format!("{base}/hello.bf"), format!("{base}/help/")]);
+        assert_eq!(suggestions[0].span, Span::new(6, line.len()));
+
+        assert!(ReplCompleter.complete("+++", 3).is_empty());
+    }
+
+    #[test]
+    fn bare_script_load_then_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("in.bf");
+        let dst = dir.path().join("out.bf");
+        std::fs::write(&src, "++>+").unwrap();
+        let script = format!(":load {}\n:save {}\n", src.display(), dst.display());
+        let mut session = ReplSession::new(false, ReplSettings::default());
+        run_bare_script(&script, &mut session).unwrap();
+        assert_eq!(std::fs::read_to_string(&dst).unwrap(), "++>+");
+        // The loaded code ran before :save
+        assert_eq!((session.memory[0], session.memory[1]), (2, 1));
+    }
+
     #[test]
     fn read_submission_empty_returns_none() {
         let mut cursor = Cursor::new(Vec::<u8>::new());
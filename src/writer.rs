@@ -22,6 +22,8 @@
 
 use std::cmp::Ordering;
 
+use crate::{BrainfuckFormatter, FormatOptions};
+
 /// Errors that can occur while generating Brainfuck code.
 #[derive(Debug)]
 pub enum BrainfuckWriterError {
@@ -31,12 +33,64 @@ pub enum BrainfuckWriterError {
     UnmatchedBrackets,
     /// An underlying I/O error occurred when reading from stdin.
     IoError(std::io::Error),
+    /// The target allows no cells at all (`max_cells == 0`).
+    TargetTooSmall,
+}
+
+/// Cell size of the interpreter that will run the generated code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellWidth {
+    Bits8,
+    Bits16,
+    Bits32,
+}
+
+/// Describes the interpreter the generated code must run on.
+///
+/// `BrainfuckWriter` only picks constructions that are valid for the target:
+/// - Wrap-around deltas (e.g. `0 -> 255` as a single `-`) need 8-bit wrapping cells.
+/// - Loop multiplication needs a second (temp) cell, and on 8-bit cells without
+///   wrapping its product must stay within `0..=255`.
+/// - When the starting cell state is unknown, the first byte clears the cell first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriterTarget {
+    /// Cells the program may use, counting from the starting cell.
+    pub max_cells: usize,
+    pub cell_width: CellWidth,
+    /// Whether incrementing/decrementing past the cell range wraps around.
+    pub wrapping: bool,
+    /// Whether cells are known to start at zero.
+    pub zeroed_start: bool,
+}
+
+impl WriterTarget {
+    /// 30,000 zeroed 8-bit wrapping cells, like `BrainfuckReader` and most interpreters.
+    pub const STANDARD: WriterTarget = WriterTarget {
+        max_cells: 30_000,
+        cell_width: CellWidth::Bits8,
+        wrapping: true,
+        zeroed_start: true,
+    };
+
+    /// True when an 8-bit value can wrap from 0 to 255 and back.
+    fn wraps_u8(&self) -> bool {
+        self.cell_width == CellWidth::Bits8 && self.wrapping
+    }
+}
+
+impl Default for WriterTarget {
+    fn default() -> Self {
+        Self::STANDARD
+    }
 }
 
 pub struct WriterOptions {
     pub use_loops: bool, // Use loop-based multiplication when building from zero
     pub max_loop_factor: u8, // Maximum outer loop counter to consider (e.g., 16..32 is fine)
-    pub assume_wrapping_u8: bool, // Assume BF cells wrap (most interpreters do)
+    pub assume_wrapping_u8: bool, // Assume BF cells wrap (most interpreters do); ignored unless the target wraps at 256
+    pub target: WriterTarget, // Interpreter the code must run on; limits which constructions are used
+    pub tidy: Option<FormatOptions>, // Lay out the generated code with the formatter instead of one line
+    pub annotate: bool, // One line per byte with a comment naming the value and how it was built
 }
 
 impl Default for WriterOptions {
@@ -45,6 +99,9 @@ impl Default for WriterOptions {
             use_loops: true,
             max_loop_factor: 16,
             assume_wrapping_u8: true,
+            target: WriterTarget::STANDARD,
+            tidy: None,
+            annotate: false,
         }
     }
 }
@@ -64,42 +121,66 @@ impl<'writer> BrainfuckWriter<'writer> {
     }
 
     pub fn generate(&self) -> Result<String, BrainfuckWriterError> {
+        if self.options.target.max_cells == 0 {
+            return Err(BrainfuckWriterError::TargetTooSmall);
+        }
+
         let mut output = String::new();
-        let mut cursor = 0u8;
+        // Current cell value, if known
+        let mut cursor = self.options.target.zeroed_start.then_some(0u8);
 
         for b in self.input.iter() {
             // Option A: delta encodes from cursor -> b using wrapping arithmetic
-            let delta_sequence = self.encode_delta(cursor, *b);
+            let delta_sequence = cursor.map(|cursor| self.encode_delta(cursor, *b));
 
             // Option B: clear and rebuild from zero (no reliance on wrapping)
-            let from_zero_sequence = self.encode_from_zero(*b);
+            let (from_zero_sequence, from_zero_strategy) = self.encode_from_zero(*b);
 
             // Choose the shorter option for this byte
-            let best_sequence = if delta_sequence.len() <= from_zero_sequence.len() {
-                delta_sequence
+            let delta_sequence = delta_sequence.filter(|d| d.len() <= from_zero_sequence.len());
+            let (best_sequence, strategy) = if let Some(delta_sequence) = delta_sequence {
+                let delta = if delta_sequence.starts_with('-') {
+                    -(delta_sequence.len() as i32)
+                } else {
+                    delta_sequence.len() as i32
+                };
+                (delta_sequence, Strategy::Delta(delta))
             } else {
-                from_zero_sequence
+                (from_zero_sequence, from_zero_strategy)
             };
 
+            if self.options.annotate && !output.is_empty() {
+                output.push('\n');
+            }
             output.push_str(&best_sequence);
             output.push('.');
+            if self.options.annotate {
+                output.push_str("  ");
+                output.push_str(&annotation(*b, strategy));
+            }
 
-            cursor = *b;
+            cursor = Some(*b);
+        }
+
+        if let Some(format_options) = self.options.tidy {
+            output = BrainfuckFormatter::with_options(&output, format_options)
+                .format()
+                .map_err(|_| BrainfuckWriterError::UnmatchedBrackets)?;
         }
 
         Ok(output)
     }
 
     /// Encode the shortest delta from cursor to target.
-    /// If `assume_wrapping` is true, it computes the shortest path on a ring of 256.
-    /// If false, we avoid wrap and prefer clear+build, but still produce a non-wrapping delta.
-    fn encode_delta(&self, cursor: u8, target: u8) -> String {
+    /// If `assume_wrapping_u8` is true and the target wraps at 256, it computes the shortest
+    /// path on a ring of 256. Otherwise it produces a non-wrapping delta.
+    pub(crate) fn encode_delta(&self, cursor: u8, target: u8) -> String {
         if cursor == target {
             return String::new();
         }
 
         let mut output = String::new();
-        if self.options.assume_wrapping_u8 {
+        if self.options.assume_wrapping_u8 && self.options.target.wraps_u8() {
             // Compute the shortest path on a ring of 256
             let forward = (target.wrapping_sub(cursor)) as u8; // `+` count
             let backward = (cursor.wrapping_sub(target)) as u8; // `-` count
@@ -120,17 +201,49 @@ impl<'writer> BrainfuckWriter<'writer> {
     }
 
     /// Build exact value `target` in the current cell starting from an unknown prior value.
-    fn encode_from_zero(&self, target: u8) -> String {
+    fn encode_from_zero(&self, target: u8) -> (String, Strategy) {
         // Always start by clearing the current cell
         let mut best = String::from("[-]");
         best.push_str(&"+".repeat(target as usize));
+        let mut strategy = Strategy::Clear;
 
         if !self.options.use_loops || target == 0 {
-            return best;
+            return (best, strategy);
+        }
+
+        // Loop-based constructions also need the temp cell (>) to start at zero: >[-]<
+        if let Some((product, a, b, r)) = self.product_construction(target) {
+            let seq = format!("[-]>[-]<{product}");
+            if seq.len() < best.len() {
+                best = seq;
+                strategy = Strategy::Loop { a, b, r };
+            }
         }
 
+        (best, strategy)
+    }
+
+    /// Shortest loop construction of `target`, assuming the current cell and the temp cell (>)
+    /// are both zero. Returns `None` when loops are disabled or `target` is zero.
+    pub(crate) fn encode_product(&self, target: u8) -> Option<String> {
+        self.product_construction(target).map(|(seq, ..)| seq)
+    }
+
+    /// Like `encode_product`, also returning the factors `a`, `b` and remainder `r`
+    /// (`target == a * b + r`).
+    fn product_construction(&self, target: u8) -> Option<(String, u8, u8, i32)> {
+        // The construction needs a temp cell next to the current one
+        if !self.options.use_loops || target == 0 || self.options.target.max_cells < 2 {
+            return None;
+        }
+        // An 8-bit cell that can't wrap must never hold a*b > 255
+        let max_product = if self.options.target.cell_width == CellWidth::Bits8 && !self.options.target.wrapping {
+            255
+        } else {
+            i32::MAX
+        };
+
         // Try loop-based constructions of the form:
-        // Ensure temp cell (>) is zero: >[-]<
         // Set current to 'a': '+' * a
         // [ > '+' * b < - ] ; multiply a*b into temp, clear current
         // > adjust remainder r = cursor - a*b with '+' or '-'
@@ -141,7 +254,7 @@ impl<'writer> BrainfuckWriter<'writer> {
         //
         // We search a in [1..max_factor], b ~ round(cursor / a), clamp b to [1..=255],
         // and adjust the small remainder with +/-.
-        let mut best_len = best.len();
+        let mut best: Option<(String, u8, u8, i32)> = None;
 
         for a in 1..=self.options.max_loop_factor {
             // choose b as nearest integer to cursor / a, but at least 1
@@ -151,10 +264,10 @@ impl<'writer> BrainfuckWriter<'writer> {
             if b > 255 { b = 255; }
 
             let prod = (a as i32) * b;
+            if prod > max_product {
+                continue;
+            }
             let mut seq = String::new();
-            seq.push_str("[-]"); // clear current cell
-            seq.push_str(">[-]<"); // ensure temp cell is zero
-
             seq.push_str(&"+".repeat(a as usize));
             seq.push('[');
             seq.push('>');
@@ -177,9 +290,8 @@ impl<'writer> BrainfuckWriter<'writer> {
             seq.push(']');
             seq.push('<'); // return to current cell
 
-            if seq.len() < best_len {
-                best_len = seq.len();
-                best = seq;
+            if best.as_ref().is_none_or(|(best_seq, ..)| seq.len() < best_seq.len()) {
+                best = Some((seq, a, b as u8, r));
             }
         }
 
@@ -187,6 +299,359 @@ impl<'writer> BrainfuckWriter<'writer> {
     }
 }
 
+/// How `generate` built a byte; only used for `--annotate` comments.
+#[derive(Debug, Clone, Copy)]
+enum Strategy {
+    /// Adjust the previous value by this many steps.
+    Delta(i32),
+    /// Clear the cell, then add the value one at a time.
+    Clear,
+    /// Clear, then `a` times add `b` into the temp cell, adjust by `r` and move it back.
+    Loop { a: u8, b: u8, r: i32 },
+}
+
+/// Comment for one annotated output byte, e.g. `'H' 0x48 loop 8x9`.
+///
+/// Comments never contain command characters, so annotated output still runs in
+/// readers that ignore non-command text.
+fn annotation(byte: u8, strategy: Strategy) -> String {
+    let shown = match byte {
+        b'+' => "plus sign".to_string(),
+        b'-' => "minus sign".to_string(),
+        b'<' => "less than".to_string(),
+        b'>' => "greater than".to_string(),
+        b'.' => "period".to_string(),
+        b',' => "comma".to_string(),
+        b'[' => "open bracket".to_string(),
+        b']' => "close bracket".to_string(),
+        b' ' => "space".to_string(),
+        b'\n' => "newline".to_string(),
+        b'\t' => "tab".to_string(),
+        b if b.is_ascii_graphic() => format!("'{}'", b as char),
+        _ => "byte".to_string(),
+    };
+
+    let how = match strategy {
+        Strategy::Delta(0) => "delta none (repeat)".to_string(),
+        Strategy::Delta(d) if d > 0 => format!("delta up {d}"),
+        Strategy::Delta(d) => format!("delta down {}", -d),
+        Strategy::Clear => format!("clear then add {byte}"),
+        Strategy::Loop { a, b, r: 0 } => format!("loop {a}x{b}"),
+        Strategy::Loop { a, b, r } if r > 0 => format!("loop {a}x{b} plus {r}"),
+        Strategy::Loop { a, b, r } => format!("loop {a}x{b} minus {}", -r),
+    };
+
+    format!("{shown} 0x{byte:02X} {how}")
+}
+
+/// Ready-made programs that transform their input rather than printing a constant.
+///
+/// All templates assume 8-bit wrapping cells and treat a `0` byte from `,` as EOF,
+/// which matches `BrainfuckReader`'s default input behavior.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriterTemplate {
+    /// Copy input to output unchanged.
+    Cat,
+    /// Convert ASCII `a-z` to `A-Z`; other bytes pass through.
+    Upper,
+    /// Convert ASCII `A-Z` to `a-z`; other bytes pass through.
+    Lower,
+    /// Rotate ASCII letters by 13 places; other bytes pass through.
+    Rot13,
+    /// Read all input, then print it back in reverse byte order.
+    Reverse,
+    /// Count newline bytes in the input and print the count in decimal.
+    Lines,
+    /// Print the decimal value of each input byte on its own line.
+    Decimal,
+}
+
+impl WriterTemplate {
+    /// Every template, in the order they are listed in help output.
+    pub const ALL: [WriterTemplate; 7] = [
+        WriterTemplate::Cat,
+        WriterTemplate::Upper,
+        WriterTemplate::Lower,
+        WriterTemplate::Rot13,
+        WriterTemplate::Reverse,
+        WriterTemplate::Lines,
+        WriterTemplate::Decimal,
+    ];
+
+    /// Name used to select the template on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            WriterTemplate::Cat => "cat",
+            WriterTemplate::Upper => "upper",
+            WriterTemplate::Lower => "lower",
+            WriterTemplate::Rot13 => "rot13",
+            WriterTemplate::Reverse => "reverse",
+            WriterTemplate::Lines => "lines",
+            WriterTemplate::Decimal => "decimal",
+        }
+    }
+
+    /// One-line description for help output.
+    pub fn description(&self) -> &'static str {
+        match self {
+            WriterTemplate::Cat => "Copy input to output unchanged",
+            WriterTemplate::Upper => "Convert ASCII letters to uppercase",
+            WriterTemplate::Lower => "Convert ASCII letters to lowercase",
+            WriterTemplate::Rot13 => "Apply ROT13 to ASCII letters",
+            WriterTemplate::Reverse => "Print all input in reverse byte order",
+            WriterTemplate::Lines => "Print the number of newlines in the input",
+            WriterTemplate::Decimal => "Print each input byte as a decimal number, one per line",
+        }
+    }
+
+    /// Look up a template by its command-line name (case-insensitive).
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase();
+        Self::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    /// Generate the Brainfuck source for this template.
+    pub fn generate(&self) -> String {
+        match self {
+            WriterTemplate::Cat => String::from(",[.,]"),
+            // Cell 0 stays zero as a sentinel; bytes are stored from cell 1 rightwards.
+            WriterTemplate::Reverse => String::from(">,[>,]<[.<]"),
+            WriterTemplate::Upper => transform_bytes(&[(b'a', 26, -32)]),
+            WriterTemplate::Lower => transform_bytes(&[(b'A', 26, 32)]),
+            WriterTemplate::Rot13 => transform_bytes(&[
+                (b'a', 13, 13),
+                (b'n', 13, -13),
+                (b'A', 13, 13),
+                (b'N', 13, -13),
+            ]),
+            WriterTemplate::Lines => count_lines(),
+            WriterTemplate::Decimal => print_decimal_bytes(),
+        }
+    }
+}
+
+/// Emits Brainfuck while tracking the data pointer, so cells can be addressed absolutely.
+///
+/// Every loop body must return to the loop's cell before `]`; `while_nonzero` enforces this.
+struct Emitter {
+    code: String,
+    ptr: usize,
+}
+
+impl Emitter {
+    fn new() -> Self {
+        Self { code: String::new(), ptr: 0 }
+    }
+
+    fn goto(&mut self, cell: usize) {
+        match cell.cmp(&self.ptr) {
+            Ordering::Greater => self.code.push_str(&">".repeat(cell - self.ptr)),
+            Ordering::Less => self.code.push_str(&"<".repeat(self.ptr - cell)),
+            Ordering::Equal => {}
+        }
+        self.ptr = cell;
+    }
+
+    fn add(&mut self, cell: usize, n: i32) {
+        self.goto(cell);
+        let op = if n >= 0 { "+" } else { "-" };
+        self.code.push_str(&op.repeat(n.unsigned_abs() as usize));
+    }
+
+    fn input(&mut self, cell: usize) {
+        self.goto(cell);
+        self.code.push(',');
+    }
+
+    fn output(&mut self, cell: usize) {
+        self.goto(cell);
+        self.code.push('.');
+    }
+
+    fn clear(&mut self, cell: usize) {
+        self.goto(cell);
+        self.code.push_str("[-]");
+    }
+
+    /// `cell[ body ]`, returning to `cell` before the closing bracket.
+    fn while_nonzero(&mut self, cell: usize, body: impl FnOnce(&mut Self)) {
+        self.goto(cell);
+        self.code.push('[');
+        body(self);
+        self.goto(cell);
+        self.code.push(']');
+    }
+
+    /// Add `from` into `to`, leaving `from` zero.
+    fn move_cell(&mut self, from: usize, to: usize) {
+        self.while_nonzero(from, |e| {
+            e.add(from, -1);
+            e.add(to, 1);
+        });
+    }
+
+    /// Set `flag` to 1 when `lo <= src < lo + len`, leaving `src` intact.
+    ///
+    /// `y`, `budget` and `tmp` must be zero on entry and are zero again on exit.
+    fn in_range(&mut self, src: usize, lo: u8, len: u8, flag: usize, [y, budget, tmp]: [usize; 3]) {
+        // y = src - lo (wrapping), copied via tmp so src survives
+        self.while_nonzero(src, |e| {
+            e.add(src, -1);
+            e.add(y, 1);
+            e.add(tmp, 1);
+        });
+        self.move_cell(tmp, src);
+        self.add(y, -(lo as i32));
+
+        // Spend y from a budget of `len` with a saturating decrement; anything left means y < len
+        self.add(budget, len as i32);
+        self.while_nonzero(y, |e| {
+            e.add(y, -1);
+            e.while_nonzero(budget, |e| {
+                e.add(budget, -1);
+                e.while_nonzero(budget, |e| {
+                    e.add(budget, -1);
+                    e.add(tmp, 1);
+                });
+            });
+            e.move_cell(tmp, budget);
+        });
+        self.while_nonzero(budget, |e| {
+            e.clear(budget);
+            e.add(flag, 1);
+        });
+    }
+}
+
+/// Cells used by a decimal counter: digit `i` occupies four cells starting at `base + 4 * i`
+/// (digit value, move temp, carry flag, "digit is in use" flag), least significant first.
+struct DecimalCounter {
+    base: usize,
+    digits: usize,
+}
+
+impl DecimalCounter {
+    fn digit(&self, i: usize) -> usize { self.base + 4 * i }
+    fn temp(&self, i: usize) -> usize { self.digit(i) + 1 }
+    fn carry(&self, i: usize) -> usize { self.digit(i) + 2 }
+    fn in_use(&self, i: usize) -> usize { self.digit(i) + 3 }
+
+    /// Increment digit `i`, carrying into higher digits on overflow past 9.
+    fn increment(&self, e: &mut Emitter, i: usize) {
+        let (digit, temp, carry) = (self.digit(i), self.temp(i), self.carry(i));
+        // digit + 1 - 10 is zero exactly when the digit overflows
+        e.add(digit, -9);
+        e.add(carry, 1);
+        e.while_nonzero(digit, |e| {
+            e.add(digit, 10);
+            e.move_cell(digit, temp);
+            e.add(carry, -1);
+        });
+        e.move_cell(temp, digit);
+        e.while_nonzero(carry, |e| {
+            e.add(carry, -1);
+            if i + 1 < self.digits {
+                e.clear(self.in_use(i + 1));
+                e.add(self.in_use(i + 1), 1);
+                self.increment(e, i + 1);
+            }
+        });
+    }
+
+    /// Print the counter without leading zeros, then a newline. Clears the in-use flags.
+    fn print(&self, e: &mut Emitter) {
+        for i in (1..self.digits).rev() {
+            let digit = self.digit(i);
+            e.while_nonzero(self.in_use(i), |e| {
+                e.add(self.in_use(i), -1);
+                e.add(digit, b'0' as i32);
+                e.output(digit);
+                e.add(digit, -(b'0' as i32));
+            });
+        }
+        let (digit, temp) = (self.digit(0), self.temp(0));
+        e.add(digit, b'0' as i32);
+        e.output(digit);
+        e.add(digit, -(b'0' as i32));
+        e.add(temp, b'\n' as i32);
+        e.output(temp);
+        e.clear(temp);
+    }
+
+    fn reset(&self, e: &mut Emitter) {
+        for i in 0..self.digits {
+            e.clear(self.digit(i));
+        }
+    }
+}
+
+/// Echo every byte, shifting it by `delta` when it falls in `[lo, lo + len)`.
+/// Ranges are tested against the original byte, so shifts never cascade.
+fn transform_bytes(ranges: &[(u8, u8, i32)]) -> String {
+    const BYTE: usize = 0;
+    const SCRATCH: [usize; 3] = [1, 2, 3];
+    const FLAGS: usize = 4;
+
+    let mut e = Emitter::new();
+    e.input(BYTE);
+    e.while_nonzero(BYTE, |e| {
+        for (k, &(lo, len, _)) in ranges.iter().enumerate() {
+            e.in_range(BYTE, lo, len, FLAGS + k, SCRATCH);
+        }
+        for (k, &(_, _, delta)) in ranges.iter().enumerate() {
+            e.while_nonzero(FLAGS + k, |e| {
+                e.add(FLAGS + k, -1);
+                e.add(BYTE, delta);
+            });
+        }
+        e.output(BYTE);
+        e.input(BYTE);
+    });
+    e.code
+}
+
+fn count_lines() -> String {
+    const BYTE: usize = 0;
+    const IS_NEWLINE: usize = 1;
+    let counter = DecimalCounter { base: 2, digits: 8 };
+
+    let mut e = Emitter::new();
+    e.input(BYTE);
+    e.while_nonzero(BYTE, |e| {
+        e.add(BYTE, -(b'\n' as i32));
+        e.add(IS_NEWLINE, 1);
+        e.while_nonzero(BYTE, |e| {
+            e.clear(BYTE);
+            e.add(IS_NEWLINE, -1);
+        });
+        e.while_nonzero(IS_NEWLINE, |e| {
+            e.add(IS_NEWLINE, -1);
+            counter.increment(e, 0);
+        });
+        e.input(BYTE);
+    });
+    counter.print(&mut e);
+    e.code
+}
+
+fn print_decimal_bytes() -> String {
+    const BYTE: usize = 0;
+    let counter = DecimalCounter { base: 1, digits: 3 };
+
+    let mut e = Emitter::new();
+    e.input(BYTE);
+    e.while_nonzero(BYTE, |e| {
+        e.while_nonzero(BYTE, |e| {
+            e.add(BYTE, -1);
+            counter.increment(e, 0);
+        });
+        counter.print(e);
+        counter.reset(e);
+        e.input(BYTE);
+    });
+    e.code
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -206,6 +671,9 @@ mod tests {
             use_loops: true,
             max_loop_factor: 16,
             assume_wrapping_u8: true,
+            target: WriterTarget::STANDARD,
+            tidy: None,
+            annotate: false,
         };
         let input = &[0u8, 0u8, 0u8];
         let writer = BrainfuckWriter::with_options(&*input, options);
@@ -213,4 +681,139 @@ mod tests {
         assert_eq!(output, "...");
         assert_eq!(output.matches('.').count(), 3);
     }
+
+    #[test]
+    fn annotate_comments_each_byte() {
+        let input = b"Hi+ \x80";
+        let plain = BrainfuckWriter::new(input).generate().unwrap();
+        let options = WriterOptions { annotate: true, ..WriterOptions::default() };
+        let annotated = BrainfuckWriter::with_options(input, options).generate().unwrap();
+
+        let lines: Vec<&str> = annotated.lines().collect();
+        assert_eq!(lines.len(), input.len());
+        assert!(lines[0].ends_with("'H' 0x48 loop 8x9"), "{}", lines[0]);
+        assert!(lines[1].ends_with("'i' 0x69 delta up 33"), "{}", lines[1]);
+        assert!(lines[2].contains("plus sign 0x2B"), "{}", lines[2]);
+        assert!(lines[3].contains("space 0x20"), "{}", lines[3]);
+        assert!(lines[4].contains("byte 0x80"), "{}", lines[4]);
+
+        // Comments add no commands, so the program is unchanged
+        assert_eq!(crate::bf_only(&annotated), plain);
+    }
+
+    /// Run generated code on a strict interpreter: no wrapping (unless `wrap` is set),
+    /// `bits`-wide cells, `cells` cells, and an optional junk starting value in every cell.
+    fn run_strict(code: &str, bits: u32, wrap: bool, cells: usize, start: i64) -> Vec<u8> {
+        let code: Vec<char> = code.chars().collect();
+        let modulus = 1i64 << bits;
+        let mut tape = vec![start; cells];
+        let (mut ptr, mut ip, mut out) = (0usize, 0usize, Vec::new());
+        while ip < code.len() {
+            match code[ip] {
+                '>' => { ptr += 1; assert!(ptr < cells, "pointer past {cells} cells"); }
+                '<' => ptr = ptr.checked_sub(1).expect("pointer below 0"),
+                '+' | '-' => {
+                    let v = tape[ptr] + if code[ip] == '+' { 1 } else { -1 };
+                    assert!(wrap || (0..modulus).contains(&v), "cell left 0..{modulus}: {v}");
+                    tape[ptr] = v.rem_euclid(modulus);
+                }
+                '.' => out.push(tape[ptr] as u8),
+                '[' if tape[ptr] == 0 => {
+                    let mut depth = 0;
+                    loop {
+                        match code[ip] { '[' => depth += 1, ']' => depth -= 1, _ => {} }
+                        if depth == 0 { break; }
+                        ip += 1;
+                    }
+                }
+                ']' if tape[ptr] != 0 => {
+                    let mut depth = 0;
+                    loop {
+                        match code[ip] { ']' => depth += 1, '[' => depth -= 1, _ => {} }
+                        if depth == 0 { break; }
+                        ip -= 1;
+                    }
+                }
+                _ => {}
+            }
+            ip += 1;
+        }
+        out
+    }
+
+    #[test]
+    fn targets_limit_constructions() {
+        let input: Vec<u8> = vec![255, 0, 200, 1, 254, 128, 72, 105];
+        let generate = |target: WriterTarget| {
+            let options = WriterOptions { target, ..WriterOptions::default() };
+            BrainfuckWriter::with_options(&input, options).generate().unwrap()
+        };
+
+        let no_wrap = generate(WriterTarget { wrapping: false, ..WriterTarget::STANDARD });
+        assert_eq!(run_strict(&no_wrap, 8, false, 2, 0), input);
+
+        let wide = generate(WriterTarget { cell_width: CellWidth::Bits16, ..WriterTarget::STANDARD });
+        assert_eq!(run_strict(&wide, 16, false, 2, 0), input);
+
+        let one_cell = generate(WriterTarget { max_cells: 1, ..WriterTarget::STANDARD });
+        assert!(!one_cell.contains('>'));
+        assert_eq!(run_strict(&one_cell, 8, true, 1, 0), input);
+
+        let dirty = generate(WriterTarget { zeroed_start: false, ..WriterTarget::STANDARD });
+        assert!(dirty.starts_with("[-]"));
+        assert_eq!(run_strict(&dirty, 8, true, 2, 77), input);
+
+        let none = WriterOptions { target: WriterTarget { max_cells: 0, ..WriterTarget::STANDARD }, ..WriterOptions::default() };
+        assert!(matches!(BrainfuckWriter::with_options(&input, none).generate(), Err(BrainfuckWriterError::TargetTooSmall)));
+    }
+
+    fn run_with_input(code: &str, input: &[u8]) -> Vec<u8> {
+        use crate::BrainfuckReader;
+        use std::collections::VecDeque;
+        use std::sync::{Arc, Mutex};
+
+        let pending = Arc::new(Mutex::new(input.iter().copied().collect::<VecDeque<u8>>()));
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut bf = BrainfuckReader::new(code.to_string());
+        bf.set_input_provider(move || pending.lock().unwrap().pop_front());
+        let sink = output.clone();
+        bf.set_output_sink(move |bytes: &[u8]| sink.lock().unwrap().extend_from_slice(bytes));
+        bf.run().expect("template should run");
+        output.lock().unwrap().clone()
+    }
+
+    #[test]
+    fn template_names_roundtrip() {
+        for t in WriterTemplate::ALL {
+            assert_eq!(WriterTemplate::from_name(t.name()), Some(t));
+        }
+        assert_eq!(WriterTemplate::from_name("ROT13"), Some(WriterTemplate::Rot13));
+        assert_eq!(WriterTemplate::from_name("nope"), None);
+    }
+
+    #[test]
+    fn case_and_rot13_templates() {
+        let input = b"Hello, World! az AZ mn MN {}@[`";
+        assert_eq!(run_with_input(&WriterTemplate::Cat.generate(), input), input.to_vec());
+        assert_eq!(run_with_input(&WriterTemplate::Upper.generate(), input), b"HELLO, WORLD! AZ AZ MN MN {}@[`".to_vec());
+        assert_eq!(run_with_input(&WriterTemplate::Lower.generate(), input), b"hello, world! az az mn mn {}@[`".to_vec());
+        assert_eq!(run_with_input(&WriterTemplate::Rot13.generate(), input), b"Uryyb, Jbeyq! nm NM za ZA {}@[`".to_vec());
+    }
+
+    #[test]
+    fn reverse_template() {
+        assert_eq!(run_with_input(&WriterTemplate::Reverse.generate(), b"abc\n"), b"\ncba".to_vec());
+        assert_eq!(run_with_input(&WriterTemplate::Reverse.generate(), b""), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn counting_templates() {
+        let lines = WriterTemplate::Lines.generate();
+        assert_eq!(run_with_input(&lines, b""), b"0\n".to_vec());
+        assert_eq!(run_with_input(&lines, b"a\nb\nc"), b"2\n".to_vec());
+        assert_eq!(run_with_input(&lines, "\n".repeat(1234).as_bytes()), b"1234\n".to_vec());
+
+        let decimal = WriterTemplate::Decimal.generate();
+        assert_eq!(run_with_input(&decimal, &[1, 9, 10, 99, 100, 255]), b"1\n9\n10\n99\n100\n255\n".to_vec());
+    }
 }
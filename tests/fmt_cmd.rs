@@ -0,0 +1,45 @@
+// `bf fmt` re-lays out Brainfuck source and `--check` reports unformatted files.
+use predicates::prelude::*;
+use std::fs;
+
+#[test]
+fn fmt_indents_loops() {
+    let mut cmd = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    cmd.args(["fmt", "++[>++++++++<-] add"])
+        .assert()
+        .success()
+        .stdout("++\n[\n  >++++++++ <-\n] add\n");
+}
+
+#[test]
+fn fmt_check_and_write() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let path = dir.path().join("prog.bf");
+    fs::write(&path, "++[>++++++++<-]").expect("write");
+    let path_str = path.to_str().unwrap();
+
+    let mut check = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    check.args(["fmt", "--check", "--file", path_str])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("is not formatted"));
+
+    let mut write = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    write.args(["fmt", "--write", "--file", path_str]).assert().success();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "++\n[\n  >++++++++ <-\n]\n");
+
+    let mut recheck = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    recheck.args(["fmt", "--check", "--file", path_str]).assert().success();
+}
+
+#[test]
+fn fmt_check_accepts_formatted_positional_code() {
+    let mut check = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    check.args(["fmt", "--check", "+++[-] >."]).assert().success();
+
+    let mut unformatted = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    unformatted.args(["fmt", "--check", "+++[-]>."])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("<code> is not formatted"));
+}
@@ -0,0 +1,30 @@
+// `bf golf` prints a shorter equivalent program and can verify it against the original.
+use predicates::prelude::*;
+
+#[test]
+fn golf_strips_comments_and_dead_loops() {
+    let mut cmd = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    cmd.args(["golf", "[a comment loop.] ++ +- print: ."])
+        .assert()
+        .success()
+        .stdout("++.\n");
+}
+
+#[test]
+fn golf_verify_reports_matching_samples() {
+    let mut cmd = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    cmd.args(["golf", "--verify", "--input", "abc", ",[.,] echo"])
+        .assert()
+        .success()
+        .stdout(",[.,]\n")
+        .stderr(predicate::str::contains("verified: 1 of 1 samples match"));
+}
+
+#[test]
+fn golf_reports_unmatched_brackets() {
+    let mut cmd = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    cmd.args(["golf", "+]"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unmatched bracket"));
+}
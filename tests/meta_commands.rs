@@ -31,3 +31,82 @@ fn meta_reset_clears_current_buffer_but_not_history() {
         .stderr(predicate::str::contains(""))
         .stdout(predicate::str::contains("+").or(predicate::str::contains("")));
 }
+
+#[test]
+fn settings_flags_and_set_show_in_settings() {
+    let mut cmd = Command::cargo_bin("bf").unwrap();
+    cmd.args(["repl", "--bare", "--timeout", "5000", "--eof", "max"])
+        .write_stdin(":set memory 64\n:show settings\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("timeout    5000 ms"))
+        .stderr(predicate::str::contains("memory     64 cells"))
+        .stderr(predicate::str::contains("eof        max"));
+}
+
+#[test]
+fn invalid_setting_flag_is_a_usage_error() {
+    let mut cmd = Command::cargo_bin("bf").unwrap();
+    cmd.args(["repl", "--bare", "--eof", "sometimes"])
+        .write_stdin("+.")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid eof behavior"));
+}
+
+#[test]
+fn queued_input_is_read_by_comma() {
+    let mut cmd = Command::cargo_bin("bf").unwrap();
+    cmd.args(["repl", "--bare"])
+        .write_stdin(":input \"hi\"\n,.,.,.\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("hi\0"));
+}
+
+#[test]
+fn recorded_transcript_replays_with_script() {
+    let dir = tempfile::tempdir().unwrap();
+    let transcript = dir.path().join("session.bft");
+    let session = format!(
+        ":record {}\n:input \"AB\"\n,.,.\n:#\n++++++++[>++++++++<-]>+.\n:stop-record\n",
+        transcript.display()
+    );
+    let recorded = Command::cargo_bin("bf")
+        .unwrap()
+        .args(["repl", "--bare"])
+        .write_stdin(session)
+        .output()
+        .unwrap();
+    assert!(recorded.status.success());
+    assert_eq!(recorded.stdout, b"AB\nA\n");
+
+    let mut replay = Command::cargo_bin("bf").unwrap();
+    replay.args(["repl", "--script"])
+        .arg(&transcript)
+        .write_stdin("")
+        .assert()
+        .success()
+        .stdout(predicate::eq(&b"AB\nA\n"[..]));
+
+    // --help does not run the script
+    let mut help = Command::cargo_bin("bf").unwrap();
+    help.args(["repl", "--help", "--script"])
+        .arg(&transcript)
+        .write_stdin("")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("--help cannot be used with --script"));
+}
+
+#[test]
+fn repl_help_lists_every_meta_command() {
+    let mut cmd = Command::cargo_bin("bf").unwrap();
+    cmd.args(["repl", "--help"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(":input-file <path>"))
+        .stderr(predicate::str::contains(":stop-record"))
+        .stderr(predicate::str::contains(":dump [-n|--stderr]"));
+}
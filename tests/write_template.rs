@@ -0,0 +1,42 @@
+// Template programs from `bf write --template` transform stdin when run with `bf read`.
+use predicates::prelude::*;
+
+fn generate(name: &str) -> String {
+    let mut cmd = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    let assert = cmd.args(["write", "--template", name]).assert().success();
+    String::from_utf8(assert.get_output().stdout.clone())
+        .expect("utf8")
+        .trim_end()
+        .to_string()
+}
+
+#[test]
+fn upper_template_roundtrip() {
+    let code = generate("upper");
+    let mut run = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    run.args(["read", &code])
+        .write_stdin("Hello, bf!")
+        .assert()
+        .success()
+        .stdout("HELLO, BF!\n");
+}
+
+#[test]
+fn lines_template_roundtrip() {
+    let code = generate("lines");
+    let mut run = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    run.args(["read", &code])
+        .write_stdin("one\ntwo\nthree\n")
+        .assert()
+        .success()
+        .stdout("3\n\n");
+}
+
+#[test]
+fn unknown_template_is_an_error() {
+    let mut cmd = assert_cmd::Command::cargo_bin("bf").expect("bf binary");
+    cmd.args(["write", "--template", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown template").and(predicate::str::contains("rot13")));
+}